@@ -0,0 +1,49 @@
+use std::f64::consts::PI;
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use memegeom::geom::contains::poly_contains_pt;
+use memegeom::primitive::polygon::Poly;
+use memegeom::primitive::{poly, pt};
+
+fn regular(n: usize) -> Poly {
+    let pts: Vec<_> = (0..n)
+        .map(|i| {
+            let a = 2.0 * PI * i as f64 / n as f64;
+            pt(a.cos(), a.sin())
+        })
+        .collect();
+    poly(&pts)
+}
+
+fn star(n: usize) -> Poly {
+    let pts: Vec<_> = (0..2 * n)
+        .map(|i| {
+            let a = PI * i as f64 / n as f64;
+            let r = if i % 2 == 0 { 1.0 } else { 0.5 };
+            pt(r * a.cos(), r * a.sin())
+        })
+        .collect();
+    poly(&pts)
+}
+
+fn bench_poly_contains_pt(c: &mut Criterion) {
+    let queries: Vec<_> = (0..64)
+        .map(|i| {
+            let a = 0.7 * f64::from(i);
+            let r = f64::from(i % 8) / 6.0;
+            pt(r * a.cos(), r * a.sin())
+        })
+        .collect();
+    for (name, p) in [("convex8", regular(8)), ("convex64", regular(64)), ("star32", star(32))] {
+        c.bench_function(&format!("{name}/winding"), |b| {
+            b.iter(|| queries.iter().filter(|q| poly_contains_pt(&p, black_box(q))).count());
+        });
+        c.bench_function(&format!("{name}/tris"), |b| {
+            b.iter(|| queries.iter().filter(|q| p.contains_pt_via_tris(black_box(q))).count());
+        });
+    }
+}
+
+criterion_group!(benches, bench_poly_contains_pt);
+criterion_main!(benches);
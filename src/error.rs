@@ -0,0 +1,10 @@
+use derive_more::Display;
+
+#[must_use]
+#[derive(Debug, Display, derive_more::Error, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[display("shape is out of bounds")]
+    OutOfBounds,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
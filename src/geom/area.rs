@@ -0,0 +1,82 @@
+use crate::geom::clip::clip_pts_left_of;
+use crate::primitive::line;
+use crate::primitive::point::Pt;
+use crate::primitive::polygon::edges;
+use crate::primitive::shape::Shape;
+
+// Area of the closed polyline |pts|, via the shoelace formula. Positive for
+// CCW polylines.
+#[must_use]
+pub fn pts_signed_area(pts: &[Pt]) -> f64 {
+    edges(pts).map(|[a, b]| a.cross(*b)).sum::<f64>() / 2.0
+}
+
+// Returns the CCW vertices of |s| if it is a convex polygonal shape.
+fn convex_pts(s: &Shape) -> Option<Vec<Pt>> {
+    match s {
+        Shape::Polygon(s) if s.is_convex() => Some(s.pts().to_vec()),
+        Shape::Rect(s) if s.is_empty() => Some(vec![]),
+        Shape::Rect(s) => Some(s.pts().to_vec()),
+        Shape::Tri(s) => Some(s.pts().to_vec()),
+        _ => None,
+    }
+}
+
+// Area of the intersection of |a| and |b|. Returns None unless both are
+// convex polygonal shapes, i.e. rects, triangles, or convex polygons. Curved
+// shapes such as circles and capsules also return None, since the result
+// would depend on a tolerance. To approximate them, convert to a polygon with
+// Shape::to_polyline at a suitable tolerance first.
+#[must_use]
+pub fn intersection_area(a: &Shape, b: &Shape) -> Option<f64> {
+    let a = convex_pts(a)?;
+    let b = convex_pts(b)?;
+    let clipped = edges(&b).fold(a, |pts, [&p0, &p1]| clip_pts_left_of(&pts, &line(p0, p1)));
+    Some(pts_signed_area(&clipped).max(0.0))
+}
+
+// Area covered by exactly one of |a| and |b|, useful as an error metric
+// between a shape and a reference. Returns None in the same cases as
+// intersection_area.
+#[must_use]
+pub fn symmetric_difference_area(a: &Shape, b: &Shape) -> Option<f64> {
+    let inter = intersection_area(a, b)?;
+    let area = |s: &Shape| pts_signed_area(&convex_pts(s).unwrap_or_default());
+    Some((area(a) + area(b) - 2.0 * inter).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::math::EP;
+    use crate::primitive::{ShapeOps, circ, poly, pt, rt, tri};
+
+    #[test]
+    fn test_symmetric_difference_area() {
+        let a = rt(0.0, 0.0, 2.0, 2.0).shape();
+        assert_relative_eq!(symmetric_difference_area(&a, &a).unwrap(), 0.0);
+        let b = rt(5.0, 0.0, 6.0, 1.0).shape();
+        assert_relative_eq!(symmetric_difference_area(&a, &b).unwrap(), 5.0);
+        let b = rt(1.0, 0.0, 3.0, 2.0).shape();
+        assert_relative_eq!(intersection_area(&a, &b).unwrap(), 2.0);
+        assert_relative_eq!(symmetric_difference_area(&a, &b).unwrap(), 4.0);
+
+        let t = tri(pt(0.0, 0.0), pt(2.0, 0.0), pt(0.0, 2.0)).shape();
+        assert_relative_eq!(symmetric_difference_area(&a, &t).unwrap(), 2.0, epsilon = EP);
+        let p = poly(&[pt(1.0, -1.0), pt(3.0, 1.0), pt(1.0, 3.0), pt(-1.0, 1.0)]).shape();
+        assert_relative_eq!(intersection_area(&a, &p).unwrap(), 4.0, epsilon = EP);
+
+        let concave = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(1.0, 1.0), pt(0.0, 4.0)]).shape();
+        assert_eq!(symmetric_difference_area(&a, &concave), None);
+        assert_eq!(symmetric_difference_area(&a, &circ(pt(0.0, 0.0), 1.0).shape()), None);
+        // Circles can be approximated as polygons first.
+        let c = circ(pt(0.0, 0.0), 1.0).shape().to_polyline(1e-4);
+        let c = poly(&c[0]).shape();
+        assert_relative_eq!(intersection_area(&a, &c).unwrap(), PI / 4.0, epsilon = 1e-3);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::geom::math::{cross_at, orientation};
+use crate::primitive::line_shape::Line;
+use crate::primitive::point::Pt;
+use crate::primitive::polygon::{Poly, edges};
+use crate::primitive::rect::Rt;
+use crate::primitive::{line, poly};
+
+// Clips the closed polyline |pts| to the half-plane left of |l|, keeping
+// points on |l|. This is one step of Sutherland-Hodgman clipping, so it is
+// only exact for convex inputs.
+#[must_use]
+pub fn clip_pts_left_of(pts: &[Pt], l: &Line) -> Vec<Pt> {
+    let mut out = Vec::new();
+    for [&p0, &p1] in edges(pts) {
+        let o0 = orientation(l, p0);
+        let o1 = orientation(l, p1);
+        if o0 >= 0 {
+            out.push(p0);
+        }
+        // Only add a crossing point if the edge strictly crosses |l|.
+        if o0 * o1 < 0 {
+            let d0 = cross_at(l.st(), l.en(), p0);
+            let d1 = cross_at(l.st(), l.en(), p1);
+            out.push(p0 + (p1 - p0) * (d0 / (d0 - d1)));
+        }
+    }
+    out
+}
+
+// Clips the CCW convex polyline |pts| to |r|. Returns None if what remains has
+// no area.
+pub(crate) fn clip_convex_pts_rt(pts: &[Pt], r: &Rt) -> Option<Poly> {
+    if r.is_empty() {
+        return None;
+    }
+    let pts = r.segs().iter().fold(pts.to_vec(), |pts, s| clip_pts_left_of(&pts, &s.line()));
+    let p = poly(&pts);
+    (p.pts().len() >= 3).then_some(p)
+}
+
+// Returns the intersection of the convex polygon |p| with |r|, or None if it
+// has no area, e.g. if they are disjoint or only touch. Panics if |p| is not
+// convex; use Poly::clip_to_rect for general polygons.
+#[must_use]
+pub fn convex_poly_clip_rt(p: &Poly, r: &Rt) -> Option<Poly> {
+    assert!(p.is_convex(), "polygon must be convex");
+    clip_convex_pts_rt(p.pts(), r)
+}
+
+// Splits the convex polygon |p| by |l| into the pieces left and right of it.
+// Either piece is None if it has no area, e.g. if |l| misses |p|. Panics if
+// |p| is not convex.
+#[must_use]
+pub fn convex_poly_split(p: &Poly, l: &Line) -> (Option<Poly>, Option<Poly>) {
+    assert!(p.is_convex(), "polygon must be convex");
+    let piece = |l: &Line| {
+        let p = poly(&clip_pts_left_of(p.pts(), l));
+        (p.pts().len() >= 3).then_some(p)
+    };
+    (piece(l), piece(&line(l.en(), l.st())))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::area::pts_signed_area;
+    use crate::primitive::{ShapeOps, pt, rt, square};
+
+    #[test]
+    fn test_convex_poly_clip_rt() {
+        let r = rt(0.0, 0.0, 2.0, 2.0);
+        // Straddling the top right corner.
+        let t = poly(&[pt(1.0, 1.0), pt(3.0, 1.0), pt(1.0, 3.0)]);
+        let clipped = convex_poly_clip_rt(&t, &r).unwrap();
+        assert_eq!(clipped.bounds(), rt(1.0, 1.0, 2.0, 2.0));
+        assert_relative_eq!(pts_signed_area(clipped.pts()), 1.0);
+
+        let inside = poly(&[pt(0.5, 0.5), pt(1.5, 0.5), pt(0.5, 1.5)]);
+        assert_eq!(convex_poly_clip_rt(&inside, &r).unwrap().pts(), inside.pts());
+
+        // Disjoint, or only touching.
+        assert!(
+            convex_poly_clip_rt(&poly(&[pt(3.0, 3.0), pt(4.0, 3.0), pt(3.0, 4.0)]), &r).is_none()
+        );
+        assert!(
+            convex_poly_clip_rt(&poly(&[pt(2.0, 0.0), pt(3.0, 0.0), pt(2.0, 1.0)]), &r).is_none()
+        );
+    }
+
+    #[test]
+    fn test_convex_poly_split() {
+        let square = square(0.0, 0.0, 2.0);
+        // Upwards through the centre, so the left piece is at smaller x.
+        let (l, r) = convex_poly_split(&square, &line(pt(1.0, -5.0), pt(1.0, 5.0)));
+        let (l, r) = (l.unwrap(), r.unwrap());
+        assert_eq!(l.bounds(), rt(0.0, 0.0, 1.0, 2.0));
+        assert_eq!(r.bounds(), rt(1.0, 0.0, 2.0, 2.0));
+        assert_relative_eq!(pts_signed_area(l.pts()), 2.0);
+        assert_relative_eq!(pts_signed_area(r.pts()), 2.0);
+
+        // Missing, or only touching an edge.
+        let (l, r) = convex_poly_split(&square, &line(pt(3.0, 0.0), pt(3.0, 1.0)));
+        assert_eq!(l.unwrap().pts(), square.pts());
+        assert!(r.is_none());
+        let (l, r) = convex_poly_split(&square, &line(pt(0.0, 0.0), pt(1.0, 0.0)));
+        assert_eq!(l.unwrap().pts(), square.pts());
+        assert!(r.is_none());
+
+        // Diagonal split into two triangles.
+        let (l, r) = convex_poly_split(&square, &line(pt(0.0, 0.0), pt(2.0, 2.0)));
+        assert_eq!(l.unwrap().pts().len(), 3);
+        assert_eq!(r.unwrap().pts().len(), 3);
+    }
+}
@@ -1,14 +1,15 @@
-use crate::geom::distance::{polyline_pt_dist, pt_seg_dist};
-use crate::geom::math::{ge, is_left_of, is_right_of, le, lt, orientation};
+use crate::geom::distance::{poly_outline_pt_dist, polyline_pt_dist, pt_seg_dist};
+use crate::geom::intersects::seg_crosses_seg;
+use crate::geom::math::{EP, eq, f64_cmp, ge, gt, is_left_of, is_right_of, le, lt, orientation};
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
 use crate::primitive::path_shape::Path;
 use crate::primitive::point::Pt;
-use crate::primitive::polygon::Poly;
+use crate::primitive::polygon::{Poly, edges};
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
 use crate::primitive::triangle::Tri;
-use crate::primitive::{ShapeOps, line};
+use crate::primitive::{ShapeOps, line, poly, seg};
 
 #[must_use]
 pub fn cap_contains_pt(a: &Capsule, b: &Pt) -> bool {
@@ -17,7 +18,25 @@ pub fn cap_contains_pt(a: &Capsule, b: &Pt) -> bool {
         return false;
     }
 
-    le(pt_seg_dist(b, &a.seg()), a.r())
+    pt_seg_dist(b, &a.seg()).is_some_and(|d| le(d, a.r()))
+}
+
+#[must_use]
+pub fn cap_contains_cap(a: &Capsule, b: &Capsule) -> bool {
+    // Distance to the spine of |a| is convex along the spine of |b|, so it is
+    // largest at the end caps of |b|.
+    cap_contains_circ(a, &b.st_cap()) && cap_contains_circ(a, &b.en_cap())
+}
+
+#[must_use]
+pub fn cap_contains_circ(a: &Capsule, b: &Circle) -> bool {
+    pt_seg_dist(&b.p(), &a.seg()).is_some_and(|d| le(d + b.r(), a.r()))
+}
+
+#[must_use]
+pub fn cap_contains_seg(a: &Capsule, b: &Segment) -> bool {
+    // Capsules are convex, so it is enough to check the endpoints.
+    cap_contains_pt(a, &b.st()) && cap_contains_pt(a, &b.en())
 }
 
 #[must_use]
@@ -49,6 +68,16 @@ pub fn circ_contains_pt(a: &Circle, b: &Pt) -> bool {
     le(a.p().dist(*b), a.r())
 }
 
+#[must_use]
+pub fn path_contains_pt(a: &Path, b: &Pt) -> bool {
+    for cap in a.caps() {
+        if cap_contains_pt(&cap, b) {
+            return true;
+        }
+    }
+    false
+}
+
 #[must_use]
 pub fn path_contains_rt(a: &Path, b: &Rt) -> bool {
     // Bounding box check.
@@ -72,8 +101,89 @@ pub fn path_contains_rt(a: &Path, b: &Rt) -> bool {
 }
 
 #[must_use]
-pub fn path_contains_seg(_a: &Path, _b: &Segment) -> bool {
-    todo!()
+pub fn path_contains_cap(a: &Path, b: &Capsule) -> bool {
+    // Like path_contains_rt, this misses capsules only covered by the union
+    // of several capsules, e.g. one going around a corner of the path.
+    a.caps().any(|cap| cap_contains_cap(&cap, b))
+}
+
+#[must_use]
+pub fn path_contains_circ(a: &Path, b: &Circle) -> bool {
+    // Like path_contains_rt, this misses circles only covered by the union of
+    // several capsules.
+    a.caps().any(|cap| cap_contains_circ(&cap, b))
+}
+
+#[must_use]
+pub fn path_contains_seg(a: &Path, b: &Segment) -> bool {
+    // Bounding box check.
+    if !a.bounds().contains_rt(&b.bounds()) {
+        return false;
+    }
+
+    // Each capsule covers a single interval of |b|, since capsules are convex.
+    // Check that the union of the intervals covers all of |b|.
+    let mut ranges: Vec<_> = a.caps().filter_map(|cap| cap_seg_range(&cap, b)).collect();
+    ranges.sort_unstable_by(|x, y| f64_cmp(&x.0, &y.0));
+    let mut covered = 0.0;
+    for (lo, hi) in ranges {
+        if gt(lo, covered) {
+            return false;
+        }
+        covered = hi.max(covered);
+    }
+    ge(covered, 1.0)
+}
+
+// Returns the range of t in [0, 1] for which b.st() + t * b.dir() is inside
+// |a|, or None if |b| misses |a|. |a| is the union of its end circles and the
+// rectangle between them, so take the hull of the ranges for each of those.
+fn cap_seg_range(a: &Capsule, b: &Segment) -> Option<(f64, f64)> {
+    let r = a.r() + EP;
+    let d = b.dir();
+    let mut ranges = vec![circ_seg_range(a.st(), r, b), circ_seg_range(a.en(), r, b)];
+    if !a.dir().is_zero() {
+        let u = a.dir().norm();
+        let n = a.dir().perp();
+        let mut range = (f64::NEG_INFINITY, f64::INFINITY);
+        for (axis, min, max) in [(u, 0.0, a.dir().mag()), (n, -r, r)] {
+            // Solve min <= g0 + g1 * t <= max.
+            let g0 = (b.st() - a.st()).dot(axis);
+            let g1 = d.dot(axis);
+            if g1 == 0.0 {
+                if g0 < min || g0 > max {
+                    range = (f64::INFINITY, f64::NEG_INFINITY);
+                }
+            } else {
+                let (t0, t1) = ((min - g0) / g1, (max - g0) / g1);
+                range = (range.0.max(t0.min(t1)), range.1.min(t0.max(t1)));
+            }
+        }
+        ranges.push((range.0 <= range.1).then_some(range));
+    }
+    let (lo, hi) = ranges
+        .into_iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |acc, v| (acc.0.min(v.0), acc.1.max(v.1)));
+    let (lo, hi) = (lo.max(0.0), hi.min(1.0));
+    (lo <= hi).then_some((lo, hi))
+}
+
+// Returns the range of t for which b.st() + t * b.dir() is within |r| of |p|.
+fn circ_seg_range(p: Pt, r: f64, b: &Segment) -> Option<(f64, f64)> {
+    let d = b.dir();
+    let f = b.st() - p;
+    let c = f.mag2() - r * r;
+    if d.is_zero() {
+        return (c <= 0.0).then_some((f64::NEG_INFINITY, f64::INFINITY));
+    }
+    let (a, h) = (d.mag2(), f.dot(d));
+    let disc = h * h - a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sq = disc.sqrt();
+    Some(((-h - sq) / a, (-h + sq) / a))
 }
 
 #[must_use]
@@ -97,7 +207,8 @@ pub fn poly_contains_cap(a: &Poly, b: &Capsule) -> bool {
     if !poly_contains_seg(a, &b.right_seg()) {
         return false;
     }
-    true
+    // The outline of |b| is in |a|, but |b| may still surround a hole.
+    poly_holes_outside(a, |p| cap_contains_pt(b, p))
 }
 
 #[must_use]
@@ -106,7 +217,7 @@ pub fn poly_contains_circ(a: &Poly, b: &Circle) -> bool {
     if !poly_contains_pt(a, &b.p()) {
         return false;
     }
-    ge(polyline_pt_dist(a.pts(), &b.p()), b.r())
+    poly_outline_pt_dist(a, &b.p()).is_some_and(|d| ge(d, b.r()))
 }
 
 #[must_use]
@@ -125,31 +236,50 @@ pub fn poly_contains_path(a: &Poly, b: &Path) -> bool {
 }
 
 #[must_use]
-pub fn poly_contains_pt(a: &Poly, b: &Pt) -> bool {
+pub fn poly_contains_poly(a: &Poly, b: &Poly) -> bool {
     // Bounding box check.
-    if !a.bounds().contains(*b) {
+    if !a.bounds().contains_rt(&b.bounds()) {
         return false;
     }
 
-    // Winding number test. Look at horizontal line at b.y and count crossings
-    // of edges from |a|. Treats points on the boundary of the polygon as
-    // contained.
-    let mut winding = 0;
-    for [&p0, &p1] in a.edges() {
-        // Treat points at b.y as slightly above it.
-        if ge(p0.y, b.y) {
-            // Downward crossing edge with |b| to the right of it decreases
-            // winding number.
-            if lt(p1.y, b.y) && is_right_of(&line(p0, p1), *b) {
-                winding -= 1;
+    // Check point containment of |b| in |a|.
+    for p in b.pts() {
+        if !poly_contains_pt(a, p) {
+            return false;
+        }
+    }
+    // Check segment containment of |b| in |a| if |a| is non-convex.
+    if !a.is_convex() {
+        for [&p0, &p1] in b.edges() {
+            if !poly_contains_seg(a, &seg(p0, p1)) {
+                return false;
             }
-        } else if ge(p1.y, b.y) && is_left_of(&line(p0, p1), *b) {
-            // Upward crossing edge with |b| to the left of it increases
-            // winding number.
-            winding += 1;
         }
     }
-    winding != 0
+    // Holes of |a| must not overlap |b|. If no edges cross, each hole is
+    // either entirely inside or entirely outside |b|.
+    for h in a.holes() {
+        for [&h0, &h1] in edges(h) {
+            if b.edges().any(|[&p0, &p1]| seg_crosses_seg(&seg(h0, h1), &seg(p0, p1))) {
+                return false;
+            }
+        }
+        if poly(h).interior_point().is_some_and(|p| poly_contains_pt(b, &p)) {
+            return false;
+        }
+    }
+    true
+}
+
+#[must_use]
+pub fn poly_contains_pt(a: &Poly, b: &Pt) -> bool {
+    // Bounding box check.
+    if !a.bounds().contains(*b) {
+        return false;
+    }
+
+    // Points inside a hole are not contained.
+    poly_winding_number(a, b) != 0 && a.holes().iter().all(|h| pts_winding_number(h, b) == 0)
 }
 
 #[must_use]
@@ -173,7 +303,15 @@ pub fn poly_contains_rt(a: &Poly, b: &Rt) -> bool {
             }
         }
     }
-    true
+    // The outline of |b| is in |a|, but |b| may still surround a hole.
+    poly_holes_outside(a, |p| b.contains(*p))
+}
+
+// Whether no hole of |a| is inside a shape whose outline is contained in |a|,
+// given its point containment test |contains|. Such an outline cannot enter a
+// hole, so each hole is either entirely inside or entirely outside the shape.
+fn poly_holes_outside(a: &Poly, contains: impl Fn(&Pt) -> bool) -> bool {
+    a.holes().iter().all(|h| !poly(h).interior_point().is_some_and(|p| contains(&p)))
 }
 
 #[must_use]
@@ -193,18 +331,72 @@ pub fn poly_contains_seg(a: &Poly, b: &Segment) -> bool {
         return true;
     }
 
-    // Check that |b| does not cross any edge of |a|.
-    for [&p0, &p1] in a.edges() {
+    // Check that |b| does not cross any edge of |a|, and find where it touches
+    // the boundary of |a|, e.g. at reflex vertices.
+    let d = b.dir();
+    if d.is_zero() {
+        return true;
+    }
+    let mut ts = vec![0.0, 1.0];
+    for [&p0, &p1] in a.rings().flat_map(edges) {
         let p_st = orientation(&b.line(), p0);
         let p_en = orientation(&b.line(), p1);
         let b_st = orientation(&line(p0, p1), b.st());
         let b_en = orientation(&line(p0, p1), b.en());
         // Segments are crossing and no collinear points.
-        if p_st != p_en && b_st != b_en {
+        if p_st * p_en < 0 && b_st * b_en < 0 {
             return false;
         }
+        if b.contains(p0) {
+            ts.push(d.dot(p0 - b.st()) / d.mag2());
+        }
     }
-    true
+    // Between contacts |b| is either entirely inside or entirely outside |a|,
+    // so check the midpoint of each piece.
+    ts.sort_by(f64::total_cmp);
+    ts.windows(2)
+        .filter(|t| !eq(t[0], t[1]))
+        .all(|t| poly_contains_pt_closed(a, &(b.st() + d * ((t[0] + t[1]) / 2.0))))
+}
+
+// Like poly_contains_pt, but all points on the boundary are contained. Pieces
+// of a segment between contacts may run along an edge, and the winding number
+// only counts some boundary points (e.g. not those on bottom edges).
+fn poly_contains_pt_closed(a: &Poly, b: &Pt) -> bool {
+    let on_ring = |pts: &[Pt]| edges(pts).any(|[&p0, &p1]| seg(p0, p1).contains(*b));
+    if !on_ring(a.pts()) && pts_winding_number(a.pts(), b) == 0 {
+        return false;
+    }
+    !a.holes().iter().any(|h| !on_ring(h) && pts_winding_number(h, b) != 0)
+}
+
+// Returns the winding number of |a| around |b|. Useful for debugging
+// point-in-polygon classification, or for even-odd fill rules.
+#[must_use]
+pub fn poly_winding_number(a: &Poly, b: &Pt) -> i32 {
+    pts_winding_number(a.pts(), b)
+}
+
+fn pts_winding_number(a: &[Pt], b: &Pt) -> i32 {
+    // Winding number test. Look at horizontal line at b.y and count crossings
+    // of edges from |a|. The result for points on the boundary of the polygon
+    // depends on which edge they lie on.
+    let mut winding = 0;
+    for [&p0, &p1] in edges(a) {
+        // Treat points at b.y as slightly above it.
+        if ge(p0.y, b.y) {
+            // Downward crossing edge with |b| to the right of it decreases
+            // winding number.
+            if lt(p1.y, b.y) && is_right_of(&line(p0, p1), *b) {
+                winding -= 1;
+            }
+        } else if ge(p1.y, b.y) && is_left_of(&line(p0, p1), *b) {
+            // Upward crossing edge with |b| to the left of it increases
+            // winding number.
+            winding += 1;
+        }
+    }
+    winding
 }
 
 #[must_use]
@@ -299,6 +491,58 @@ pub fn rt_contains_tri(a: &Rt, b: &Tri) -> bool {
     true
 }
 
+#[must_use]
+pub fn tri_contains_cap(a: &Tri, b: &Capsule) -> bool {
+    // Triangles are convex, so it is enough to check the end caps.
+    tri_contains_circ(a, &b.st_cap()) && tri_contains_circ(a, &b.en_cap())
+}
+
+#[must_use]
+pub fn tri_contains_circ(a: &Tri, b: &Circle) -> bool {
+    // Test that the centre of the circle is in the triangle.
+    if !tri_contains_pt(a, &b.p()) {
+        return false;
+    }
+    polyline_pt_dist(a.pts(), &b.p()).is_some_and(|d| ge(d, b.r()))
+}
+
+#[must_use]
+pub fn tri_contains_path(a: &Tri, b: &Path) -> bool {
+    // Bounding box check.
+    if !a.bounds().contains_rt(&b.bounds()) {
+        return false;
+    }
+
+    for cap in b.caps() {
+        if !tri_contains_cap(a, &cap) {
+            return false;
+        }
+    }
+    true
+}
+
+#[must_use]
+pub fn tri_contains_poly(a: &Tri, b: &Poly) -> bool {
+    // Triangles are convex, so just check all points in |b| are in |a|.
+    b.pts().iter().all(|p| tri_contains_pt(a, p))
+}
+
+#[must_use]
+pub fn tri_contains_rt(a: &Tri, b: &Rt) -> bool {
+    !b.is_empty() && b.pts().iter().all(|p| tri_contains_pt(a, p))
+}
+
+#[must_use]
+pub fn tri_contains_seg(a: &Tri, b: &Segment) -> bool {
+    // Just need to check containment of both endpoints.
+    tri_contains_pt(a, &b.st()) && tri_contains_pt(a, &b.en())
+}
+
+#[must_use]
+pub fn tri_contains_tri(a: &Tri, b: &Tri) -> bool {
+    b.pts().iter().all(|p| tri_contains_pt(a, p))
+}
+
 #[must_use]
 pub fn tri_contains_pt(a: &Tri, b: &Pt) -> bool {
     let orientation0 = orientation(&line(a[0], a[1]), *b);
@@ -306,3 +550,218 @@ pub fn tri_contains_pt(a: &Tri, b: &Pt) -> bool {
     let orientation2 = orientation(&line(a[2], a[0]), *b);
     orientation0 == orientation1 && orientation1 == orientation2
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::primitive::{cap, circ, path, poly, pt, rt, seg, square, tri};
+
+    #[test]
+    fn test_cap_contains_circ_seg() {
+        let c = cap(pt(0.0, 0.0), pt(10.0, 0.0), 3.0);
+        assert!(cap_contains_circ(&c, &circ(pt(5.0, 1.0), 2.0)));
+        assert!(cap_contains_circ(&c, &circ(pt(-1.0, 0.0), 2.0)));
+        assert!(!cap_contains_circ(&c, &circ(pt(5.0, 1.5), 2.0)));
+        assert!(!cap_contains_circ(&c, &circ(pt(12.0, 0.0), 2.0)));
+
+        assert!(cap_contains_seg(&c, &seg(pt(-2.0, 0.0), pt(12.0, 2.0))));
+        assert!(!cap_contains_seg(&c, &seg(pt(-2.0, 0.0), pt(12.0, 3.0))));
+    }
+
+    #[test]
+    fn test_path_contains_pt_circ_cap() {
+        let p = path(&[pt(0.0, 0.0), pt(10.0, 0.0), pt(10.0, 10.0)], 1.0);
+        assert!(path_contains_pt(&p, &pt(10.5, 5.0)));
+        assert!(path_contains_pt(&p, &pt(11.0, 0.0)));
+        assert!(!path_contains_pt(&p, &pt(5.0, 1.5)));
+
+        assert!(path_contains_circ(&p, &circ(pt(5.0, 0.0), 1.0)));
+        assert!(!path_contains_circ(&p, &circ(pt(5.0, 0.5), 1.0)));
+
+        assert!(cap_contains_cap(
+            &cap(pt(0.0, 0.0), pt(10.0, 0.0), 1.0),
+            &cap(pt(1.0, 0.0), pt(9.0, 0.5), 0.5)
+        ));
+        assert!(!cap_contains_cap(
+            &cap(pt(0.0, 0.0), pt(10.0, 0.0), 1.0),
+            &cap(pt(1.0, 0.0), pt(9.0, 0.5), 0.6)
+        ));
+        assert!(path_contains_cap(&p, &cap(pt(10.0, 2.0), pt(10.0, 8.0), 0.5)));
+        assert!(!path_contains_cap(&p, &cap(pt(10.0, 2.0), pt(10.0, 8.0), 1.5)));
+        // Covered by the union of both capsules, but not by either alone.
+        assert!(!path_contains_cap(&p, &cap(pt(8.0, 0.0), pt(10.0, 2.0), 0.5)));
+        let p = p.shape();
+        assert!(p.contains_shape(&cap(pt(2.0, 0.0), pt(8.0, 0.0), 1.0).shape()));
+        assert!(p.contains_shape(&pt(10.0, 10.0).shape()));
+    }
+
+    #[test]
+    fn test_path_contains_circ_seg() {
+        let p = path(&[pt(0.0, 0.0), pt(10.0, 0.0), pt(10.0, 10.0)], 0.5);
+        assert!(path_contains_circ(&p, &circ(pt(10.0, 5.0), 0.5)));
+        assert!(!path_contains_circ(&p, &circ(pt(10.0, 5.0), 0.6)));
+
+        // Segment around the corner, covered by both capsules together.
+        assert!(path_contains_seg(&p, &seg(pt(9.6, -0.3), pt(10.3, 3.0))));
+        assert!(!cap_contains_seg(&p.caps().next().unwrap(), &seg(pt(9.6, -0.3), pt(10.3, 3.0))));
+        assert!(path_contains_seg(&p, &seg(pt(0.0, -0.5), pt(10.0, -0.5))));
+        // Thin path, segment pokes out of the side.
+        assert!(!path_contains_seg(&p, &seg(pt(5.0, 0.0), pt(6.0, 0.6))));
+        // Pokes out past the end.
+        assert!(!path_contains_seg(&p, &seg(pt(10.0, 9.0), pt(10.0, 10.6))));
+        // Cuts the inside corner between the capsules.
+        assert!(!path_contains_seg(&p, &seg(pt(8.0, 0.0), pt(10.0, 2.0))));
+    }
+
+    #[test]
+    fn test_poly_contains_poly() {
+        let a = square(0.0, 0.0, 4.0);
+        assert!(poly_contains_poly(&a, &square(1.0, 1.0, 2.0)));
+        assert!(poly_contains_poly(&a, &square(2.0, 2.0, 2.0)));
+        assert!(!poly_contains_poly(&a, &square(3.0, 3.0, 2.0)));
+        assert!(!poly_contains_poly(&square(1.0, 1.0, 2.0), &a));
+
+        // U shape: the notch is within the bounds but not the polygon.
+        let u = poly(&[
+            pt(0.0, 0.0),
+            pt(6.0, 0.0),
+            pt(6.0, 4.0),
+            pt(4.0, 4.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        assert!(poly_contains_poly(&u, &square(0.5, 0.5, 1.0)));
+        assert!(!poly_contains_poly(&u, &square(2.5, 2.5, 1.0)));
+        // All vertices are inside, but the top edge crosses the notch.
+        let bridge = poly(&[pt(1.0, 3.0), pt(1.0, 1.0), pt(5.0, 1.0), pt(5.0, 3.0)]);
+        assert!(!poly_contains_poly(&u, &bridge));
+
+        let ring = Poly::new_with_holes(
+            square(0.0, 0.0, 6.0).pts(),
+            &[square(2.0, 2.0, 2.0).pts().to_vec()],
+        );
+        assert!(poly_contains_poly(&ring, &square(0.5, 0.5, 1.0)));
+        // Around the hole.
+        assert!(!poly_contains_poly(&ring, &square(1.0, 1.0, 4.0)));
+        // Partly over the hole.
+        assert!(!poly_contains_poly(&ring, &square(1.0, 1.0, 2.0)));
+        // Touching the hole from outside.
+        assert!(poly_contains_poly(&ring, &square(0.5, 2.0, 1.5)));
+        assert!(ring.shape().contains_shape(&square(0.5, 0.5, 1.0).shape()));
+    }
+
+    #[test]
+    fn test_poly_holes() {
+        let ring = Poly::new_with_holes(
+            &rt(0.0, 0.0, 6.0, 6.0).pts(),
+            &[rt(2.0, 2.0, 4.0, 4.0).pts().to_vec()],
+        );
+        // Inside the hole.
+        let inner = circ(pt(3.0, 3.0), 0.5);
+        assert!(!poly_contains_circ(&ring, &inner));
+        assert!(!ring.clone().shape().intersects_shape(&inner.shape()));
+        assert!(!ring.clone().shape().intersects_shape(&rt(2.5, 2.5, 3.5, 3.5).shape()));
+        assert!(
+            !ring.clone().shape().intersects_shape(&cap(pt(2.5, 3.0), pt(3.5, 3.0), 0.4).shape())
+        );
+        assert!(!ring.clone().shape().intersects_shape(&seg(pt(2.5, 3.0), pt(3.5, 3.0)).shape()));
+        // Overlapping the hole.
+        assert!(!poly_contains_circ(&ring, &circ(pt(1.5, 3.0), 1.0)));
+        assert!(ring.clone().shape().intersects_shape(&circ(pt(1.5, 3.0), 1.0).shape()));
+        // Around the hole, with the outline inside the polygon.
+        assert!(!poly_contains_rt(&ring, &rt(1.0, 1.0, 5.0, 5.0)));
+        assert!(!poly_contains_cap(&ring, &cap(pt(1.0, 3.0), pt(5.0, 3.0), 2.0)));
+        assert!(!poly_contains_path(&ring, &path(&[pt(1.0, 3.0), pt(5.0, 3.0)], 2.0)));
+        // Beside the hole.
+        assert!(poly_contains_circ(&ring, &circ(pt(1.0, 3.0), 1.0)));
+        assert!(poly_contains_rt(&ring, &rt(0.5, 0.5, 5.5, 1.5)));
+        assert!(poly_contains_cap(&ring, &cap(pt(1.0, 1.0), pt(5.0, 1.0), 0.5)));
+    }
+
+    #[test]
+    fn test_poly_contains_across_notch() {
+        // A U shape with a notch from (2, 2) to (4, 6). Shapes passing across
+        // the notch only touch its reflex vertices rather than crossing edges.
+        let u = poly(&[
+            pt(0.0, 0.0),
+            pt(6.0, 0.0),
+            pt(6.0, 6.0),
+            pt(4.0, 6.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 6.0),
+            pt(0.0, 6.0),
+        ]);
+        assert!(!poly_contains_seg(&u, &seg(pt(1.0, 4.0), pt(5.0, 4.0))));
+        assert!(!poly_contains_seg(&u, &seg(pt(2.0, 4.0), pt(4.0, 4.0))));
+        assert!(!poly_contains_seg(&u, &seg(pt(1.0, 6.0), pt(5.0, 6.0))));
+        assert!(poly_contains_seg(&u, &seg(pt(1.0, 2.0), pt(5.0, 2.0))));
+        assert!(poly_contains_seg(&u, &seg(pt(1.0, 1.0), pt(5.0, 2.0))));
+        assert!(!poly_contains_seg(&u, &seg(pt(1.0, 1.0), pt(5.0, 3.0))));
+        // Through the reflex vertex at (2, 2), from one arm to the base.
+        assert!(poly_contains_seg(&u, &seg(pt(1.5, 3.0), pt(2.5, 1.0))));
+
+        let bridge = rt(1.0, 3.0, 5.0, 5.0);
+        assert!(!poly_contains_rt(&u, &bridge));
+        assert!(!poly_contains_rt(&u, &rt(2.0, 2.0, 4.0, 4.0)));
+        assert!(poly_contains_rt(&u, &rt(0.5, 0.5, 5.5, 2.0)));
+        assert!(poly_contains_rt(&u, &rt(0.5, 1.0, 1.5, 5.0)));
+        assert!(!poly_contains_poly(&u, &poly(&bridge.pts())));
+        assert!(poly_contains_poly(&u, &poly(&[pt(1.0, 1.0), pt(5.0, 1.0), pt(3.0, 1.5)])));
+
+        // Grid cells in the notch are not covered.
+        let rects = u.to_rects(2.0);
+        let area: f64 = rects.iter().map(Rt::area).sum();
+        assert!(eq(area, 28.0), "{rects:?}");
+        assert!(rects.iter().all(|r| poly_contains_rt(&u, &r.inset(2.0 * EP, 2.0 * EP))));
+    }
+
+    #[test]
+    fn test_tri_contains() {
+        let big = tri(pt(0.0, 0.0), pt(10.0, 0.0), pt(0.0, 10.0));
+        assert!(tri_contains_tri(&big, &tri(pt(1.0, 1.0), pt(3.0, 1.0), pt(1.0, 3.0))));
+        assert!(!tri_contains_tri(&big, &tri(pt(1.0, 1.0), pt(6.0, 5.0), pt(1.0, 3.0))));
+
+        assert!(tri_contains_seg(&big, &seg(pt(1.0, 1.0), pt(5.0, 4.0))));
+        assert!(!tri_contains_seg(&big, &seg(pt(1.0, 1.0), pt(6.0, 6.0))));
+
+        assert!(tri_contains_circ(&big, &circ(pt(2.0, 2.0), 1.0)));
+        // Pokes out of the hypotenuse, though the centre is inside.
+        assert!(!tri_contains_circ(&big, &circ(pt(4.0, 4.0), 1.5)));
+        assert!(!tri_contains_circ(&big, &circ(pt(1.0, 5.0), 1.5)));
+
+        assert!(tri_contains_cap(&big, &cap(pt(1.0, 1.0), pt(5.0, 1.0), 0.5)));
+        assert!(!tri_contains_cap(&big, &cap(pt(1.0, 1.0), pt(8.5, 1.0), 0.5)));
+        assert!(tri_contains_rt(&big, &rt(1.0, 1.0, 4.0, 4.0)));
+        assert!(!tri_contains_rt(&big, &rt(1.0, 1.0, 6.0, 6.0)));
+        let t = big.shape();
+        assert!(t.contains_shape(&path(&[pt(1.0, 1.0), pt(5.0, 1.0), pt(1.0, 5.0)], 0.5).shape()));
+        assert!(t.contains_shape(&poly(&[pt(1.0, 1.0), pt(5.0, 1.0), pt(1.0, 5.0)]).shape()));
+        assert!(!t.contains_shape(&pt(6.0, 6.0).shape()));
+    }
+
+    #[test]
+    fn test_poly_winding_number() {
+        let square = square(0.0, 0.0, 2.0);
+        assert_eq!(poly_winding_number(&square, &pt(1.0, 1.0)), 1);
+        assert_eq!(poly_winding_number(&square, &pt(3.0, 1.0)), 0);
+
+        // A pentagram winds twice around its centre and once around its tips.
+        let star: Vec<_> = (0..5)
+            .map(|i| {
+                let a = PI / 2.0 + f64::from(i) * 4.0 * PI / 5.0;
+                pt(a.cos(), a.sin())
+            })
+            .collect();
+        let star = poly(&star);
+        assert_eq!(poly_winding_number(&star, &pt(0.0, 0.0)), 2);
+        assert_eq!(poly_winding_number(&star, &pt(0.0, 0.8)), 1);
+        assert_eq!(poly_winding_number(&star, &pt(0.9, 0.9)), 0);
+    }
+}
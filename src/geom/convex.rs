@@ -1,6 +1,12 @@
-use crate::geom::math::{is_collinear, is_left_of, is_strictly_left_of};
-use crate::primitive::line;
+use std::f64::consts::PI;
+
+use crate::geom::area::pts_signed_area;
+use crate::geom::math::{f64_cmp, is_collinear, is_strictly_left_of};
 use crate::primitive::point::Pt;
+use crate::primitive::polygon::Poly;
+use crate::primitive::shape::Shape;
+use crate::primitive::{line, poly};
+use crate::tf::Tf;
 
 #[must_use]
 pub fn remove_collinear(pts: &[Pt]) -> Vec<Pt> {
@@ -18,8 +24,19 @@ pub fn remove_collinear(pts: &[Pt]) -> Vec<Pt> {
     out
 }
 
-pub fn ensure_ccw(pts: &mut [Pt]) {
-    if pts.len() > 2 && !is_left_of(&line(pts[0], pts[1]), pts[2]) {
+// Reverses |pts| if the closed polyline is CW. Uses the signed area, so works
+// for non-convex polylines. Returns true if |pts| was reversed.
+pub fn ensure_ccw(pts: &mut [Pt]) -> bool {
+    let reverse = pts_signed_area(pts) < 0.0;
+    if reverse {
+        pts.reverse();
+    }
+    reverse
+}
+
+// Reverses |pts| if the closed polyline is CCW, e.g. for polygon holes.
+pub fn ensure_cw(pts: &mut [Pt]) {
+    if pts_signed_area(pts) > 0.0 {
         pts.reverse();
     }
 }
@@ -37,3 +54,274 @@ pub fn is_convex_ccw(pts: &[Pt]) -> bool {
     }
     true
 }
+
+// Returns the point in |pts| furthest in direction |dir|, i.e. the support
+// point of their convex hull.
+pub fn support_pts(pts: &[Pt], dir: Pt) -> Pt {
+    *pts.iter().max_by(|a, b| a.dot(dir).total_cmp(&b.dot(dir))).unwrap()
+}
+
+// Sorts |pts| counterclockwise by angle around |center|, starting from the
+// positive x axis. Points at the same angle are sorted by distance.
+pub fn sort_ccw_around(center: Pt, pts: &mut [Pt]) {
+    let key = |p: &Pt| {
+        let d = *p - center;
+        let angle = d.y.atan2(d.x);
+        (if angle < 0.0 { angle + 2.0 * PI } else { angle }, d.mag2())
+    };
+    pts.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        f64_cmp(&a.0, &b.0).then_with(|| f64_cmp(&a.1, &b.1))
+    });
+}
+
+// Returns the convex hull of |pts| as a CCW polygon without collinear points.
+#[must_use]
+pub fn convex_hull(pts: &[Pt]) -> Vec<Pt> {
+    let mut pts = pts.to_vec();
+    pts.sort_by(|a, b| f64_cmp(&a.x, &b.x).then_with(|| f64_cmp(&a.y, &b.y)));
+    pts.dedup();
+    if pts.len() <= 2 {
+        return pts;
+    }
+    // Andrew's monotone chain: build the lower then the upper hull.
+    let half = |pts: &mut dyn Iterator<Item = &Pt>| {
+        let mut hull: Vec<Pt> = vec![];
+        for &p in pts {
+            while hull.len() >= 2
+                && !is_strictly_left_of(&line(hull[hull.len() - 2], hull[hull.len() - 1]), p)
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+        hull
+    };
+    let mut hull = half(&mut pts.iter());
+    hull.extend(half(&mut pts.iter().rev()));
+    hull
+}
+
+// Returns the convex hull of |pts| as a polygon. The polygon is empty if the
+// hull has no area, e.g. for fewer than three distinct or collinear points.
+pub fn convex_hull_poly(pts: &[Pt]) -> Poly {
+    let hull = convex_hull(pts);
+    if hull.len() >= 3 { poly(&hull) } else { poly(&[]) }
+}
+
+// Returns the farthest pair of points in |pts| and the distance between
+// them, or None if |pts| is empty. Uses rotating calipers over the convex
+// hull, so takes O(n log n) time.
+#[must_use]
+pub fn diameter(pts: &[Pt]) -> Option<(Pt, Pt, f64)> {
+    let hull = convex_hull(pts);
+    let n = hull.len();
+    let mut best = (*hull.first()?, hull[0], 0.0);
+    let mut check = |a: Pt, b: Pt| {
+        let d = a.dist(b);
+        if d > best.2 {
+            best = (a, b, d);
+        }
+    };
+    if n <= 2 {
+        check(hull[0], hull[n - 1]);
+        return Some(best);
+    }
+    // For each edge, advance |j| to the hull point furthest from it. The
+    // farthest pair is always among the edge endpoints and that point.
+    let area = |a: Pt, b: Pt, c: Pt| (b - a).cross(c - a).abs();
+    let mut j = 1;
+    for i in 0..n {
+        let (a, b) = (hull[i], hull[(i + 1) % n]);
+        while area(a, b, hull[(j + 1) % n]) > area(a, b, hull[j]) {
+            j = (j + 1) % n;
+        }
+        check(a, hull[j]);
+        check(b, hull[j]);
+    }
+    Some(best)
+}
+
+// Returns a convex region containing |s| as it moves from pose |from| to pose
+// |to|, to within |tol|. Rotations are sampled so the arcs traced by points
+// deviate from the hull by at most |tol|. Returns None if the swept region
+// has no area, e.g. for lines or a point moving along a straight line.
+#[must_use]
+pub fn swept_hull(s: &Shape, from: &Tf, to: &Tf, tol: f64) -> Option<Poly> {
+    let pts: Vec<Pt> = s.to_polyline(tol).into_iter().flatten().collect();
+    if pts.is_empty() {
+        return None;
+    }
+    // Each step rotates by at most the angle whose arc deviates by |tol| from
+    // its chord, for the point furthest from the center of rotation.
+    let da = (to.rotation() - from.rotation() + PI).rem_euclid(2.0 * PI) - PI;
+    let origin = from.pt(Pt::zero());
+    let r = pts.iter().map(|&p| (from.pt(p) - origin).mag()).fold(0.0, f64::max);
+    let steps =
+        if r > tol { (da.abs() / (2.0 * (1.0 - tol / r).acos())).ceil() as usize } else { 0 };
+    let steps = steps.max(1);
+    let mut samples = Vec::with_capacity(pts.len() * (steps + 1));
+    for i in 0..=steps {
+        samples.extend(from.lerp(to, i as f64 / steps as f64).pts(&pts));
+    }
+    let hull = convex_hull(&samples);
+    (hull.len() >= 3).then(|| poly(&hull))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::distance::circ_rt_dist;
+    use crate::geom::math::EP;
+    use crate::primitive::{Convex, ShapeOps, cap, circ, pt, rt, tri};
+
+    // Distance between convex shapes: the largest gap between their
+    // projections onto some direction. Sampling directions gives a lower bound
+    // which is accurate enough for testing.
+    fn convex_dist<A: Convex, B: Convex>(a: &A, b: &B) -> f64 {
+        let n = 3600;
+        (0..n)
+            .map(|i| {
+                let t = 2.0 * PI * f64::from(i) / f64::from(n);
+                let d = pt(t.cos(), t.sin());
+                b.support(-d).dot(d) - a.support(d).dot(d)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_convex_support() {
+        let r = rt(0.0, 0.0, 4.0, 2.0);
+        for c in [circ(pt(7.0, 1.0), 1.0), circ(pt(6.0, 5.0), 0.5), circ(pt(-3.0, -3.0), 2.0)] {
+            assert_relative_eq!(convex_dist(&c, &r), circ_rt_dist(&c, &r).unwrap(), epsilon = 1e-4);
+        }
+        assert_relative_eq!(convex_dist(&circ(pt(1.0, 1.0), 2.0), &r), 0.0);
+
+        assert_eq!(r.support(pt(1.0, 1.0)), pt(4.0, 2.0));
+        assert_eq!(r.support(pt(-1.0, 0.1)), pt(0.0, 2.0));
+        assert_eq!(circ(pt(1.0, 1.0), 2.0).support(pt(0.0, -3.0)), pt(1.0, -1.0));
+        assert_eq!(cap(pt(0.0, 0.0), pt(4.0, 0.0), 1.0).support(pt(1.0, 0.0)), pt(5.0, 0.0));
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        assert_eq!(t.support(pt(1.0, 2.0)), pt(0.0, 4.0));
+        assert_eq!(t.center(), pt(4.0 / 3.0, 4.0 / 3.0));
+        let p = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(0.0, 2.0)]);
+        assert_eq!(p.support(pt(-1.0, -1.0)), pt(0.0, 0.0));
+        assert_eq!(p.center(), pt(2.0, 1.0));
+        assert_relative_eq!(convex_dist(&t, &p), 0.0);
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let pts =
+            [pt(1.0, 1.0), pt(0.0, 0.0), pt(2.0, 0.0), pt(1.0, 0.0), pt(0.0, 2.0), pt(2.0, 2.0)];
+        assert_eq!(convex_hull(&pts), [pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 2.0), pt(0.0, 2.0)]);
+        assert_eq!(
+            convex_hull(&[pt(0.0, 0.0), pt(2.0, 2.0), pt(1.0, 1.0)]),
+            [pt(0.0, 0.0), pt(2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn test_convex_hull_poly() {
+        // Duplicates, interior points and points on the hull's edges.
+        let pts = [
+            pt(0.0, 0.0),
+            pt(3.0, 0.0),
+            pt(0.0, 0.0),
+            pt(1.0, 0.0),
+            pt(3.0, 3.0),
+            pt(1.0, 1.0),
+            pt(3.0, 3.0),
+            pt(0.0, 3.0),
+            pt(0.0, 1.5),
+        ];
+        let hull = convex_hull_poly(&pts);
+        assert_eq!(hull.pts(), [pt(0.0, 0.0), pt(3.0, 0.0), pt(3.0, 3.0), pt(0.0, 3.0)]);
+        assert!(hull.is_convex());
+        assert!(pts.iter().all(|p| hull.dist_to_shape(&p.shape()) < EP));
+
+        assert!(convex_hull_poly(&[pt(0.0, 0.0), pt(1.0, 1.0), pt(2.0, 2.0)]).pts().is_empty());
+        assert!(convex_hull_poly(&[pt(0.0, 0.0), pt(0.0, 0.0), pt(1.0, 0.0)]).pts().is_empty());
+        assert!(convex_hull_poly(&[]).pts().is_empty());
+    }
+
+    #[test]
+    fn test_diameter() {
+        assert!(diameter(&[]).is_none());
+        assert_eq!(diameter(&[pt(1.0, 2.0)]), Some((pt(1.0, 2.0), pt(1.0, 2.0), 0.0)));
+
+        let (a, b, d) =
+            diameter(&[pt(0.0, 0.0), pt(1.0, 0.0), pt(1.0, 1.0), pt(0.0, 1.0)]).unwrap();
+        assert_relative_eq!(d, 2.0_f64.sqrt());
+        assert_relative_eq!(a.dist(b), d);
+
+        // Collinear points give the extreme pair.
+        let (a, b, d) =
+            diameter(&[pt(1.0, 1.0), pt(3.0, 3.0), pt(0.0, 0.0), pt(2.0, 2.0)]).unwrap();
+        assert_relative_eq!(d, 3.0 * 2.0_f64.sqrt());
+        assert_eq!((a, b), (pt(0.0, 0.0), pt(3.0, 3.0)));
+
+        // Agrees with checking all pairs.
+        let pts: Vec<_> = (0..40)
+            .map(|i| {
+                let t = f64::from(i) * 2.4;
+                pt(t.cos() * (3.0 + t.sin()), t.sin() * 2.0 + f64::from(i % 7))
+            })
+            .collect();
+        let brute = pts.iter().flat_map(|a| pts.iter().map(|b| a.dist(*b))).fold(0.0, f64::max);
+        assert_relative_eq!(diameter(&pts).unwrap().2, brute);
+    }
+
+    #[test]
+    fn test_swept_hull() {
+        let tol = 0.01;
+        let r = rt(0.0, 0.0, 2.0, 1.0).shape();
+        let hull = swept_hull(&r, &Tf::identity(), &Tf::translate(pt(5.0, 0.0)), tol).unwrap();
+        assert_eq!(hull.bounds(), rt(0.0, 0.0, 7.0, 1.0));
+        assert_relative_eq!(pts_signed_area(hull.pts()), 7.0);
+
+        // Rotating a quarter turn about the origin sweeps out an arc.
+        let r = rt(1.0, -0.5, 3.0, 0.5).shape();
+        let hull = swept_hull(&r, &Tf::identity(), &Tf::rotate(90.0), tol).unwrap();
+        let far = pt(3.0, 0.5).mag();
+        let b = hull.bounds();
+        assert_relative_eq!(b.l(), -0.5, epsilon = EP);
+        assert_relative_eq!(b.b(), -0.5, epsilon = EP);
+        assert_relative_eq!(b.r(), far, epsilon = tol);
+        assert_relative_eq!(b.t(), far, epsilon = tol);
+        let mid = Tf::rotate(45.0);
+        assert!(hull.contains_shape(&mid.pt(pt(far - tol, 0.0)).shape()));
+        assert!(!hull.contains_shape(&pt(0.1, 0.1).shape()));
+
+        assert!(swept_hull(&line(pt(0.0, 0.0), pt(1.0, 0.0)).shape(), &mid, &mid, tol).is_none());
+        let p = pt(1.0, 1.0).shape();
+        assert!(swept_hull(&p, &Tf::identity(), &Tf::translate(pt(1.0, 0.0)), tol).is_none());
+
+        // Custom shapes are swept using their outline.
+        let c = Shape::Custom(Box::new(rt(0.0, 0.0, 2.0, 1.0).shape()));
+        let hull = swept_hull(&c, &Tf::identity(), &Tf::translate(pt(5.0, 0.0)), tol).unwrap();
+        assert_eq!(hull.bounds(), rt(0.0, 0.0, 7.0, 1.0));
+    }
+
+    #[test]
+    fn test_sort_ccw_around() {
+        let expected = [pt(1.0, 1.0), pt(-1.0, 1.0), pt(-1.0, -1.0), pt(1.0, -1.0)];
+        for i in 0..4 {
+            let mut pts = expected;
+            pts.rotate_left(i);
+            pts.swap(0, 2);
+            sort_ccw_around(pt(0.0, 0.0), &mut pts);
+            assert_eq!(pts, expected);
+            assert!(is_convex_ccw(&pts));
+        }
+
+        // Ties in angle are broken by distance.
+        let mut pts = [pt(2.0, 0.0), pt(0.0, 1.0), pt(1.0, 0.0)];
+        sort_ccw_around(pt(0.0, 0.0), &mut pts);
+        assert_eq!(pts, [pt(1.0, 0.0), pt(2.0, 0.0), pt(0.0, 1.0)]);
+    }
+}
@@ -1,9 +1,11 @@
-use crate::geom::contains::poly_contains_pt;
+use crate::geom::contains::{poly_contains_pt, tri_contains_pt};
 use crate::geom::intersects::{
-    cap_intersects_poly, circ_intersects_poly, circ_intersects_rt, poly_intersects_rt,
-    rt_intersects_seg, seg_intersects_seg,
+    cap_intersects_poly, cap_intersects_tri, circ_intersects_poly, circ_intersects_rt,
+    circ_intersects_tri, line_intersects_line, poly_intersects_poly, poly_intersects_rt,
+    poly_intersects_seg, poly_intersects_tri, rt_intersects_seg, rt_intersects_tri,
+    seg_crosses_seg, seg_intersects_tri, tri_intersects_tri,
 };
-use crate::geom::math::eq;
+use crate::geom::math::{eq, le, lt, pts_strictly_same_side};
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
 use crate::primitive::line_shape::Line;
@@ -12,166 +14,335 @@ use crate::primitive::point::Pt;
 use crate::primitive::polygon::{Poly, edges};
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
-use crate::primitive::{pt, seg};
+use crate::primitive::shape::Shape;
+use crate::primitive::triangle::Tri;
+use crate::primitive::{ShapeOps, pt, seg};
 
 // Distance functions should return 0 if there is intersection or containment.
 // This property is used by quadtree which returns 0 if it detects an intersection
 // by e.g. regular intersection tests.
+//
+// Distance functions return None rather than a meaningless result if the
+// distance is not finite, e.g. for degenerate inputs with NaN coordinates
+// produced by arithmetic.
 
-fn min_dist(iter: impl Iterator<Item = f64>) -> f64 {
+fn finite(d: f64) -> Option<f64> {
+    d.is_finite().then_some(d)
+}
+
+// Distance |d| less the radius |r|, clamped to zero. f64::max ignores NaN, so
+// the check has to happen before clamping.
+fn less_radius(d: f64, r: f64) -> Option<f64> {
+    finite(d - r).map(|d| d.max(0.0))
+}
+
+fn min_dist(iter: impl Iterator<Item = Option<f64>>) -> Option<f64> {
     let mut best = f64::MAX;
     for i in iter {
-        best = best.min(i);
+        // f64::min ignores NaN, so it would be silently dropped here.
+        debug_assert!(!i.is_some_and(f64::is_nan), "NaN distance");
+        best = best.min(i?);
         if eq(best, 0.0) {
-            return best;
+            return Some(best);
         }
     }
-    best
+    Some(best)
+}
+
+// Lower bound on the distance between shapes with bounds |a| and |b|. Lines
+// have empty bounds despite being unbounded, so empty bounds give zero, as do
+// non-finite bounds.
+#[must_use]
+pub fn bounds_dist_lower_bound(a: &Rt, b: &Rt) -> f64 {
+    if a.is_empty() || b.is_empty() { 0.0 } else { rt_rt_dist(a, b).unwrap_or(0.0) }
+}
+
+// Returns true if |a| and |b| are closer than the clearance |c|. Shapes exactly
+// |c| apart do not violate it, but touching or overlapping shapes (distance 0)
+// always violate any positive clearance, even one smaller than EP.
+#[must_use]
+pub fn violates_clearance(a: &Shape, b: &Shape, c: f64) -> bool {
+    let d = a.dist_to_shape(b);
+    (eq(d, 0.0) && c > 0.0) || lt(d, c)
+}
+
+// Returns true if |a| and |b| are within |tol| of each other but their
+// interiors don't overlap, e.g. squares sharing an edge. Overlap is checked
+// both ways since shapes without area, like segments, have no interior.
+#[must_use]
+pub fn are_adjacent(a: &Shape, b: &Shape, tol: f64) -> bool {
+    le(a.dist_to_shape(b), tol) && !a.intersects_shape_open(b) && !b.intersects_shape_open(a)
+}
+
+// Like dist_to_shape, but returns None rather than a meaningless result if
+// either shape has non-finite coordinates, or if the distance is not finite.
+// Note that NaN inputs can otherwise produce a distance of 0, since f64::max
+// ignores NaN.
+#[must_use]
+pub fn checked_dist(a: &Shape, b: &Shape) -> Option<f64> {
+    let finite_rt = |r: Rt| [r.l(), r.b(), r.r(), r.t()].iter().all(|v| v.is_finite());
+    if !finite_rt(a.bounds()) || !finite_rt(b.bounds()) {
+        return None;
+    }
+    finite(a.dist_to_shape(b))
 }
 
 #[must_use]
-pub fn cap_cap_dist(a: &Capsule, b: &Capsule) -> f64 {
-    let d = seg_seg_dist(&a.seg(), &b.seg()) - a.r() - b.r();
-    d.max(0.0)
+pub fn cap_cap_dist(a: &Capsule, b: &Capsule) -> Option<f64> {
+    less_radius(seg_seg_dist(&a.seg(), &b.seg())?, a.r() + b.r())
 }
 
 #[must_use]
-pub fn cap_circ_dist(a: &Capsule, b: &Circle) -> f64 {
-    let d = pt_seg_dist(&b.p(), &a.seg()) - a.r() - b.r();
-    d.max(0.0)
+pub fn cap_circ_dist(a: &Capsule, b: &Circle) -> Option<f64> {
+    less_radius(pt_seg_dist(&b.p(), &a.seg())?, a.r() + b.r())
 }
 
 #[must_use]
-pub fn cap_path_dist(a: &Capsule, b: &Path) -> f64 {
+pub fn cap_path_dist(a: &Capsule, b: &Path) -> Option<f64> {
     min_dist(b.caps().map(|cap| cap_cap_dist(a, &cap)))
 }
 
 #[must_use]
-pub fn cap_poly_dist(a: &Capsule, b: &Poly) -> f64 {
+pub fn cap_poly_dist(a: &Capsule, b: &Poly) -> Option<f64> {
     if cap_intersects_poly(a, b) {
-        0.0
+        Some(0.0)
     } else {
-        min_dist(b.edges().map(|[&p0, &p1]| cap_seg_dist(a, &seg(p0, p1))))
+        min_dist(b.outline_segs().map(|seg| cap_seg_dist(a, &seg)))
     }
 }
 
 #[must_use]
-pub fn cap_rt_dist(a: &Capsule, b: &Rt) -> f64 {
-    let d = rt_seg_dist(b, &a.seg()) - a.r();
-    d.max(0.0)
+pub fn cap_pt_dist(a: &Capsule, b: &Pt) -> Option<f64> {
+    less_radius(pt_seg_dist(b, &a.seg())?, a.r())
+}
+
+#[must_use]
+pub fn cap_rt_dist(a: &Capsule, b: &Rt) -> Option<f64> {
+    less_radius(rt_seg_dist(b, &a.seg())?, a.r())
 }
 
 #[must_use]
-pub fn cap_seg_dist(a: &Capsule, b: &Segment) -> f64 {
-    let d = seg_seg_dist(&a.seg(), b) - a.r();
-    d.max(0.0)
+pub fn cap_seg_dist(a: &Capsule, b: &Segment) -> Option<f64> {
+    less_radius(seg_seg_dist(&a.seg(), b)?, a.r())
 }
 
 #[must_use]
-pub fn circ_circ_dist(a: &Circle, b: &Circle) -> f64 {
-    let d = pt_pt_dist(&a.p(), &b.p()) - a.r() - b.r();
-    d.max(0.0)
+pub fn cap_tri_dist(a: &Capsule, b: &Tri) -> Option<f64> {
+    if cap_intersects_tri(a, b) {
+        Some(0.0)
+    } else {
+        min_dist(b.segs().iter().map(|seg| cap_seg_dist(a, seg)))
+    }
+}
+
+#[must_use]
+pub fn circ_circ_dist(a: &Circle, b: &Circle) -> Option<f64> {
+    less_radius(pt_pt_dist(&a.p(), &b.p())?, a.r() + b.r())
 }
 
 #[must_use]
-pub fn circ_path_dist(a: &Circle, b: &Path) -> f64 {
+pub fn circ_path_dist(a: &Circle, b: &Path) -> Option<f64> {
     min_dist(b.caps().map(|cap| cap_circ_dist(&cap, a)))
 }
 
 #[must_use]
-pub fn circ_poly_dist(a: &Circle, b: &Poly) -> f64 {
+pub fn circ_poly_dist(a: &Circle, b: &Poly) -> Option<f64> {
     if circ_intersects_poly(a, b) {
-        0.0
+        Some(0.0)
     } else {
-        let d = poly_pt_dist(b, &a.p()) - a.r();
-        d.max(0.0)
+        less_radius(poly_pt_dist(b, &a.p())?, a.r())
     }
 }
 
 #[must_use]
-pub fn circ_rt_dist(a: &Circle, b: &Rt) -> f64 {
+pub fn circ_pt_dist(a: &Circle, b: &Pt) -> Option<f64> {
+    less_radius(pt_pt_dist(&a.p(), b)?, a.r())
+}
+
+#[must_use]
+pub fn circ_seg_dist(a: &Circle, b: &Segment) -> Option<f64> {
+    less_radius(pt_seg_dist(&a.p(), b)?, a.r())
+}
+
+#[must_use]
+pub fn circ_tri_dist(a: &Circle, b: &Tri) -> Option<f64> {
+    if circ_intersects_tri(a, b) { Some(0.0) } else { less_radius(pt_tri_dist(&a.p(), b)?, a.r()) }
+}
+
+#[must_use]
+pub fn circ_rt_dist(a: &Circle, b: &Rt) -> Option<f64> {
     if circ_intersects_rt(a, b) {
-        0.0
+        Some(0.0)
     } else {
         // Project circle centre onto the rectangle:
         let p = a.p().clamp(b);
-        p.dist(a.p()) - a.r()
+        finite(p.dist(a.p()) - a.r())
+    }
+}
+
+#[must_use]
+pub fn line_line_dist(a: &Line, b: &Line) -> Option<f64> {
+    if line_intersects_line(a, b) { Some(0.0) } else { line_pt_dist(a, &b.st()) }
+}
+
+#[must_use]
+pub fn line_pt_dist(a: &Line, b: &Pt) -> Option<f64> {
+    finite(b.dist(a.project(*b)))
+}
+
+// Distance from |a| to the convex hull of |b|. If |a| separates or touches
+// the points the distance is zero, otherwise the closest point is a vertex.
+fn line_hull_dist(a: &Line, b: &[Pt]) -> Option<f64> {
+    if pts_strictly_same_side(a, b) {
+        min_dist(b.iter().map(|p| line_pt_dist(a, p)))
+    } else {
+        Some(0.0)
     }
 }
 
+// Distance from |a| to any bounded shape. Returns None if |b| is unbounded,
+// i.e. another Line, or if the distance is not finite. Use line_line_dist for
+// the unbounded case.
 #[must_use]
-pub fn line_pt_dist(a: &Line, b: &Pt) -> f64 {
-    b.dist(a.project(*b))
+pub fn line_shape_dist(a: &Line, b: &Shape) -> Option<f64> {
+    match b {
+        Shape::Capsule(s) => less_radius(line_hull_dist(a, &[s.st(), s.en()])?, s.r()),
+        Shape::Circle(s) => less_radius(line_pt_dist(a, &s.p())?, s.r()),
+        Shape::Compound(s) => min_dist(
+            s.quadtree().live_shapes().filter_map(|v| line_shape_dist(a, v.shape())).map(Some),
+        ),
+        Shape::Custom(s) => finite(s.dyn_dist_to_shape(&a.shape())),
+        Shape::Line(_) => None,
+        Shape::Path(s) => min_dist(
+            s.caps().map(|cap| less_radius(line_hull_dist(a, &[cap.st(), cap.en()])?, s.r())),
+        ),
+        Shape::Point(s) => line_pt_dist(a, s),
+        Shape::Polygon(s) => line_hull_dist(a, s.pts()),
+        Shape::Rect(s) => line_hull_dist(a, &s.pts()),
+        Shape::Segment(s) => line_hull_dist(a, &[s.st(), s.en()]),
+        Shape::Tri(s) => line_hull_dist(a, s.pts()),
+    }
 }
 
 #[must_use]
-pub fn path_poly_dist(a: &Path, b: &Poly) -> f64 {
+pub fn path_poly_dist(a: &Path, b: &Poly) -> Option<f64> {
     min_dist(a.caps().map(|cap| cap_poly_dist(&cap, b)))
 }
 
+#[must_use]
+pub fn path_tri_dist(a: &Path, b: &Tri) -> Option<f64> {
+    min_dist(a.caps().map(|cap| cap_tri_dist(&cap, b)))
+}
+
 // Distance to a polygon outline.
 #[must_use]
-pub fn polyline_pt_dist(a: &[Pt], b: &Pt) -> f64 {
+pub fn polyline_pt_dist(a: &[Pt], b: &Pt) -> Option<f64> {
     min_dist(edges(a).map(|[&p0, &p1]| pt_seg_dist(b, &seg(p0, p1))))
 }
 
+// Distance to the outer ring or any hole of a polygon.
+#[must_use]
+pub fn poly_outline_pt_dist(a: &Poly, b: &Pt) -> Option<f64> {
+    min_dist(a.rings().map(|ring| polyline_pt_dist(ring, b)))
+}
+
 #[must_use]
-pub fn poly_pt_dist(a: &Poly, b: &Pt) -> f64 {
-    if poly_contains_pt(a, b) { 0.0 } else { polyline_pt_dist(a.pts(), b) }
+pub fn poly_pt_dist(a: &Poly, b: &Pt) -> Option<f64> {
+    if poly_contains_pt(a, b) { Some(0.0) } else { poly_outline_pt_dist(a, b) }
+}
+
+#[must_use]
+pub fn poly_poly_dist(a: &Poly, b: &Poly) -> Option<f64> {
+    if poly_intersects_poly(a, b) {
+        Some(0.0)
+    } else {
+        // Closest points are on the outlines, which includes holes since one
+        // polygon may lie inside a hole of the other.
+        min_dist(
+            a.outline_segs().flat_map(|sa| b.outline_segs().map(move |sb| seg_seg_dist(&sa, &sb))),
+        )
+    }
 }
 
 #[must_use]
-pub fn poly_rt_dist(a: &Poly, b: &Rt) -> f64 {
+pub fn poly_rt_dist(a: &Poly, b: &Rt) -> Option<f64> {
     if poly_intersects_rt(a, b) {
-        0.0
+        Some(0.0)
+    } else {
+        min_dist(a.outline_segs().map(|seg| rt_seg_dist(b, &seg)))
+    }
+}
+
+#[must_use]
+pub fn poly_seg_dist(a: &Poly, b: &Segment) -> Option<f64> {
+    if poly_intersects_seg(a, b) {
+        Some(0.0)
+    } else {
+        min_dist(a.outline_segs().map(|seg| seg_seg_dist(&seg, b)))
+    }
+}
+
+#[must_use]
+pub fn poly_tri_dist(a: &Poly, b: &Tri) -> Option<f64> {
+    if poly_intersects_tri(a, b) {
+        Some(0.0)
     } else {
-        min_dist(a.edges().map(|[&p0, &p1]| rt_seg_dist(b, &seg(p0, p1))))
+        min_dist(a.outline_segs().map(|seg| seg_tri_dist(&seg, b)))
     }
 }
 
 #[must_use]
-pub fn pt_pt_dist(a: &Pt, b: &Pt) -> f64 {
-    a.dist(*b)
+pub fn pt_pt_dist(a: &Pt, b: &Pt) -> Option<f64> {
+    finite(a.dist(*b))
 }
 
 #[must_use]
-pub fn pt_rt_dist(a: &Pt, b: &Rt) -> f64 {
+pub fn pt_rt_dist(a: &Pt, b: &Rt) -> Option<f64> {
     if b.contains(*a) {
-        0.0
+        Some(0.0)
     } else {
         // Project centre onto the rectangle:
         let p = a.clamp(b);
-        p.dist(*a)
+        finite(p.dist(*a))
     }
 }
 
 #[must_use]
-pub fn pt_seg_dist(a: &Pt, b: &Segment) -> f64 {
-    let st_dist = a.dist(b.st());
-    let en_dist = a.dist(b.en());
+pub fn pt_seg_dist(a: &Pt, b: &Segment) -> Option<f64> {
+    let st_dist = finite(a.dist(b.st()))?;
+    let en_dist = finite(a.dist(b.en()))?;
     let project = b.line().project(*a);
     let dist = st_dist.min(en_dist);
-    if b.contains(project) { dist.min(a.dist(project)) } else { dist }
+    Some(if b.contains(project) { dist.min(a.dist(project)) } else { dist })
+}
+
+#[must_use]
+pub fn pt_tri_dist(a: &Pt, b: &Tri) -> Option<f64> {
+    if tri_contains_pt(b, a) {
+        Some(0.0)
+    } else {
+        min_dist(b.segs().iter().map(|seg| pt_seg_dist(a, seg)))
+    }
 }
 
 #[must_use]
-pub fn rt_path_dist(a: &Rt, b: &Path) -> f64 {
+pub fn rt_path_dist(a: &Rt, b: &Path) -> Option<f64> {
     min_dist(b.caps().map(|cap| cap_rt_dist(&cap, a)))
 }
 
 #[must_use]
-pub fn rt_rt_dist(a: &Rt, b: &Rt) -> f64 {
+pub fn rt_rt_dist(a: &Rt, b: &Rt) -> Option<f64> {
     // Compute shortest distance between each axis.
     // Select a non-negative distance. Not possible for both axis differences to be positive.
-    let x = (a.l() - b.r()).max(b.l() - a.r()).max(0.0);
-    let y = (a.b() - b.t()).max(b.b() - a.t()).max(0.0);
-    pt(x, y).mag()
+    let x = finite(a.l() - b.r())?.max(finite(b.l() - a.r())?).max(0.0);
+    let y = finite(a.b() - b.t())?.max(finite(b.b() - a.t())?).max(0.0);
+    finite(pt(x, y).mag())
 }
 
 #[must_use]
-pub fn rt_seg_dist(a: &Rt, b: &Segment) -> f64 {
+pub fn rt_seg_dist(a: &Rt, b: &Segment) -> Option<f64> {
     if rt_intersects_seg(a, b) {
-        0.0
+        Some(0.0)
     } else {
         // Check for closest distance from the segment to the edges of the rectangle.
         min_dist(a.segs().iter().map(|seg| seg_seg_dist(seg, b)))
@@ -179,34 +350,214 @@ pub fn rt_seg_dist(a: &Rt, b: &Segment) -> f64 {
 }
 
 #[must_use]
-pub fn seg_seg_dist(a: &Segment, b: &Segment) -> f64 {
+pub fn rt_tri_dist(a: &Rt, b: &Tri) -> Option<f64> {
+    if rt_intersects_tri(a, b) {
+        Some(0.0)
+    } else {
+        min_dist(b.segs().iter().map(|seg| rt_seg_dist(a, seg)))
+    }
+}
+
+#[must_use]
+pub fn seg_seg_dist(a: &Segment, b: &Segment) -> Option<f64> {
     // Closest distance must be between an endpoint and a segment, unless
     // the segments cross, in which case it is zero.
-    if seg_intersects_seg(a, b) {
-        return 0.0;
+    if seg_crosses_seg(a, b) {
+        return Some(0.0);
+    }
+    let ends = [a.st(), a.en()].map(|p| pt_seg_dist(&p, b));
+    min_dist(ends.into_iter().chain([b.st(), b.en()].map(|p| pt_seg_dist(&p, a))))
+}
+
+#[must_use]
+pub fn seg_tri_dist(a: &Segment, b: &Tri) -> Option<f64> {
+    if seg_intersects_tri(a, b) {
+        Some(0.0)
+    } else {
+        min_dist(b.segs().iter().map(|seg| seg_seg_dist(a, seg)))
+    }
+}
+
+#[must_use]
+pub fn tri_tri_dist(a: &Tri, b: &Tri) -> Option<f64> {
+    if tri_intersects_tri(a, b) {
+        Some(0.0)
+    } else {
+        // Closest points are on the edges, so check all nine edge pairs.
+        let (a, b) = (a.segs(), b.segs());
+        min_dist(a.iter().flat_map(|sa| b.iter().map(move |sb| seg_seg_dist(sa, sb))))
     }
-    let mut best = pt_seg_dist(&a.st(), b);
-    best = best.min(pt_seg_dist(&a.en(), b));
-    best = best.min(pt_seg_dist(&b.st(), a));
-    best = best.min(pt_seg_dist(&b.en(), a));
-    best
 }
 
 #[cfg(test)]
 mod tests {
     use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
 
     use super::*;
     use crate::geom::math::EP;
-    use crate::primitive::{cap, circ, rt};
+    use crate::primitive::compound::Compound;
+    use crate::primitive::{cap, circ, line, path, poly, rt, square, tri};
+
+    #[test]
+    fn test_line_shape_dist() {
+        let l = line(pt(0.0, 0.0), pt(1.0, 0.0));
+        // Tangent circle.
+        let c = circ(pt(5.0, 2.0), 2.0).shape();
+        assert_relative_eq!(line_shape_dist(&l, &c).unwrap(), 0.0, epsilon = EP);
+        let c = circ(pt(5.0, -3.0), 2.0).shape();
+        assert_relative_eq!(line_shape_dist(&l, &c).unwrap(), 1.0, epsilon = EP);
+
+        // Offset polygon, then crossing polygon.
+        let p = poly(&[pt(2.0, 1.5), pt(4.0, 3.0), pt(1.0, 4.0)]).shape();
+        assert_relative_eq!(line_shape_dist(&l, &p).unwrap(), 1.5, epsilon = EP);
+        let p = poly(&[pt(2.0, -1.0), pt(4.0, 3.0), pt(1.0, 4.0)]).shape();
+        assert_relative_eq!(line_shape_dist(&l, &p).unwrap(), 0.0, epsilon = EP);
+
+        let r = rt(-3.0, -4.0, -1.0, -2.5).shape();
+        assert_relative_eq!(line_shape_dist(&l, &r).unwrap(), 2.5, epsilon = EP);
+        let s = seg(pt(10.0, 1.0), pt(12.0, 3.0)).shape();
+        assert_relative_eq!(line_shape_dist(&l, &s).unwrap(), 1.0, epsilon = EP);
+        let c = cap(pt(10.0, 1.0), pt(12.0, 3.0), 0.25).shape();
+        assert_relative_eq!(line_shape_dist(&l, &c).unwrap(), 0.75, epsilon = EP);
+        assert_relative_eq!(l.dist_to_shape(&c), 0.75, epsilon = EP);
+
+        let parallel = line(pt(0.0, 2.0), pt(-1.0, 2.0));
+        assert!(line_shape_dist(&l, &parallel.shape()).is_none());
+        assert_relative_eq!(l.dist_to_shape(&parallel.shape()), 2.0, epsilon = EP);
+        let crossing = line(pt(0.0, 2.0), pt(1.0, 3.0)).shape();
+        assert_relative_eq!(l.dist_to_shape(&crossing), 0.0, epsilon = EP);
+
+        // Removed compound children are ignored.
+        let mut c = Compound::from_plain_shapes(&[
+            circ(pt(0.0, 5.0), 1.0).shape(),
+            circ(pt(3.0, 0.5), 1.0).shape(),
+        ]);
+        assert_relative_eq!(line_shape_dist(&l, &c.clone().shape()).unwrap(), 0.0);
+        c.remove_shape(1);
+        assert_relative_eq!(line_shape_dist(&l, &c.shape()).unwrap(), 4.0);
+    }
+
+    #[test]
+    fn test_zero_length_seg() {
+        let z = seg(pt(1.0, 1.0), pt(1.0, 1.0));
+        let s = seg(pt(0.0, 0.0), pt(4.0, 0.0));
+        assert_relative_eq!(pt_seg_dist(&pt(4.0, 4.0), &z).unwrap(), 18.0_f64.sqrt());
+        assert_relative_eq!(seg_seg_dist(&z, &s).unwrap(), 1.0);
+        assert_relative_eq!(seg_seg_dist(&s, &z).unwrap(), 1.0);
+        assert_relative_eq!(seg_seg_dist(&z, &z).unwrap(), 0.0);
+        assert_relative_eq!(line_pt_dist(&line(z.st(), z.en()), &pt(1.0, 3.0)).unwrap(), 2.0);
+        let c = cap(z.st(), z.en(), 0.5);
+        assert_relative_eq!(cap_seg_dist(&c, &s).unwrap(), 0.5);
+        assert_relative_eq!(cap_rt_dist(&c, &rt(3.0, 0.0, 4.0, 1.0)).unwrap(), 1.5);
+        assert_eq!(checked_dist(&c.shape(), &s.shape()), Some(0.5));
+    }
+
+    #[test]
+    fn test_checked_dist() {
+        let a = circ(pt(0.0, 0.0), 1.0).shape();
+        let b = circ(pt(3.0, 0.0), 1.0).shape();
+        assert_eq!(checked_dist(&a, &b), Some(1.0));
+        let nan = circ(pt(f64::NAN, 0.0), 1.0).shape();
+        assert_eq!(checked_dist(&a, &nan), None);
+        assert_eq!(checked_dist(&nan, &a), None);
+        let inf = rt(0.0, 0.0, f64::INFINITY, 1.0).shape();
+        assert_eq!(checked_dist(&inf, &a), None);
+    }
+
+    #[test]
+    fn test_non_finite_dist() {
+        let z = seg(pt(1.0, 1.0), pt(1.0, 1.0));
+        let nan = seg(pt(f64::NAN, 0.0), pt(4.0, 0.0));
+        assert_eq!(pt_seg_dist(&pt(f64::NAN, 0.0), &z), None);
+        assert_eq!(seg_seg_dist(&z, &nan), None);
+        assert_eq!(seg_seg_dist(&nan, &z), None);
+        // Clamping to zero must not hide NaN.
+        let c = cap(pt(f64::NAN, 1.0), pt(1.0, 1.0), 0.5);
+        assert_eq!(cap_seg_dist(&c, &z), None);
+        assert_eq!(cap_cap_dist(&c, &cap(z.st(), z.en(), 0.5)), None);
+        assert_eq!(rt_rt_dist(&rt(0.0, 0.0, 1.0, 1.0), &rt(f64::NAN, 0.0, 3.0, 1.0)), None);
+        assert_eq!(rt_rt_dist(&rt(0.0, 0.0, 1.0, 1.0), &rt(2.0, 0.0, f64::INFINITY, 1.0)), None);
+        assert!(c.dist_to_shape(&z.shape()).is_nan());
+    }
+
+    #[test]
+    fn test_are_adjacent() {
+        let a = rt(0.0, 0.0, 2.0, 2.0).shape();
+        // Sharing all or part of an edge, or a corner.
+        assert!(are_adjacent(&a, &rt(2.0, 0.0, 4.0, 2.0).shape(), EP));
+        assert!(are_adjacent(&a, &rt(2.0, 1.0, 4.0, 3.0).shape(), EP));
+        assert!(are_adjacent(&a, &rt(2.0, 2.0, 4.0, 4.0).shape(), EP));
+        // Overlapping, contained or disjoint.
+        assert!(!are_adjacent(&a, &rt(1.0, 0.0, 3.0, 2.0).shape(), EP));
+        assert!(!are_adjacent(&a, &rt(0.5, 0.5, 1.0, 1.0).shape(), EP));
+        assert!(!are_adjacent(&a, &rt(3.0, 0.0, 4.0, 2.0).shape(), EP));
+        // A small gap is allowed by the tolerance.
+        assert!(are_adjacent(&a, &rt(2.1, 0.0, 4.0, 2.0).shape(), 0.2));
+
+        // Shapes without area are adjacent if they lie along the boundary.
+        let s = seg(pt(0.0, 2.0), pt(2.0, 2.0)).shape();
+        assert!(are_adjacent(&a, &s, EP));
+        assert!(are_adjacent(&s, &a, EP));
+        let s = seg(pt(1.0, 1.0), pt(3.0, 1.0)).shape();
+        assert!(!are_adjacent(&a, &s, EP));
+        assert!(!are_adjacent(&s, &a, EP));
+
+        let t = tri(pt(2.0, 0.0), pt(4.0, 0.0), pt(2.0, 2.0)).shape();
+        assert!(are_adjacent(&a, &t, EP));
+        assert!(are_adjacent(&circ(pt(3.0, 1.0), 1.0).shape(), &a, EP));
+        assert!(!are_adjacent(&circ(pt(2.5, 1.0), 1.0).shape(), &a, EP));
+
+        // A trace running along the top of a pad, then one ending on it.
+        let trace = path(&[pt(-1.0, 3.0), pt(4.0, 3.0)], 1.0).shape();
+        assert!(are_adjacent(&trace, &a, EP));
+        assert!(are_adjacent(&a, &trace, EP));
+        let trace = path(&[pt(1.0, 5.0), pt(1.0, 2.5)], 1.0).shape();
+        assert!(!are_adjacent(&trace, &a, EP));
+        assert!(!are_adjacent(&a, &trace, EP));
+        assert!(!are_adjacent(&path(&[pt(5.0, 0.0), pt(5.0, 4.0)], 1.0).shape(), &a, EP));
+
+        // Compounds are adjacent if no live child overlaps.
+        let c = Compound::from_plain_shapes(&[
+            rt(2.0, 0.0, 4.0, 2.0).shape(),
+            circ(pt(1.0, -1.0), 1.0).shape(),
+        ])
+        .shape();
+        assert!(are_adjacent(&c, &a, EP));
+        assert!(are_adjacent(&a, &c, EP));
+        let c = Compound::from_plain_shapes(&[c, circ(pt(1.0, 1.0), 0.5).shape()]).shape();
+        assert!(!are_adjacent(&c, &a, EP));
+    }
+
+    #[test]
+    fn test_violates_clearance() {
+        let a = circ(pt(0.0, 0.0), 1.0).shape();
+        let b = circ(pt(3.0, 0.0), 1.0).shape();
+        let r = rt(2.0, -1.0, 3.0, 1.0).shape();
+
+        // Exactly at clearance.
+        assert!(!violates_clearance(&a, &b, 1.0));
+        // Within clearance.
+        assert!(violates_clearance(&a, &b, 1.5));
+        // Beyond clearance.
+        assert!(!violates_clearance(&a, &b, 0.5));
+        // Touching always violates a positive clearance.
+        let touching = rt(1.0, -1.0, 2.0, 1.0).shape();
+        assert!(violates_clearance(&a, &touching, EP * 10.0));
+        assert!(violates_clearance(&a, &touching, EP / 10.0));
+        assert!(!violates_clearance(&a, &touching, 0.0));
+        // Symmetric.
+        assert!(violates_clearance(&r, &a, 1.5));
+        assert!(!violates_clearance(&r, &a, 1.0));
+    }
 
     #[test]
     fn test_circ_circ() {
         let circ1 = circ(pt(0.0, 0.0), 0.4);
-        assert_relative_eq!(0.0, circ_circ_dist(&circ1, &circ1));
+        assert_relative_eq!(0.0, circ_circ_dist(&circ1, &circ1).unwrap());
         assert_relative_eq!(
             130.94659781997535,
-            circ_circ_dist(&circ(pt(111.6414, -70.632), 0.762), &circ1)
+            circ_circ_dist(&circ(pt(111.6414, -70.632), 0.762), &circ1).unwrap()
         );
     }
 
@@ -215,7 +566,7 @@ mod tests {
         let cap1 = cap(pt(47.0, -119.4), pt(47.8, -118.6), 0.125);
         let cap2 = cap(pt(47.0, -119.8), pt(46.6, -120.2), 0.125);
 
-        assert_relative_eq!(0.15, cap_cap_dist(&cap1, &cap2), epsilon = EP);
+        assert_relative_eq!(0.15, cap_cap_dist(&cap1, &cap2).unwrap(), epsilon = EP);
     }
 
     #[test]
@@ -223,20 +574,203 @@ mod tests {
         let cap = cap(pt(19.8, -100.6), pt(35.8, -100.6), 0.125);
         let circ = circ(pt(24.5, -98.25), 2.05);
 
-        assert_relative_eq!(0.175, cap_circ_dist(&cap, &circ), epsilon = EP);
+        assert_relative_eq!(0.175, cap_circ_dist(&cap, &circ).unwrap(), epsilon = EP);
+    }
+
+    #[test]
+    fn test_poly_poly() {
+        let a = square(0.0, 0.0, 2.0);
+        assert_relative_eq!(poly_poly_dist(&a, &square(1.0, 1.0, 2.0)).unwrap(), 0.0);
+        assert_relative_eq!(poly_poly_dist(&a, &square(0.5, 0.5, 1.0)).unwrap(), 0.0);
+        assert_relative_eq!(poly_poly_dist(&a, &square(2.0, 0.0, 1.0)).unwrap(), 0.0);
+        assert_relative_eq!(poly_poly_dist(&a, &square(3.0, 0.5, 1.0)).unwrap(), 1.0, epsilon = EP);
+        assert_relative_eq!(
+            poly_poly_dist(&a, &square(3.0, 3.0, 1.0)).unwrap(),
+            2.0_f64.sqrt(),
+            epsilon = EP
+        );
+
+        let tri = poly(&[pt(3.0, 1.0), pt(5.0, 0.0), pt(5.0, 2.0)]);
+        assert_relative_eq!(poly_poly_dist(&a, &tri).unwrap(), 1.0, epsilon = EP);
+        assert_relative_eq!(a.shape().dist_to_shape(&tri.shape()), 1.0, epsilon = EP);
+
+        // Inside a hole, the distance is to the hole's edges.
+        let ring = Poly::new_with_holes(
+            square(0.0, 0.0, 6.0).pts(),
+            &[square(2.0, 2.0, 2.0).pts().to_vec()],
+        );
+        assert_relative_eq!(
+            poly_poly_dist(&ring, &square(2.5, 2.5, 1.0)).unwrap(),
+            0.5,
+            epsilon = EP
+        );
+        assert_relative_eq!(
+            poly_rt_dist(&ring, &rt(2.5, 2.5, 3.5, 3.5)).unwrap(),
+            0.5,
+            epsilon = EP
+        );
+        let c = cap(pt(3.0, 2.75), pt(3.0, 3.25), 0.25);
+        assert_relative_eq!(cap_poly_dist(&c, &ring).unwrap(), 0.5, epsilon = EP);
+        let p = path(&[pt(3.0, 2.75), pt(3.0, 3.25)], 0.25);
+        assert_relative_eq!(path_poly_dist(&p, &ring).unwrap(), 0.5, epsilon = EP);
+    }
+
+    #[test]
+    fn test_seg_dist() {
+        let c = circ(pt(2.0, 3.0), 1.0);
+        assert_relative_eq!(circ_seg_dist(&c, &seg(pt(0.0, 0.0), pt(4.0, 0.0))).unwrap(), 2.0);
+        assert_relative_eq!(circ_seg_dist(&c, &seg(pt(5.0, 3.0), pt(8.0, 3.0))).unwrap(), 2.0);
+        assert_relative_eq!(circ_seg_dist(&c, &seg(pt(0.0, 3.0), pt(4.0, 3.0))).unwrap(), 0.0);
+
+        let p = square(0.0, 0.0, 4.0);
+        // Grazing an edge.
+        assert_relative_eq!(poly_seg_dist(&p, &seg(pt(1.0, 4.0), pt(3.0, 4.0))).unwrap(), 0.0);
+        assert_relative_eq!(poly_seg_dist(&p, &seg(pt(-1.0, 5.0), pt(5.0, 5.0))).unwrap(), 1.0);
+        assert_relative_eq!(poly_seg_dist(&p, &seg(pt(6.0, 1.0), pt(6.0, 3.0))).unwrap(), 2.0);
+        assert_relative_eq!(poly_seg_dist(&p, &seg(pt(1.0, 1.0), pt(2.0, 2.0))).unwrap(), 0.0);
+        assert_relative_eq!(
+            seg(pt(6.0, 5.0), pt(7.0, 6.0)).shape().dist_to_shape(&p.shape()),
+            5.0_f64.sqrt(),
+            epsilon = EP
+        );
+    }
+
+    #[test]
+    fn test_pt_dist() {
+        let c = circ(pt(1.0, 1.0), 1.0);
+        assert_relative_eq!(circ_pt_dist(&c, &pt(1.5, 1.0)).unwrap(), 0.0);
+        assert_relative_eq!(circ_pt_dist(&c, &pt(4.0, 5.0)).unwrap(), 4.0);
+        let c = cap(pt(0.0, 0.0), pt(4.0, 0.0), 1.0);
+        assert_relative_eq!(cap_pt_dist(&c, &pt(2.0, 0.5)).unwrap(), 0.0);
+        assert_relative_eq!(cap_pt_dist(&c, &pt(2.0, 3.0)).unwrap(), 2.0);
+        assert_relative_eq!(cap_pt_dist(&c, &pt(7.0, 4.0)).unwrap(), 4.0);
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        assert_relative_eq!(pt_tri_dist(&pt(1.0, 1.0), &t).unwrap(), 0.0);
+        assert_relative_eq!(pt_tri_dist(&pt(2.0, 2.0), &t).unwrap(), 0.0);
+        assert_relative_eq!(pt_tri_dist(&pt(3.0, 3.0), &t).unwrap(), 2.0_f64.sqrt(), epsilon = EP);
+        assert_relative_eq!(pt_tri_dist(&pt(-3.0, -4.0), &t).unwrap(), 5.0);
+
+        for s in [c.shape(), circ(pt(1.0, 1.0), 1.0).shape(), t.shape()] {
+            let p = pt(6.0, 6.0).shape();
+            assert_relative_eq!(p.dist_to_shape(&s), s.dist_to_shape(&p));
+        }
+    }
+
+    #[test]
+    fn test_cap_tri() {
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        assert_relative_eq!(cap_tri_dist(&cap(pt(1.0, 1.0), pt(1.5, 1.0), 0.1), &t).unwrap(), 0.0);
+        assert_relative_eq!(
+            cap_tri_dist(&cap(pt(-2.0, 1.0), pt(-2.0, 3.0), 1.0), &t).unwrap(),
+            1.0
+        );
+        assert_relative_eq!(
+            cap_tri_dist(&cap(pt(-2.0, 1.0), pt(-2.0, 3.0), 2.0), &t).unwrap(),
+            0.0
+        );
+        // Closest to the hypotenuse.
+        let c = cap(pt(4.0, 4.0), pt(6.0, 6.0), 0.5);
+        let d = 2.0_f64.sqrt() * 2.0 - 0.5;
+        assert_relative_eq!(cap_tri_dist(&c, &t).unwrap(), d, epsilon = EP);
+        assert_relative_eq!(c.shape().dist_to_shape(&t.shape()), d, epsilon = EP);
+        assert_relative_eq!(t.shape().dist_to_shape(&c.shape()), d, epsilon = EP);
+    }
+
+    #[test]
+    fn test_tri_dist() {
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        let ts = t.shape();
+        let dist = |s: Shape| {
+            let d = ts.dist_to_shape(&s);
+            assert_relative_eq!(d, s.dist_to_shape(&ts), epsilon = EP);
+            d
+        };
+        assert_relative_eq!(circ_tri_dist(&circ(pt(1.0, 1.0), 0.5), &t).unwrap(), 0.0);
+        assert_relative_eq!(dist(circ(pt(-3.0, 2.0), 1.0).shape()), 2.0, epsilon = EP);
+        assert_relative_eq!(seg_tri_dist(&seg(pt(-1.0, -1.0), pt(1.0, 1.0)), &t).unwrap(), 0.0);
+        assert_relative_eq!(dist(seg(pt(0.0, -2.0), pt(4.0, -3.0)).shape()), 2.0, epsilon = EP);
+        assert_relative_eq!(tri_tri_dist(&t, &t).unwrap(), 0.0);
+        let d = 2.0_f64.sqrt();
+        assert_relative_eq!(
+            dist(tri(pt(3.0, 3.0), pt(5.0, 3.0), pt(3.0, 5.0)).shape()),
+            d,
+            epsilon = EP
+        );
+        let p = poly(&[pt(5.0, 0.0), pt(6.0, 0.0), pt(6.0, 1.0), pt(5.0, 1.0)]);
+        assert_relative_eq!(poly_tri_dist(&p, &t).unwrap(), 1.0, epsilon = EP);
+        assert_relative_eq!(dist(p.shape()), 1.0, epsilon = EP);
+        let path = path(&[pt(-2.0, -2.0), pt(-2.0, 6.0), pt(6.0, 6.0)], 1.0);
+        assert_relative_eq!(path_tri_dist(&path, &t).unwrap(), 1.0, epsilon = EP);
+        assert_relative_eq!(dist(path.shape()), 1.0, epsilon = EP);
+        assert_relative_eq!(dist(line(pt(0.0, -1.0), pt(1.0, -1.0)).shape()), 1.0, epsilon = EP);
+        assert_relative_eq!(dist(line(pt(0.0, 1.0), pt(1.0, 1.0)).shape()), 0.0);
+    }
+
+    #[test]
+    fn test_tri_tri() {
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        // Overlapping, contained, and sharing an edge.
+        assert_relative_eq!(
+            tri_tri_dist(&t, &tri(pt(1.0, 1.0), pt(5.0, 1.0), pt(1.0, 5.0))).unwrap(),
+            0.0
+        );
+        assert_relative_eq!(
+            tri_tri_dist(&t, &tri(pt(0.5, 0.5), pt(1.0, 0.5), pt(0.5, 1.0))).unwrap(),
+            0.0
+        );
+        assert_relative_eq!(
+            tri_tri_dist(&t, &tri(pt(4.0, 0.0), pt(4.0, 4.0), pt(0.0, 4.0))).unwrap(),
+            0.0
+        );
+        // Vertex to edge.
+        let b = tri(pt(-1.0, 2.0), pt(-3.0, 1.0), pt(-3.0, 3.0));
+        assert_relative_eq!(tri_tri_dist(&t, &b).unwrap(), 1.0, epsilon = EP);
+        assert_relative_eq!(tri_tri_dist(&b, &t).unwrap(), 1.0, epsilon = EP);
+        // Parallel edges.
+        let b = tri(pt(0.0, -2.0), pt(4.0, -2.0), pt(2.0, -5.0));
+        assert_relative_eq!(tri_tri_dist(&t, &b).unwrap(), 2.0, epsilon = EP);
+        // Vertex to vertex.
+        let b = tri(pt(5.0, -1.0), pt(7.0, -1.0), pt(7.0, -3.0));
+        assert_relative_eq!(tri_tri_dist(&t, &b).unwrap(), 2.0_f64.sqrt(), epsilon = EP);
+    }
+
+    #[test]
+    fn test_rt_tri() {
+        let r = rt(0.0, 0.0, 2.0, 2.0);
+        let t = tri(pt(1.0, 1.0), pt(4.0, 1.0), pt(1.0, 4.0));
+        assert_relative_eq!(rt_tri_dist(&r, &t).unwrap(), 0.0);
+        // Containing in either direction.
+        assert_relative_eq!(
+            rt_tri_dist(&r, &tri(pt(0.5, 0.5), pt(1.0, 0.5), pt(0.5, 1.0))).unwrap(),
+            0.0
+        );
+        let big = tri(pt(-10.0, -10.0), pt(10.0, -10.0), pt(0.0, 10.0));
+        assert_relative_eq!(rt_tri_dist(&r, &big).unwrap(), 0.0);
+        // Closest to a triangle vertex.
+        let t = tri(pt(3.0, 1.0), pt(5.0, 0.0), pt(5.0, 2.0));
+        assert_relative_eq!(rt_tri_dist(&r, &t).unwrap(), 1.0, epsilon = EP);
+        // Closest to a triangle edge, from a rect corner.
+        let t = tri(pt(4.0, 2.0), pt(2.0, 4.0), pt(5.0, 5.0));
+        assert_relative_eq!(rt_tri_dist(&r, &t).unwrap(), 2.0_f64.sqrt(), epsilon = EP);
+        assert_relative_eq!(r.shape().dist_to_shape(&t.shape()), 2.0_f64.sqrt(), epsilon = EP);
+        assert_relative_eq!(t.shape().dist_to_shape(&r.shape()), 2.0_f64.sqrt(), epsilon = EP);
     }
 
     #[test]
     fn test_rt_rt() {
         let rt1 = rt(0.0, 0.0, 1.0, 1.0);
 
-        assert_relative_eq!(0.0, rt_rt_dist(&rt1, &rt1), epsilon = EP);
-        assert_relative_eq!(0.0, rt_rt_dist(&rt(1.0, 1.0, 2.0, 2.0), &rt1), epsilon = EP);
-        assert_relative_eq!(1.0, rt_rt_dist(&rt(2.0, 0.5, 2.0, 2.0), &rt1), epsilon = EP);
-        assert_relative_eq!(1.0, rt_rt_dist(&rt(-2.0, 0.5, -1.0, 2.0), &rt1), epsilon = EP);
+        assert_relative_eq!(0.0, rt_rt_dist(&rt1, &rt1).unwrap(), epsilon = EP);
+        assert_relative_eq!(0.0, rt_rt_dist(&rt(1.0, 1.0, 2.0, 2.0), &rt1).unwrap(), epsilon = EP);
+        assert_relative_eq!(1.0, rt_rt_dist(&rt(2.0, 0.5, 2.0, 2.0), &rt1).unwrap(), epsilon = EP);
+        assert_relative_eq!(
+            1.0,
+            rt_rt_dist(&rt(-2.0, 0.5, -1.0, 2.0), &rt1).unwrap(),
+            epsilon = EP
+        );
         assert_relative_eq!(
             2.0_f64.sqrt(),
-            rt_rt_dist(&rt(2.0, 2.0, 3.0, 3.0), &rt1),
+            rt_rt_dist(&rt(2.0, 2.0, 3.0, 3.0), &rt1).unwrap(),
             epsilon = EP
         );
     }
@@ -0,0 +1,132 @@
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use rand::seq::SliceRandom;
+
+use crate::geom::contains::circ_contains_pt;
+use crate::geom::math::eq;
+use crate::primitive::circ;
+use crate::primitive::circle::Circle;
+use crate::primitive::point::Pt;
+
+// Smallest circle with |a| and |b| on its boundary.
+fn circ_from_2(a: Pt, b: Pt) -> Circle {
+    circ((a + b) / 2.0, a.dist(b) / 2.0)
+}
+
+// Smallest circle with |a|, |b| and |c| on or inside it. This is the
+// circumcircle unless the points are collinear, in which case the outer two
+// points span the circle.
+fn circ_from_3(a: Pt, b: Pt, c: Pt) -> Circle {
+    let (ab, ac) = (b - a, c - a);
+    let d = 2.0 * ab.cross(ac);
+    if eq(d, 0.0) {
+        let pairs = [(a, b), (a, c), (b, c)];
+        let (p, q) =
+            pairs.into_iter().max_by(|x, y| x.0.dist(x.1).total_cmp(&y.0.dist(y.1))).unwrap();
+        return circ_from_2(p, q);
+    }
+    let (m_ab, m_ac) = (ab.mag2(), ac.mag2());
+    let o = Pt::new(ac.y * m_ab - ab.y * m_ac, ab.x * m_ac - ac.x * m_ab) / d;
+    circ(a + o, o.mag())
+}
+
+// Returns the smallest circle containing all of |pts|, or None if there are
+// none. Uses Welzl's algorithm in its iterative form, which takes expected
+// linear time after shuffling. The shuffle is seeded so results are
+// deterministic.
+#[must_use]
+pub fn min_enclosing_circle(pts: &[Pt]) -> Option<Circle> {
+    let mut pts = pts.to_vec();
+    pts.shuffle(&mut SmallRng::seed_from_u64(0));
+    let mut c = circ(*pts.first()?, 0.0);
+    for i in 1..pts.len() {
+        if circ_contains_pt(&c, &pts[i]) {
+            continue;
+        }
+        // |pts[i]| must be on the boundary of the circle for the first i + 1.
+        c = circ(pts[i], 0.0);
+        for j in 0..i {
+            if circ_contains_pt(&c, &pts[j]) {
+                continue;
+            }
+            c = circ_from_2(pts[i], pts[j]);
+            for k in 0..j {
+                if !circ_contains_pt(&c, &pts[k]) {
+                    c = circ_from_3(pts[i], pts[j], pts[k]);
+                }
+            }
+        }
+    }
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+
+    use super::*;
+    use crate::geom::math::EP;
+    use crate::primitive::pt;
+
+    fn check(pts: &[Pt]) -> Circle {
+        let c = min_enclosing_circle(pts).unwrap();
+        for p in pts {
+            assert!(circ_contains_pt(&c, p), "{c:?} contains {p}");
+        }
+        c
+    }
+
+    #[test]
+    fn test_min_enclosing_circle() {
+        assert!(min_enclosing_circle(&[]).is_none());
+
+        let c = check(&[pt(1.0, 2.0)]);
+        assert_eq!(c.p(), pt(1.0, 2.0));
+        assert_relative_eq!(c.r(), 0.0);
+
+        let c = check(&[pt(0.0, 0.0), pt(4.0, 0.0)]);
+        assert_eq!(c.p(), pt(2.0, 0.0));
+        assert_relative_eq!(c.r(), 2.0);
+
+        // Collinear and duplicate points.
+        let c = check(&[pt(0.0, 0.0), pt(1.0, 1.0), pt(3.0, 3.0), pt(1.0, 1.0), pt(2.0, 2.0)]);
+        assert_relative_eq!(c.r(), 1.5 * 2.0_f64.sqrt(), epsilon = EP);
+
+        // Square corners plus interior points are spanned by the diagonal.
+        let c = check(&[
+            pt(0.0, 0.0),
+            pt(2.0, 0.0),
+            pt(2.0, 2.0),
+            pt(0.0, 2.0),
+            pt(1.0, 1.0),
+            pt(0.5, 1.5),
+        ]);
+        assert_relative_eq!(c.p().x, 1.0, epsilon = EP);
+        assert_relative_eq!(c.p().y, 1.0, epsilon = EP);
+        assert_relative_eq!(c.r(), 2.0_f64.sqrt(), epsilon = EP);
+
+        // Acute triangle uses its circumcircle.
+        let c = check(&[pt(0.0, 0.0), pt(2.0, 0.0), pt(1.0, 1.5)]);
+        assert_relative_eq!(c.p().x, 1.0, epsilon = EP);
+        assert_relative_eq!(c.r(), c.p().dist(pt(1.0, 1.5)), epsilon = EP);
+        // Obtuse triangle uses its longest edge.
+        let c = check(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(2.0, 0.5)]);
+        assert_relative_eq!(c.r(), 2.0, epsilon = EP);
+    }
+
+    #[test]
+    fn test_min_enclosing_circle_random() {
+        let mut r = SmallRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let pts: Vec<_> = (0..r.gen_range(1..40))
+                .map(|_| pt(r.gen_range(-10.0..10.0), r.gen_range(-10.0..10.0)))
+                .collect();
+            let c = check(&pts);
+            // Minimal circles touch at least two points, unless there is one.
+            let touching = pts.iter().filter(|p| eq(c.p().dist(**p), c.r())).count();
+            assert!(touching >= 2.min(pts.len()), "{c:?} touches {touching}");
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::geom::math::{eq, pt_eq};
+use crate::primitive::point::Pt;
+use crate::primitive::segment::Segment;
+
+// Finds the arc of radius |r| tangent to both |a| and |b|, which must share
+// an endpoint. Returns the arc centre and the tangent points on |a| and |b|,
+// or None if the segments don't meet, are collinear, or the arc doesn't fit
+// within both segments.
+#[must_use]
+pub fn fillet(a: &Segment, b: &Segment, r: f64) -> Option<(Pt, Pt, Pt)> {
+    // Find the shared vertex and the far endpoint of each segment.
+    let (v, a_far, b_far) =
+        [(a.st(), a.en()), (a.en(), a.st())].into_iter().find_map(|(v, af)| {
+            if pt_eq(v, b.st()) {
+                Some((v, af, b.en()))
+            } else if pt_eq(v, b.en()) {
+                Some((v, af, b.st()))
+            } else {
+                None
+            }
+        })?;
+    let (a_len, b_len) = (v.dist(a_far), v.dist(b_far));
+    if eq(a_len, 0.0) || eq(b_len, 0.0) {
+        return None;
+    }
+    let u = (a_far - v) / a_len;
+    let w = (b_far - v) / b_len;
+    if eq(u.cross(w), 0.0) {
+        return None;
+    }
+    let half = u.dot(w).clamp(-1.0, 1.0).acos() / 2.0;
+    // Distance from the vertex to each tangent point.
+    let d = r / half.tan();
+    if d > a_len || d > b_len {
+        return None;
+    }
+    let centre = v + (u + w).norm() * (r / half.sin());
+    Some((centre, v + u * d, v + w * d))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::primitive::{pt, seg};
+
+    #[test]
+    fn test_fillet_right_angle() {
+        let a = seg(pt(0.0, 0.0), pt(2.0, 0.0));
+        let b = seg(pt(2.0, 0.0), pt(2.0, 2.0));
+        let (c, ta, tb) = fillet(&a, &b, 1.0).unwrap();
+        assert_relative_eq!(c, pt(1.0, 1.0));
+        assert_relative_eq!(ta, pt(1.0, 0.0));
+        assert_relative_eq!(tb, pt(2.0, 1.0));
+
+        // Segment direction doesn't matter.
+        let (c, ta, tb) = fillet(&seg(a.en(), a.st()), &seg(b.en(), b.st()), 1.0).unwrap();
+        assert_relative_eq!(c, pt(1.0, 1.0));
+        assert_relative_eq!(ta, pt(1.0, 0.0));
+        assert_relative_eq!(tb, pt(2.0, 1.0));
+    }
+
+    #[test]
+    fn test_fillet_no_fit() {
+        let a = seg(pt(0.0, 0.0), pt(2.0, 0.0));
+        let b = seg(pt(2.0, 0.0), pt(2.0, 2.0));
+        assert!(fillet(&a, &b, 3.0).is_none());
+        // Collinear.
+        assert!(fillet(&a, &seg(pt(2.0, 0.0), pt(4.0, 0.0)), 0.5).is_none());
+        // Not connected.
+        assert!(fillet(&a, &seg(pt(3.0, 0.0), pt(3.0, 2.0)), 0.5).is_none());
+    }
+}
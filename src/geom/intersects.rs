@@ -1,15 +1,18 @@
 use crate::geom::contains::{cap_contains_pt, tri_contains_pt};
-use crate::geom::distance::{rt_seg_dist, seg_seg_dist};
-use crate::geom::math::{le, lt, ne, orientation, pts_strictly_right_of};
+use crate::geom::distance::{pt_seg_dist, rt_seg_dist, seg_seg_dist};
+use crate::geom::math::{
+    cross_at, eq, le, lt, ne, orientation, pt_eq, pts_strictly_right_of, pts_strictly_same_side,
+};
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
 use crate::primitive::line_shape::Line;
 use crate::primitive::path_shape::Path;
+use crate::primitive::point::Pt;
 use crate::primitive::polygon::Poly;
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
 use crate::primitive::triangle::Tri;
-use crate::primitive::{ShapeOps, cap};
+use crate::primitive::{ShapeOps, cap, seg};
 
 #[must_use]
 pub fn cap_intersects_cap(a: &Capsule, b: &Capsule) -> bool {
@@ -18,7 +21,7 @@ pub fn cap_intersects_cap(a: &Capsule, b: &Capsule) -> bool {
         return false;
     }
 
-    le(seg_seg_dist(&a.seg(), &b.seg()), a.r() + b.r())
+    seg_seg_dist(&a.seg(), &b.seg()).is_some_and(|d| le(d, a.r() + b.r()))
 }
 
 #[must_use]
@@ -60,7 +63,12 @@ pub fn cap_intersects_rt(a: &Capsule, b: &Rt) -> bool {
         return true;
     }
 
-    le(rt_seg_dist(b, &a.seg()), a.r())
+    rt_seg_dist(b, &a.seg()).is_some_and(|d| le(d, a.r()))
+}
+
+#[must_use]
+pub fn cap_intersects_seg(a: &Capsule, b: &Segment) -> bool {
+    seg_seg_dist(&a.seg(), b).is_some_and(|d| le(d, a.r()))
 }
 
 #[must_use]
@@ -72,7 +80,7 @@ pub fn cap_intersects_tri(a: &Capsule, b: &Tri) -> bool {
     // Otherwise to intersect, the triangle boundary needs to be intersecting
     // the capsule.
     for seg in b.segs() {
-        if le(seg_seg_dist(&a.seg(), &seg), a.r()) {
+        if seg_seg_dist(&a.seg(), &seg).is_some_and(|d| le(d, a.r())) {
             return true;
         }
     }
@@ -125,6 +133,11 @@ pub fn circ_intersects_rt(a: &Circle, b: &Rt) -> bool {
     b.contains(a.p()) || lt(d, 0.0)
 }
 
+#[must_use]
+pub fn circ_intersects_seg(a: &Circle, b: &Segment) -> bool {
+    pt_seg_dist(&a.p(), b).is_some_and(|d| le(d, a.r()))
+}
+
 #[must_use]
 pub fn circ_intersects_tri(a: &Circle, b: &Tri) -> bool {
     // Check bounding boxes.
@@ -153,17 +166,29 @@ pub fn line_intersects_line(a: &Line, b: &Line) -> bool {
 }
 
 #[must_use]
-pub fn line_intersects_seg(_a: &Line, _b: &Segment) -> bool {
-    todo!()
+pub fn line_intersects_seg(a: &Line, b: &Segment) -> bool {
+    // A degenerate line is just a point.
+    if a.dir().is_zero() {
+        return b.contains(a.st());
+    }
+    // Intersects unless both endpoints are strictly on the same side. This
+    // includes endpoints on the line and collinear segments.
+    orientation(a, b.st()) * orientation(a, b.en()) <= 0
+}
+
+#[must_use]
+pub fn line_intersects_tri(a: &Line, b: &Tri) -> bool {
+    if a.dir().is_zero() {
+        return tri_contains_pt(b, &a.st());
+    }
+    !pts_strictly_same_side(a, b.pts())
 }
 
 #[must_use]
 pub fn path_intersects_path(a: &Path, b: &Path) -> bool {
     // Try pairwise intersection of capsules.
-    for i in 0..a.len() - 1 {
-        for j in i..b.len() - 1 {
-            let cap0 = cap(a[i], a[i + 1], a.r());
-            let cap1 = cap(b[j], b[j + 1], b.r());
+    for cap0 in a.caps() {
+        for cap1 in b.caps() {
             if cap_intersects_cap(&cap0, &cap1) {
                 return true;
             }
@@ -194,6 +219,42 @@ pub fn path_intersects_poly(a: &Path, b: &Poly) -> bool {
     false
 }
 
+#[must_use]
+pub fn path_intersects_seg(a: &Path, b: &Segment) -> bool {
+    for cap in a.caps() {
+        if cap_intersects_seg(&cap, b) {
+            return true;
+        }
+    }
+    false
+}
+
+#[must_use]
+pub fn path_intersects_tri(a: &Path, b: &Tri) -> bool {
+    for cap in a.caps() {
+        if cap_intersects_tri(&cap, b) {
+            return true;
+        }
+    }
+    false
+}
+
+#[must_use]
+pub fn poly_intersects_poly(a: &Poly, b: &Poly) -> bool {
+    // Check bounding boxes.
+    if !a.bounds().intersects(&b.bounds()) {
+        return false;
+    }
+
+    // Triangle intersection also catches one polygon being inside the other.
+    for tri in a.tri() {
+        if poly_intersects_tri(b, tri) {
+            return true;
+        }
+    }
+    false
+}
+
 #[must_use]
 pub fn poly_intersects_rt(a: &Poly, b: &Rt) -> bool {
     for tri in a.tri() {
@@ -204,6 +265,26 @@ pub fn poly_intersects_rt(a: &Poly, b: &Rt) -> bool {
     false
 }
 
+#[must_use]
+pub fn poly_intersects_seg(a: &Poly, b: &Segment) -> bool {
+    for tri in a.tri() {
+        if seg_intersects_tri(b, tri) {
+            return true;
+        }
+    }
+    false
+}
+
+#[must_use]
+pub fn poly_intersects_tri(a: &Poly, b: &Tri) -> bool {
+    for tri in a.tri() {
+        if tri_intersects_tri(tri, b) {
+            return true;
+        }
+    }
+    false
+}
+
 #[must_use]
 pub fn rt_intersects_rt(a: &Rt, b: &Rt) -> bool {
     a.intersects(b)
@@ -233,8 +314,8 @@ pub fn rt_intersects_seg(a: &Rt, b: &Segment) -> bool {
     if a.contains(b.st()) || a.contains(b.en()) {
         return true;
     }
-    // Test seg axis:
-    if pts_strictly_right_of(&b.line(), &a.pts()) {
+    // Test seg axis. The segment has no orientation, so check both sides:
+    if pts_strictly_same_side(&b.line(), &a.pts()) {
         return false;
     }
     // Test rect axes:
@@ -246,43 +327,160 @@ pub fn rt_intersects_seg(a: &Rt, b: &Segment) -> bool {
     true
 }
 
+// Returns true iff |a| and |b| cross at a point interior to both. Uses exact
+// signs rather than tolerances, so near-touching segments are not crossing.
+#[must_use]
+pub fn seg_crosses_seg(a: &Segment, b: &Segment) -> bool {
+    let a_st = cross_at(b.st(), b.en(), a.st());
+    let a_en = cross_at(b.st(), b.en(), a.en());
+    let b_st = cross_at(a.st(), a.en(), b.st());
+    let b_en = cross_at(a.st(), a.en(), b.en());
+    a_st * a_en < 0.0 && b_st * b_en < 0.0
+}
+
 #[must_use]
 pub fn seg_intersects_seg(a: &Segment, b: &Segment) -> bool {
-    // Check if the segment endpoints are on opposite sides of the other segment.
+    // If the segments don't cross, they can only meet at an endpoint of one of
+    // them. Defer to seg_seg_dist so this always agrees with it.
+    seg_crosses_seg(a, b) || seg_seg_dist(a, b).is_some_and(|d| eq(d, 0.0))
+}
+
+// Describes how two segments relate to each other.
+#[must_use]
+#[derive(Debug, Copy, Clone)]
+pub enum SegRelation {
+    // The segments do not intersect and are not collinear.
+    Disjoint,
+    // The segments cross at a single point interior to both.
+    Crossing(Pt),
+    // The segments meet at a single point which is an endpoint of at least
+    // one of them.
+    TouchingEndpoint(Pt),
+    // The segments are collinear and share the given sub-segment.
+    CollinearOverlap(Segment),
+    // The segments are collinear but do not intersect.
+    CollinearDisjoint,
+}
+
+pub fn seg_seg_classify(a: &Segment, b: &Segment) -> SegRelation {
+    // A degenerate segment has a zero cross product with everything, so would
+    // look collinear with any segment. Treat it as a point instead.
+    if a.dir().is_zero() {
+        return seg_seg_classify_degenerate(a.st(), b);
+    }
+    if b.dir().is_zero() {
+        return seg_seg_classify_degenerate(b.st(), a);
+    }
     let a_st = orientation(&b.line(), a.st());
     let a_en = orientation(&b.line(), a.en());
     let b_st = orientation(&a.line(), b.st());
     let b_en = orientation(&a.line(), b.en());
-    // No collinear points. Everything on different sides.
-    if a_st != a_en && b_st != b_en {
-        return true;
+    if (a_st == 0 && a_en == 0) || (b_st == 0 && b_en == 0) {
+        return seg_seg_classify_collinear(a, b);
     }
-    // Check collinear cases. Need to check both x and y coordinates to handle
-    // vertical and horizontal segments.
-    let a_rt = Rt::enclosing(a.st(), a.en());
-    let b_rt = Rt::enclosing(b.st(), b.en());
-    if a_st == 0 && b_rt.contains(a.st()) {
-        return true;
+    if !seg_intersects_seg(a, b) {
+        return SegRelation::Disjoint;
     }
-    if a_en == 0 && b_rt.contains(a.en()) {
-        return true;
+    // Exactly one point of intersection. If an endpoint lies on the other
+    // segment's line, it must be the intersection point.
+    for (o, p) in [(a_st, a.st()), (a_en, a.en()), (b_st, b.st()), (b_en, b.en())] {
+        if o == 0 {
+            return SegRelation::TouchingEndpoint(p);
+        }
     }
-    if b_st == 0 && a_rt.contains(b.st()) {
-        return true;
+    let d0 = cross_at(b.st(), b.en(), a.st());
+    let d1 = cross_at(b.st(), b.en(), a.en());
+    SegRelation::Crossing(a.st() + a.dir() * (d0 / (d0 - d1)))
+}
+
+fn seg_seg_classify_degenerate(p: Pt, b: &Segment) -> SegRelation {
+    if b.contains(p) {
+        SegRelation::TouchingEndpoint(p)
+    } else if orientation(&b.line(), p) == 0 {
+        SegRelation::CollinearDisjoint
+    } else {
+        SegRelation::Disjoint
     }
-    if b_en == 0 && a_rt.contains(b.en()) {
-        return true;
+}
+
+fn seg_seg_classify_collinear(a: &Segment, b: &Segment) -> SegRelation {
+    // Parameterise along the longer segment.
+    let (u, v) = if a.dir().mag2() >= b.dir().mag2() { (a, b) } else { (b, a) };
+    let d = u.dir();
+    let t0 = d.dot(v.st() - u.st()) / d.mag2();
+    let t1 = d.dot(v.en() - u.st()) / d.mag2();
+    let lo = t0.min(t1).max(0.0);
+    let hi = t0.max(t1).min(1.0);
+    let (st, en) = (u.st() + d * lo, u.st() + d * hi);
+    if pt_eq(st, en) {
+        SegRelation::TouchingEndpoint(st)
+    } else if lo > hi {
+        SegRelation::CollinearDisjoint
+    } else {
+        SegRelation::CollinearOverlap(seg(st, en))
     }
-    false
+}
+
+// Total length of boundary shared by |a| and |b|, i.e. the collinear
+// overlapping portions of their edges, including holes. Shapes which only
+// touch at points share no boundary.
+#[must_use]
+pub fn shared_boundary_length(a: &Poly, b: &Poly) -> f64 {
+    let b_segs: Vec<_> = b.outline_segs().collect();
+    let mut len = 0.0;
+    for sa in a.outline_segs() {
+        for sb in &b_segs {
+            if let SegRelation::CollinearOverlap(s) = seg_seg_classify(&sa, sb) {
+                len += s.length();
+            }
+        }
+    }
+    len
+}
+
+#[must_use]
+pub fn seg_intersects_tri(a: &Segment, b: &Tri) -> bool {
+    // Test seg axis. The segment has no orientation, so check both sides:
+    if pts_strictly_same_side(&a.line(), b.pts()) {
+        return false;
+    }
+    // Test tri axes:
+    for seg in b.segs() {
+        if pts_strictly_right_of(&seg.line(), &[a.st(), a.en()]) {
+            return false;
+        }
+    }
+    true
+}
+
+#[must_use]
+pub fn tri_intersects_tri(a: &Tri, b: &Tri) -> bool {
+    // Both triangles are CCW, so a separating axis has the other triangle
+    // strictly to the right of it.
+    for seg in a.segs() {
+        if pts_strictly_right_of(&seg.line(), b.pts()) {
+            return false;
+        }
+    }
+    for seg in b.segs() {
+        if pts_strictly_right_of(&seg.line(), a.pts()) {
+            return false;
+        }
+    }
+    true
 }
 
 #[cfg(test)]
 mod tests {
+    use approx::assert_relative_eq;
     use itertools::Itertools;
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::primitive::{pt, rt, seg, tri};
+    use crate::geom::qt::query::ShapeInfo;
+    use crate::primitive::compound::Compound;
+    use crate::primitive::shape::Shape;
+    use crate::primitive::{circ, line, path, poly, pt, rt, seg, square, tri};
     use crate::tf::Tf;
 
     fn test_seg_seg_permutations(a: &Segment, b: &Segment, res: bool) {
@@ -295,6 +493,95 @@ mod tests {
         assert_eq!(seg_intersects_seg(&b, &a), res, "{} {} intersects? {}", a, b, res);
     }
 
+    #[test]
+    fn test_shared_boundary_length() {
+        let a = square(0.0, 0.0, 2.0);
+        assert_relative_eq!(shared_boundary_length(&a, &square(2.0, 0.0, 2.0)), 2.0);
+        assert_relative_eq!(shared_boundary_length(&a, &square(2.0, 1.0, 2.0)), 1.0);
+        assert_relative_eq!(shared_boundary_length(&a, &square(-1.0, 2.0, 1.0)), 0.0);
+        assert_relative_eq!(shared_boundary_length(&a, &square(2.0, 2.0, 2.0)), 0.0);
+        assert_relative_eq!(shared_boundary_length(&a, &square(3.0, 0.0, 2.0)), 0.0);
+        // Smaller square abutting along part of an edge, from either side.
+        let b = square(0.5, 2.0, 1.0);
+        assert_relative_eq!(shared_boundary_length(&a, &b), 1.0);
+        assert_relative_eq!(shared_boundary_length(&b, &a), 1.0);
+    }
+
+    #[test]
+    fn test_seg_seg_classify() {
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 0.0), pt(2.0, 2.0)), &seg(pt(0.0, 2.0), pt(2.0, 0.0)));
+        assert!(matches!(rel, SegRelation::Crossing(p) if pt_eq(p, pt(1.0, 1.0))));
+
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 0.0), pt(2.0, 2.0)), &seg(pt(3.0, 0.0), pt(4.0, 0.0)));
+        assert!(matches!(rel, SegRelation::Disjoint));
+
+        // T-junction: endpoint of one segment on the interior of the other.
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 0.0), pt(2.0, 0.0)), &seg(pt(1.0, 0.0), pt(1.0, 3.0)));
+        assert!(matches!(rel, SegRelation::TouchingEndpoint(p) if pt_eq(p, pt(1.0, 0.0))));
+
+        // Collinear segments sharing only an endpoint.
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 0.0), pt(1.0, 1.0)), &seg(pt(2.0, 2.0), pt(1.0, 1.0)));
+        assert!(matches!(rel, SegRelation::TouchingEndpoint(p) if pt_eq(p, pt(1.0, 1.0))));
+
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 1.0), pt(3.0, 1.0)), &seg(pt(4.0, 1.0), pt(2.0, 1.0)));
+        assert!(matches!(rel, SegRelation::CollinearOverlap(s)
+            if pt_eq(s.st(), pt(2.0, 1.0)) && pt_eq(s.en(), pt(3.0, 1.0))));
+
+        // Contained segment overlaps over its whole length.
+        let rel =
+            seg_seg_classify(&seg(pt(1.0, 1.0), pt(2.0, 2.0)), &seg(pt(0.0, 0.0), pt(4.0, 4.0)));
+        assert!(matches!(rel, SegRelation::CollinearOverlap(s)
+            if pt_eq(s.st(), pt(1.0, 1.0)) && pt_eq(s.en(), pt(2.0, 2.0))));
+
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 1.0), pt(1.0, 1.0)), &seg(pt(2.0, 1.0), pt(3.0, 1.0)));
+        assert!(matches!(rel, SegRelation::CollinearDisjoint));
+
+        // Degenerate segment on the other segment.
+        let rel =
+            seg_seg_classify(&seg(pt(0.0, 0.0), pt(2.0, 0.0)), &seg(pt(1.0, 0.0), pt(1.0, 0.0)));
+        assert!(matches!(rel, SegRelation::TouchingEndpoint(p) if pt_eq(p, pt(1.0, 0.0))));
+        // Degenerate segment off the other segment, in both argument orders.
+        let (a, b) = (seg(pt(0.0, 0.0), pt(2.0, 0.0)), seg(pt(1.0, 5.0), pt(1.0, 5.0)));
+        assert!(matches!(seg_seg_classify(&a, &b), SegRelation::Disjoint));
+        assert!(matches!(seg_seg_classify(&b, &a), SegRelation::Disjoint));
+        let rel = seg_seg_classify(&seg(pt(1.0, 0.0), pt(1.0, 0.0)), &a);
+        assert!(matches!(rel, SegRelation::TouchingEndpoint(p) if pt_eq(p, pt(1.0, 0.0))));
+        let rel = seg_seg_classify(&seg(pt(5.0, 0.0), pt(5.0, 0.0)), &a);
+        assert!(matches!(rel, SegRelation::CollinearDisjoint));
+    }
+
+    #[test]
+    fn test_seg_seg_matches_dist() {
+        let tests = &[
+            // Endpoint just off the other segment, within tolerance.
+            (seg(pt(0.0, 0.0), pt(10.0, 0.0)), seg(pt(5.0, 5e-7), pt(5.0, 10.0))),
+            // Endpoint just off the other segment, beyond tolerance.
+            (seg(pt(0.0, 0.0), pt(10.0, 0.0)), seg(pt(5.0, 5e-5), pt(5.0, 10.0))),
+            // Short segments whose cross products are within tolerance.
+            (seg(pt(0.0, 0.0), pt(1e-3, 0.0)), seg(pt(5e-4, 9e-4), pt(5e-4, -9e-4))),
+            (seg(pt(0.0, 0.0), pt(1e-3, 0.0)), seg(pt(5e-4, 9e-4), pt(5e-4, 2e-4))),
+            // Near-collinear, barely touching at the ends.
+            (seg(pt(0.0, 0.0), pt(1.0, 1e-9)), seg(pt(1.0 + 1e-8, 0.0), pt(2.0, 0.0))),
+            (seg(pt(0.0, 0.0), pt(1.0, 1e-9)), seg(pt(1.0 + 1e-4, 0.0), pt(2.0, 0.0))),
+        ];
+        for (a, b) in tests {
+            let dist_zero = seg_seg_dist(a, b).is_some_and(|d| eq(d, 0.0));
+            test_seg_seg_permutations(a, b, dist_zero);
+        }
+        assert!(seg_intersects_seg(&tests[0].0, &tests[0].1));
+        assert!(!seg_intersects_seg(&tests[1].0, &tests[1].1));
+        assert!(seg_intersects_seg(&tests[2].0, &tests[2].1));
+        assert!(!seg_intersects_seg(&tests[3].0, &tests[3].1));
+        assert!(seg_intersects_seg(&tests[4].0, &tests[4].1));
+        assert!(!seg_intersects_seg(&tests[5].0, &tests[5].1));
+    }
+
     #[test]
     fn test_seg_seg() {
         let tests = &[
@@ -359,6 +646,72 @@ mod tests {
         }
     }
 
+    fn test_line_seg_permutations(a: &Line, b: &Segment, res: bool) {
+        // Try each combination of directions.
+        for a in [*a, line(a.en(), a.st())] {
+            for b in [*b, seg(b.en(), b.st())] {
+                assert_eq!(line_intersects_seg(&a, &b), res, "{a:?} {b} intersects? {res}");
+                assert_eq!(a.intersects_shape(&b.shape()), res, "{a:?} {b} intersects? {res}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_tri() {
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        assert!(line_intersects_tri(&line(pt(0.0, 1.0), pt(1.0, 1.0)), &t));
+        // Touching a vertex.
+        assert!(line_intersects_tri(&line(pt(0.0, 4.0), pt(1.0, 4.0)), &t));
+        assert!(!line_intersects_tri(&line(pt(0.0, 5.0), pt(1.0, 5.0)), &t));
+        assert!(line(pt(2.0, 2.0), pt(3.0, 1.0)).shape().intersects_shape(&t.shape()));
+        assert!(!t.shape().intersects_shape(&line(pt(3.0, 3.0), pt(4.0, 2.0)).shape()));
+        // Degenerate lines are points.
+        assert!(line_intersects_tri(&line(pt(1.0, 1.0), pt(1.0, 1.0)), &t));
+        assert!(!line_intersects_tri(&line(pt(3.0, 3.0), pt(3.0, 3.0)), &t));
+    }
+
+    #[test]
+    fn test_line_seg() {
+        let l = line(pt(0.0, 0.0), pt(1.0, 0.0));
+        let tests = &[
+            // Crossing, outside the points defining the line.
+            (l, seg(pt(2.0, -1.0), pt(3.0, 1.0)), true),
+            // Endpoint on the line.
+            (l, seg(pt(5.0, 0.0), pt(6.0, 2.0)), true),
+            // Collinear.
+            (l, seg(pt(7.0, 0.0), pt(9.0, 0.0)), true),
+            // Parallel, not intersecting.
+            (l, seg(pt(0.0, 1.0), pt(3.0, 1.0)), false),
+            // Same side, not intersecting.
+            (l, seg(pt(-4.0, 1.0), pt(3.0, 2.0)), false),
+            // Degenerate: Segment is a point on the line.
+            (l, seg(pt(4.0, 0.0), pt(4.0, 0.0)), true),
+            // Degenerate: Segment is a point off the line.
+            (l, seg(pt(4.0, 1.0), pt(4.0, 1.0)), false),
+            // Degenerate: Line is a point on the segment.
+            (line(pt(1.0, 1.0), pt(1.0, 1.0)), seg(pt(0.0, 0.0), pt(2.0, 2.0)), true),
+            // Degenerate: Line is a point off the segment.
+            (line(pt(1.0, 1.0), pt(1.0, 1.0)), seg(pt(0.0, 0.0), pt(2.0, 1.0)), false),
+        ];
+
+        for (a, b, res) in tests {
+            test_line_seg_permutations(a, b, *res);
+            // Negating pts should not change result.
+            let a = &line(-a.st(), -a.en());
+            let b = &seg(-b.st(), -b.en());
+            test_line_seg_permutations(a, b, *res);
+            // Rotating should not change result.
+            let tf = Tf::rotate(42.0);
+            test_line_seg_permutations(&tf.line(a), &tf.seg(b), *res);
+            // Translating should not change result.
+            let tf = Tf::translate(pt(-3.0, 4.0));
+            test_line_seg_permutations(&tf.line(a), &tf.seg(b), *res);
+            // Scaling should not change result.
+            let tf = Tf::scale(pt(-0.4, 0.7));
+            test_line_seg_permutations(&tf.line(a), &tf.seg(b), *res);
+        }
+    }
+
     fn permute_tri(t: &Tri) -> Vec<Tri> {
         t.pts().iter().permutations(3).map(|v| tri(*v[0], *v[1], *v[2])).collect()
     }
@@ -385,6 +738,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tri_tri() {
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0));
+        let tests = &[
+            // Overlapping.
+            (t, tri(pt(1.0, 1.0), pt(5.0, 1.0), pt(1.0, 5.0)), true),
+            // Fully inside.
+            (t, tri(pt(0.5, 0.5), pt(1.5, 0.5), pt(0.5, 1.5)), true),
+            // Sharing an edge.
+            (t, tri(pt(4.0, 0.0), pt(4.0, 4.0), pt(0.0, 4.0)), true),
+            // Touching at a vertex.
+            (t, tri(pt(4.0, 0.0), pt(6.0, 0.0), pt(5.0, 2.0)), true),
+            // Bounds overlap, but separated by the hypotenuse.
+            (t, tri(pt(3.0, 3.0), pt(4.0, 3.0), pt(3.0, 4.0)), false),
+            // Separated by an axis of the second triangle only.
+            (t, tri(pt(-1.0, 2.0), pt(-3.0, 0.0), pt(-1.0, -1.0)), false),
+        ];
+
+        for (a, b, res) in tests {
+            for a in permute_tri(a) {
+                for b in permute_tri(b) {
+                    assert_eq!(tri_intersects_tri(&a, &b), *res, "{a} {b} intersect? {res}");
+                    assert_eq!(tri_intersects_tri(&b, &a), *res, "{b} {a} intersect? {res}");
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_cap_rt() {
         let tests = &[
@@ -399,4 +780,86 @@ mod tests {
             assert_eq!(cap_intersects_rt(a, b), *res, "{} {} intersect? {}", a, b, res);
         }
     }
+
+    #[test]
+    fn test_poly_poly() {
+        // L shape with its notch at the top right.
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        let ring = Poly::new_with_holes(
+            square(0.0, 0.0, 6.0).pts(),
+            &[square(2.0, 2.0, 2.0).pts().to_vec()],
+        );
+        let tests = &[
+            // In the notch: bounds overlap but the polygons don't.
+            (l.clone(), square(2.5, 2.5, 1.0), false),
+            // Overlapping.
+            (l.clone(), square(1.0, 1.0, 2.0), true),
+            // Fully inside.
+            (l.clone(), square(0.5, 0.5, 1.0), true),
+            // Fully containing.
+            (l.clone(), square(-1.0, -1.0, 6.0), true),
+            // Touching an edge in the notch.
+            (l.clone(), square(2.0, 2.5, 1.0), true),
+            // Touching a corner.
+            (l.clone(), square(4.0, 2.0, 1.0), true),
+            // Disjoint bounds.
+            (l, square(5.0, 5.0, 1.0), false),
+            // Inside a hole.
+            (ring.clone(), square(2.5, 2.5, 1.0), false),
+            // Across a hole's boundary.
+            (ring, square(3.0, 3.0, 2.0), true),
+        ];
+
+        for (a, b, res) in tests {
+            assert_eq!(poly_intersects_poly(a, b), *res, "{a:?} {b:?} intersect? {res}");
+            assert_eq!(poly_intersects_poly(b, a), *res, "{b:?} {a:?} intersect? {res}");
+        }
+    }
+
+    fn shape_per_kind() -> Vec<Shape> {
+        let compound = Compound::empty();
+        compound.add_shape(ShapeInfo::anon(rt(6.0, -3.0, 8.0, -1.0).shape())).unwrap();
+        vec![
+            cap(pt(0.0, 0.0), pt(4.0, 0.0), 1.0).shape(),
+            circ(pt(5.0, 2.0), 1.0).shape(),
+            compound.shape(),
+            path(&[pt(-2.0, -3.0), pt(2.0, -3.0), pt(2.0, 3.0)], 0.5).shape(),
+            pt(1.0, 0.5).shape(),
+            poly(&[
+                pt(3.0, -2.0),
+                pt(7.0, -2.0),
+                pt(7.0, 0.0),
+                pt(5.0, 0.0),
+                pt(5.0, 3.0),
+                pt(3.0, 3.0),
+            ])
+            .shape(),
+            rt(0.0, 1.0, 2.0, 4.0).shape(),
+            seg(pt(-1.0, 4.0), pt(6.0, -1.0)).shape(),
+            tri(pt(6.0, 1.0), pt(9.0, 1.0), pt(8.0, 4.0)).shape(),
+        ]
+    }
+
+    #[test]
+    fn test_intersects_shape_symmetric() {
+        let shapes = shape_per_kind();
+        for a in &shapes {
+            for b in &shapes {
+                // Clone so compounds are never tested against themselves.
+                let b = b.clone();
+                assert_eq!(
+                    a.intersects_shape(&b),
+                    b.intersects_shape(a),
+                    "{a:?} {b:?} intersects symmetric"
+                );
+            }
+        }
+    }
 }
@@ -89,7 +89,14 @@ pub fn is_right_of(l: &Line, p: Pt) -> bool {
 
 #[must_use]
 pub fn is_collinear(a: Pt, b: Pt, c: Pt) -> bool {
-    eq(cross_at(a, b, c), 0.0)
+    is_collinear_tol(a, b, c, EP)
+}
+
+// Returns true iff |a|, |b|, and |c| are collinear, allowing the cross
+// product (twice the area of the triangle they form) to be off by |tol|.
+#[must_use]
+pub fn is_collinear_tol(a: Pt, b: Pt, c: Pt, tol: f64) -> bool {
+    relative_eq!(cross_at(a, b, c), 0.0, epsilon = tol)
 }
 
 #[must_use]
@@ -134,3 +141,20 @@ pub fn pts_strictly_same_side(l: &Line, pts: &[Pt]) -> bool {
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::pt;
+
+    #[test]
+    fn test_is_collinear() {
+        assert!(is_collinear(pt(0.0, 0.0), pt(1.0, 1.0), pt(3.0, 3.0)));
+        assert!(!is_collinear(pt(0.0, 0.0), pt(1.0, 0.0), pt(1.0, 1.0)));
+
+        let (a, b, c) = (pt(0.0, 0.0), pt(10.0, 0.0), pt(5.0, 1e-4));
+        assert!(!is_collinear(a, b, c));
+        assert!(!is_collinear_tol(a, b, c, 1e-6));
+        assert!(is_collinear_tol(a, b, c, 1e-2));
+    }
+}
@@ -1,7 +1,12 @@
+pub mod area;
 pub mod bounds;
+pub mod clip;
 pub mod contains;
 pub mod convex;
 pub mod distance;
+pub mod enclosing;
+pub mod fillet;
 pub mod intersects;
 pub mod math;
 pub mod qt;
+pub mod raster;
@@ -1,11 +1,15 @@
+use std::collections::BinaryHeap;
 use std::mem::swap;
 
-use ahash::HashMap;
+use ahash::{HashMap, HashSet};
 use ordered_float::OrderedFloat;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use smallvec::{SmallVec, smallvec};
 
+use crate::error::{Error, Result};
 use crate::geom::bounds::rt_cloud_bounds;
-use crate::geom::distance::rt_rt_dist;
+use crate::geom::distance::bounds_dist_lower_bound;
 use crate::geom::qt::query::{
     Query, ShapeInfo, cached_contains, cached_dist, cached_intersects, decompose_shape,
     matches_query,
@@ -29,6 +33,46 @@ struct IntersectData {
     tests: usize, // How many times we had to test against shapes directly.
 }
 
+// The k nearest shapes found so far, as a max-heap so the furthest is evicted
+// first.
+#[derive(Debug, Clone)]
+struct KNearest {
+    k: usize,
+    heap: BinaryHeap<(OrderedFloat<f64>, ShapeIdx)>,
+    seen: HashSet<ShapeIdx>,
+}
+
+impl KNearest {
+    fn new(k: usize) -> Self {
+        Self { k, heap: BinaryHeap::with_capacity(k + 1), seen: HashSet::default() }
+    }
+
+    // Distance a shape must be within to be one of the k nearest.
+    fn bound(&self) -> f64 {
+        if self.heap.len() < self.k {
+            f64::MAX
+        } else {
+            self.heap.peek().map_or(f64::MAX, |v| v.0.0)
+        }
+    }
+
+    // Adds shape |idx| at distance |d|, if it is nearer than the furthest one
+    // kept. Ties are broken by index, so the result doesn't depend on the
+    // order shapes are found in.
+    fn push(&mut self, idx: ShapeIdx, d: f64) {
+        let v = (OrderedFloat(d), idx);
+        if self.heap.len() >= self.k && self.heap.peek().is_some_and(|top| v >= *top) {
+            return;
+        }
+        if self.seen.insert(idx) {
+            self.heap.push(v);
+            if self.heap.len() > self.k {
+                self.heap.pop();
+            }
+        }
+    }
+}
+
 #[must_use]
 #[derive(Debug, Clone)]
 struct Node {
@@ -53,6 +97,33 @@ impl Default for Node {
     }
 }
 
+impl Node {
+    fn leaf(shapes: Vec<ShapeIdx>) -> Self {
+        let intersect = shapes.into_iter().map(|shape_idx| IntersectData { shape_idx, tests: 0 });
+        Self { intersect: intersect.collect(), ..Default::default() }
+    }
+
+    // Returns the node with its child indices shifted by |base|.
+    #[cfg(feature = "rayon")]
+    fn offset(mut self, base: NodeIdx) -> Self {
+        for child in [&mut self.bl, &mut self.br, &mut self.tr, &mut self.tl] {
+            if *child != NO_NODE {
+                *child += base;
+            }
+        }
+        self
+    }
+}
+
+// What to do when adding a shape that doesn't fit in the current bounds.
+#[must_use]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BoundsPolicy {
+    #[default]
+    Rebuild, // Expand the bounds and rebuild the tree.
+    Fixed, // Reject the shape with Error::OutOfBounds.
+}
+
 #[must_use]
 #[derive(Debug, Default, Clone)]
 pub struct QuadTree {
@@ -60,25 +131,58 @@ pub struct QuadTree {
     free_shapes: Vec<ShapeIdx>, // List of indices of shapes that have been deleted.
     nodes: Vec<Node>,
     bounds: Rt,
+    policy: BoundsPolicy,
     intersect_cache: HashMap<ShapeIdx, bool>, // Caches intersection tests.
     contain_cache: HashMap<ShapeIdx, bool>,   // Caches containment tests.
     dist_cache: HashMap<ShapeIdx, f64>,       // Caches distance tests.
+    cache_key: Option<CacheKey>,              // What the caches were computed for.
+    probe_token: Option<u64>,                 // Caller's identifier for the probe.
+    generation: usize,                        // Incremented when shapes change.
+}
+
+// Cached per-shape results are only valid for the same probe against the same
+// shapes. Results don't depend on the query, which is checked separately.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct CacheKey {
+    generation: usize,
+    probe_token: u64,
 }
 
 impl QuadTree {
     pub fn new(shapes: Vec<ShapeInfo>) -> Self {
         let bounds = rt_cloud_bounds(shapes.iter().map(|s| s.shape().bounds()));
-        let nodes = vec![Node::default(), Node {
-            intersect: (0..shapes.len())
-                .map(|shape_idx| IntersectData { shape_idx, tests: 0 })
-                .collect(),
-            ..Default::default()
-        }];
+        let nodes = vec![Node::default(), Node::leaf((0..shapes.len()).collect())];
         Self { shapes, nodes, bounds, ..Default::default() }
     }
 
+    // Builds a tree of |shapes|, each with no tag or kinds. Like new, this
+    // can't fail, since the bounds are computed to fit the shapes.
+    pub fn from_shapes(shapes: &[Shape]) -> Self {
+        Self::new(shapes.iter().cloned().map(ShapeInfo::anon).collect())
+    }
+
+    // Builds a tree of |shapes| up front like new followed by optimize, but
+    // splits nodes in parallel. Shapes can't be shared between threads, so
+    // they are assigned to quadrants by their bounds. Nodes may then list
+    // shapes which don't quite reach them and don't record which shapes
+    // contain them, but queries give the same results. Like new, this can't
+    // fail.
+    #[cfg(feature = "rayon")]
+    pub fn par_bulk_load(shapes: Vec<ShapeInfo>) -> Self {
+        let shape_bounds: Vec<_> = shapes.iter().map(|s| s.shape().bounds()).collect();
+        let bounds = rt_cloud_bounds(shape_bounds.iter().copied());
+        let root = par_build(&shape_bounds, (0..shapes.len()).collect(), bounds, 0);
+        let nodes = std::iter::once(Node::default()).chain(root.into_iter().map(|n| n.offset(1)));
+        Self { shapes, nodes: nodes.collect(), bounds, ..Default::default() }
+    }
+
     pub fn with_bounds(r: &Rt) -> Self {
-        Self { nodes: vec![Node::default(), Node::default()], bounds: *r, ..Default::default() }
+        Self {
+            nodes: vec![Node::default(), Node::default()],
+            bounds: *r,
+            policy: BoundsPolicy::Fixed,
+            ..Default::default()
+        }
     }
 
     pub fn empty() -> Self {
@@ -97,10 +201,41 @@ impl QuadTree {
         rts
     }
 
+    // All shape slots, indexed by ShapeIdx. This includes free slots from
+    // removed shapes, so use live_shapes to visit only shapes in the tree.
     pub fn shapes(&self) -> &[ShapeInfo] {
         &self.shapes
     }
 
+    // Shapes in the tree, skipping free slots from removed shapes.
+    pub fn live_shapes(&self) -> impl Iterator<Item = &ShapeInfo> {
+        let removed = self.removed_mask();
+        self.shapes.iter().enumerate().filter(move |&(idx, _)| !removed[idx]).map(|(_, s)| s)
+    }
+
+    // Number of shapes in the tree, not counting free slots from removed
+    // shapes.
+    #[must_use]
+    pub fn shape_count(&self) -> usize {
+        self.shapes.len() - self.free_shapes.len()
+    }
+
+    // Whether the tree has bounds, either from with_bounds or from shapes
+    // added to it. A tree can be initialized yet have no shapes, e.g. after
+    // with_bounds or after removing every shape.
+    #[must_use]
+    pub fn is_initialized(&self) -> bool {
+        !self.bounds.is_empty()
+    }
+
+    pub fn bounds_policy(&self) -> BoundsPolicy {
+        self.policy
+    }
+
+    pub fn set_bounds_policy(&mut self, policy: BoundsPolicy) {
+        self.policy = policy;
+    }
+
     fn rts_internal(&self, idx: NodeIdx, r: Rt, rts: &mut Vec<Rt>) {
         if idx == NO_NODE {
             return;
@@ -112,10 +247,14 @@ impl QuadTree {
         self.rts_internal(self.nodes[idx].tl, r.tl_quadrant(), rts);
     }
 
-    pub fn add_shape(&mut self, s: ShapeInfo) -> Vec<ShapeIdx> {
+    pub fn add_shape(&mut self, s: ShapeInfo) -> Result<Vec<ShapeIdx>> {
         let bounds = self.bounds().united(&s.shape().bounds());
         // If this shape expands the bounds, rebuild the tree.
         // TODO: Don't rebuild the tree?
+        if bounds != self.bounds() && self.policy == BoundsPolicy::Fixed {
+            return Err(Error::OutOfBounds);
+        }
+        self.generation += 1;
         let s = decompose_shape(s);
         let mut shape_idxs = Vec::new();
         if bounds == self.bounds() {
@@ -137,12 +276,24 @@ impl QuadTree {
                 shape_idxs.push(shapes.len());
                 shapes.push(shape);
             }
+            // Keep removed shapes' slots free rather than bringing them back.
+            let free_shapes = std::mem::take(&mut self.free_shapes);
+            let probe_token = self.probe_token;
             *self = Self::new(shapes);
+            self.nodes[1].intersect.retain(|v| !free_shapes.contains(&v.shape_idx));
+            self.free_shapes = free_shapes;
+            self.probe_token = probe_token;
         }
-        shape_idxs
+        Ok(shape_idxs)
     }
 
+    // Removes shape |s| from the tree, freeing its slot for reuse. Removing a
+    // shape which was already removed does nothing.
     pub fn remove_shape(&mut self, s: ShapeIdx) {
+        if self.free_shapes.contains(&s) {
+            return;
+        }
+        self.generation += 1;
         // Remove everything referencing this shape.
         for node in &mut self.nodes {
             node.intersect.retain(|v| v.shape_idx != s);
@@ -151,14 +302,118 @@ impl QuadTree {
         self.free_shapes.push(s);
     }
 
+    // Removes all shapes for which |pred| returns false, in a single pass over
+    // the nodes.
+    pub fn retain<F: Fn(&ShapeInfo) -> bool>(&mut self, pred: F) {
+        let mut removed = self.removed_mask();
+        let mut any_removed = false;
+        for (idx, shape) in self.shapes.iter().enumerate() {
+            if !removed[idx] && !pred(shape) {
+                removed[idx] = true;
+                self.free_shapes.push(idx);
+                any_removed = true;
+            }
+        }
+        if !any_removed {
+            return;
+        }
+        self.generation += 1;
+        for node in &mut self.nodes {
+            node.intersect.retain(|v| !removed[v.shape_idx]);
+            node.contain.retain(|&v| !removed[v]);
+        }
+    }
+
     pub fn bounds(&self) -> Rt {
         self.bounds
     }
 
+    // Returns the union of the bounds of all shapes, or None if there are no
+    // shapes. Unlike bounds, this is not the region covered by the tree, which
+    // can be larger, e.g. if set via with_bounds.
+    #[must_use]
+    pub fn tight_bounds(&self) -> Option<Rt> {
+        let mut live = self.live_shapes().peekable();
+        live.peek()?;
+        Some(rt_cloud_bounds(live.map(|s| s.shape().bounds())))
+    }
+
+    // Which shape indices are free slots from removed shapes.
+    fn removed_mask(&self) -> Vec<bool> {
+        let mut removed = vec![false; self.shapes.len()];
+        for &idx in &self.free_shapes {
+            removed[idx] = true;
+        }
+        removed
+    }
+
+    // Rebuilds the tree up front, splitting any node with more than
+    // TEST_THRESHOLD shapes, instead of waiting for queries to push shapes
+    // down. Useful after many incremental edits. Shape indices are preserved.
+    pub fn optimize(&mut self) {
+        let removed = self.removed_mask();
+        let live = (0..self.shapes.len()).filter(|&shape_idx| !removed[shape_idx]);
+        self.nodes = vec![Node::default(), Node::leaf(live.collect())];
+        self.build(1, self.bounds(), 0);
+    }
+
+    fn build(&mut self, idx: NodeIdx, r: Rt, depth: usize) {
+        let count = self.nodes[idx].intersect.len();
+        if count <= TEST_THRESHOLD || depth > MAX_DEPTH {
+            return;
+        }
+        for inter in &mut self.nodes[idx].intersect {
+            inter.tests = TEST_THRESHOLD;
+        }
+        self.maybe_push_down(idx, r, depth);
+
+        let Node { bl, br, tr, tl, .. } = self.nodes[idx];
+        for (child, child_rt) in [
+            (bl, r.bl_quadrant()),
+            (br, r.br_quadrant()),
+            (tr, r.tr_quadrant()),
+            (tl, r.tl_quadrant()),
+        ] {
+            // Only keep splitting if it separates some shapes, otherwise shapes
+            // covering the whole node would be split down to MAX_DEPTH.
+            if self.nodes[child].intersect.len() < count {
+                self.build(child, child_rt, depth + 1);
+            }
+        }
+    }
+
+    // Lets queries reuse cached per-shape results from earlier queries made
+    // with the same |token|, so repeating a query is fast. The caller must
+    // only use a token for one probe: results are only correct if every query
+    // made while the token is set uses an identical probe. Use a new token, or
+    // None, for a different or modified probe. Adding or removing shapes from
+    // this tree invalidates the caches. With no token, which is the default,
+    // caches are cleared at the start of every query.
+    pub fn set_probe_token(&mut self, token: Option<u64>) {
+        self.probe_token = token;
+    }
+
+    #[must_use]
+    pub fn probe_token(&self) -> Option<u64> {
+        self.probe_token
+    }
+
     fn reset_cache(&mut self) {
+        let key = self
+            .probe_token
+            .map(|probe_token| CacheKey { generation: self.generation, probe_token });
+        if key.is_none() || self.cache_key != key {
+            self.clear_cache();
+            self.cache_key = key;
+        }
+    }
+
+    // Forgets cached results from previous queries.
+    pub fn clear_cache(&mut self) {
         self.intersect_cache.clear();
         self.contain_cache.clear();
         self.dist_cache.clear();
+        self.cache_key = None;
     }
 
     pub fn intersects(&mut self, s: &Shape, q: Query) -> bool {
@@ -171,9 +426,162 @@ impl QuadTree {
         self.contain(s, q, 1, self.bounds(), 0)
     }
 
+    // Returns f64::MAX if no shapes match |q|, which includes any tree with no
+    // shapes, whether or not it is initialized.
     pub fn dist(&mut self, s: &Shape, q: Query) -> f64 {
         self.reset_cache();
-        self.distance(s, q, 1, self.bounds(), f64::MAX, 0)
+        self.distance(s, q, 1, self.bounds(), (f64::MAX, None), 0).0
+    }
+
+    // Like dist, but also returns which shape is nearest. Returns None if no
+    // shapes match |q|. Ties go to whichever shape is found first.
+    pub fn nearest(&mut self, s: &Shape, q: Query) -> Option<(ShapeIdx, f64)> {
+        self.reset_cache();
+        let (d, idx) = self.distance(s, q, 1, self.bounds(), (f64::MAX, None), 0);
+        idx.map(|idx| (idx, d))
+    }
+
+    // Like dist, but returns None if nothing matching |q| is within |cutoff|.
+    // Nodes further than |cutoff| from |s| are not searched.
+    pub fn dist_within(&mut self, s: &Shape, q: Query, cutoff: f64) -> Option<f64> {
+        self.reset_cache();
+        // Start just above |cutoff| so shapes exactly |cutoff| away are found.
+        let (d, _) = self.distance(s, q, 1, self.bounds(), (cutoff.next_up(), None), 0);
+        (d <= cutoff).then_some(d)
+    }
+
+    // Returns the |k| shapes matching |q| nearest to |s| and their distances,
+    // sorted by increasing distance, with ties broken by index. Returns fewer
+    // than |k| if fewer shapes match |q|, and nothing if |k| is zero.
+    #[must_use]
+    pub fn k_nearest(&mut self, s: &Shape, k: usize, q: Query) -> Vec<(ShapeIdx, f64)> {
+        let mut out = Vec::new();
+        self.k_nearest_into(s, k, q, &mut out);
+        out
+    }
+
+    // Like k_nearest, but clears and reuses |out| to avoid allocating.
+    pub fn k_nearest_into(
+        &mut self,
+        s: &Shape,
+        k: usize,
+        q: Query,
+        out: &mut Vec<(ShapeIdx, f64)>,
+    ) {
+        out.clear();
+        if k == 0 {
+            return;
+        }
+        self.reset_cache();
+        let mut near = KNearest::new(k);
+        self.k_near(s, q, 1, self.bounds(), 0, &mut near);
+        out.extend(near.heap.into_sorted_vec().into_iter().map(|(d, idx)| (idx, d.0)));
+    }
+
+    // Returns the indices of all shapes matching |q| that intersect |s|, in
+    // increasing order.
+    #[must_use]
+    pub fn query_intersecting(&mut self, s: &Shape, q: Query) -> Vec<ShapeIdx> {
+        let mut out = Vec::new();
+        self.query_intersecting_into(s, q, &mut out);
+        out
+    }
+
+    // Like query_intersecting, but clears and reuses |out| to avoid allocating.
+    pub fn query_intersecting_into(&mut self, s: &Shape, q: Query, out: &mut Vec<ShapeIdx>) {
+        out.clear();
+        self.reset_cache();
+        self.query_inter(s, q, 1, self.bounds(), 0, out);
+        // Shapes can be in multiple nodes.
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    // Returns all pairs of shapes matching |q| that intersect each other, with
+    // the smaller index first, in increasing order. Only shapes sharing a node,
+    // or in a node and one of its ancestors, are tested against each other.
+    #[must_use]
+    pub fn self_intersections(&mut self, q: Query) -> Vec<(ShapeIdx, ShapeIdx)> {
+        let mut tested = HashSet::default();
+        let mut out = Vec::new();
+        let mut ancestors = Vec::new();
+        self.self_inter(q, 1, &mut ancestors, &mut tested, &mut out);
+        out.sort_unstable();
+        out
+    }
+
+    fn self_inter(
+        &self,
+        q: Query,
+        idx: NodeIdx,
+        ancestors: &mut Vec<ShapeIdx>,
+        tested: &mut HashSet<(ShapeIdx, ShapeIdx)>,
+        out: &mut Vec<(ShapeIdx, ShapeIdx)>,
+    ) {
+        let node = &self.nodes[idx];
+        let own: Vec<_> = node
+            .intersect
+            .iter()
+            .map(|v| v.shape_idx)
+            .filter(|&v| matches_query(&self.shapes[v], q))
+            .collect();
+        for (i, &a) in own.iter().enumerate() {
+            for &b in own[i + 1..].iter().chain(ancestors.iter()) {
+                let pair = (a.min(b), a.max(b));
+                // Shapes can be in multiple nodes, so only test each pair once.
+                if tested.insert(pair)
+                    && self.shapes[a].shape().intersects_shape(self.shapes[b].shape())
+                {
+                    out.push(pair);
+                }
+            }
+        }
+
+        if node.bl != NO_NODE {
+            let len = ancestors.len();
+            ancestors.extend_from_slice(&own);
+            for child in [node.bl, node.br, node.tr, node.tl] {
+                self.self_inter(q, child, ancestors, tested, out);
+            }
+            ancestors.truncate(len);
+        }
+    }
+
+    fn query_inter(
+        &mut self,
+        s: &Shape,
+        q: Query,
+        idx: NodeIdx,
+        r: Rt,
+        depth: usize,
+        out: &mut Vec<ShapeIdx>,
+    ) {
+        if !s.intersects_shape(&r.shape()) {
+            return;
+        }
+
+        // Shapes containing this node must intersect |s|.
+        for &contain in &self.nodes[idx].contain {
+            if matches_query(&self.shapes[contain], q) {
+                out.push(contain);
+            }
+        }
+
+        let Node { bl, br, tr, tl, .. } = self.nodes[idx];
+        if bl != NO_NODE {
+            self.query_inter(s, q, bl, r.bl_quadrant(), depth + 1, out);
+            self.query_inter(s, q, br, r.br_quadrant(), depth + 1, out);
+            self.query_inter(s, q, tr, r.tr_quadrant(), depth + 1, out);
+            self.query_inter(s, q, tl, r.tl_quadrant(), depth + 1, out);
+        }
+
+        for inter in &mut self.nodes[idx].intersect {
+            inter.tests += 1;
+            if cached_intersects(&self.shapes, &mut self.intersect_cache, inter.shape_idx, s, q) {
+                out.push(inter.shape_idx);
+            }
+        }
+        self.maybe_push_down(idx, r, depth);
     }
 
     fn inter(&mut self, s: &Shape, q: Query, idx: NodeIdx, r: Rt, depth: usize) -> bool {
@@ -291,62 +699,104 @@ impl QuadTree {
         q: Query,
         idx: NodeIdx,
         r: Rt,
-        mut best: f64,
+        mut best: (f64, Option<ShapeIdx>),
         depth: usize,
-    ) -> f64 {
+    ) -> (f64, Option<ShapeIdx>) {
         // If bounds intersects |s| and there is something that contains the
         // bounds, then the distance is zero (intersecting a shape).
         let b = s.bounds();
         if r.contains_rt(&b) {
             for &contain in &self.nodes[idx].contain {
                 if matches_query(&self.shapes[contain], q) {
-                    return 0.0;
+                    return (0.0, Some(contain));
                 }
             }
         }
 
-        // Traverse children in order of shortest AABB distance. This optimises the
-        // good case where a small object goes directly to objects near it.
-        let mut children: SmallVec<[(f64, usize, Rt); 4]> = smallvec![];
-        if self.nodes[idx].bl != NO_NODE {
-            let child_rt = r.bl_quadrant();
-            children.push((rt_rt_dist(&child_rt, &b), self.nodes[idx].bl, child_rt));
-        }
-        if self.nodes[idx].br != NO_NODE {
-            let child_rt = r.br_quadrant();
-            children.push((rt_rt_dist(&child_rt, &b), self.nodes[idx].br, child_rt));
-        }
-        if self.nodes[idx].tr != NO_NODE {
-            let child_rt = r.tr_quadrant();
-            children.push((rt_rt_dist(&child_rt, &b), self.nodes[idx].tr, child_rt));
-        }
-        if self.nodes[idx].tl != NO_NODE {
-            let child_rt = r.tl_quadrant();
-            children.push((rt_rt_dist(&child_rt, &b), self.nodes[idx].tl, child_rt));
-        }
-        children.sort_unstable_by_key(|v| OrderedFloat(v.0));
-
         // If we can't do better than the current best in this node, give up.
-        for (lower_bound, child_idx, child_rt) in children {
+        for (lower_bound, child_idx, child_rt) in self.children_by_dist(idx, r, &b) {
             // Distance must be greater than lower bound, and this is sorted by
             // lower bound dist, so early exit.
-            if best < lower_bound {
+            if best.0 < lower_bound {
                 break;
             }
-            best = best.min(self.distance(s, q, child_idx, child_rt, best, depth + 1));
+            best = self.distance(s, q, child_idx, child_rt, best, depth + 1);
         }
 
         // Check shapes that intersect this node:
         for inter in &mut self.nodes[idx].intersect {
             inter.tests += 1;
             let d = cached_dist(&self.shapes, &mut self.dist_cache, inter.shape_idx, s, q);
-            best = best.min(d);
+            if d < best.0 {
+                best = (d, Some(inter.shape_idx));
+            }
         }
         self.maybe_push_down(idx, r, depth);
 
         best
     }
 
+    fn k_near(
+        &mut self,
+        s: &Shape,
+        q: Query,
+        idx: NodeIdx,
+        r: Rt,
+        depth: usize,
+        out: &mut KNearest,
+    ) {
+        // As in distance, shapes containing the node contain |s|'s bounds, so
+        // they are at distance zero.
+        let b = s.bounds();
+        if r.contains_rt(&b) {
+            for &contain in &self.nodes[idx].contain {
+                if matches_query(&self.shapes[contain], q) {
+                    out.push(contain, 0.0);
+                }
+            }
+        }
+
+        for (lower_bound, child_idx, child_rt) in self.children_by_dist(idx, r, &b) {
+            if out.bound() < lower_bound {
+                break;
+            }
+            self.k_near(s, q, child_idx, child_rt, depth + 1, out);
+        }
+
+        for inter in &mut self.nodes[idx].intersect {
+            if out.seen.contains(&inter.shape_idx) {
+                continue;
+            }
+            inter.tests += 1;
+            let d = cached_dist(&self.shapes, &mut self.dist_cache, inter.shape_idx, s, q);
+            // Shapes not matching |q| are f64::MAX away.
+            if d < f64::MAX {
+                out.push(inter.shape_idx, d);
+            }
+        }
+        self.maybe_push_down(idx, r, depth);
+    }
+
+    // Returns the children of node |idx| covering |r| with the distance from
+    // each to |b|, nearest first. Traversing children in this order optimises
+    // the good case where a small object goes directly to objects near it.
+    fn children_by_dist(&self, idx: NodeIdx, r: Rt, b: &Rt) -> SmallVec<[(f64, NodeIdx, Rt); 4]> {
+        let node = &self.nodes[idx];
+        let mut children: SmallVec<[(f64, NodeIdx, Rt); 4]> = smallvec![];
+        for (child_idx, child_rt) in [
+            (node.bl, r.bl_quadrant()),
+            (node.br, r.br_quadrant()),
+            (node.tr, r.tr_quadrant()),
+            (node.tl, r.tl_quadrant()),
+        ] {
+            if child_idx != NO_NODE {
+                children.push((bounds_dist_lower_bound(&child_rt, b), child_idx, child_rt));
+            }
+        }
+        children.sort_unstable_by_key(|v| OrderedFloat(v.0));
+        children
+    }
+
     // Move any shapes to child nodes, if necessary.
     fn maybe_push_down(&mut self, idx: NodeIdx, r: Rt, depth: usize) {
         if depth > MAX_DEPTH {
@@ -396,16 +846,55 @@ impl QuadTree {
     }
 }
 
+// Builds the subtree for the node covering |r| which holds |shapes|, splitting
+// like QuadTree::build. Returns the subtree's nodes indexed from its root at
+// zero.
+#[cfg(feature = "rayon")]
+fn par_build(shape_bounds: &[Rt], shapes: Vec<ShapeIdx>, r: Rt, depth: usize) -> Vec<Node> {
+    let count = shapes.len();
+    if count <= TEST_THRESHOLD || depth > MAX_DEPTH {
+        return vec![Node::leaf(shapes)];
+    }
+    let children: Vec<Vec<Node>> = r
+        .quadrants()
+        .into_par_iter()
+        .map(|quad| {
+            let inside: Vec<_> =
+                shapes.iter().copied().filter(|&i| shape_bounds[i].intersects(&quad)).collect();
+            // Only keep splitting if it separates some shapes.
+            if inside.len() < count {
+                par_build(shape_bounds, inside, quad, depth + 1)
+            } else {
+                vec![Node::leaf(inside)]
+            }
+        })
+        .collect();
+    let mut nodes = vec![Node::default()];
+    let mut child_idx = [NO_NODE; 4];
+    for (idx, child) in child_idx.iter_mut().zip(children) {
+        *idx = nodes.len();
+        let base = nodes.len();
+        nodes.extend(child.into_iter().map(|n| n.offset(base)));
+    }
+    [nodes[0].bl, nodes[0].br, nodes[0].tr, nodes[0].tl] = child_idx;
+    nodes
+}
+
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
     use approx::assert_relative_eq;
     use pretty_assertions::assert_eq;
     use rand::prelude::SmallRng;
     use rand::{Rng, SeedableRng};
+    use rust_dense_bitset::DenseBitSet;
 
     use super::*;
-    use crate::geom::qt::query::ALL;
-    use crate::primitive::{circ, poly, pt, rt, tri};
+    use crate::geom::qt::query::{ALL, Kinds, KindsQuery, Tag, TagQuery};
+    use crate::primitive::compound::Compound;
+    use crate::primitive::{DynShape, circ, poly, pt, rt, seg, tri};
 
     #[test]
     fn test_quadtree_tri() {
@@ -420,6 +909,119 @@ mod tests {
         assert!(qt.intersects(&rt(3.0, 3.0, 4.0, 4.0).shape(), ALL));
     }
 
+    #[test]
+    fn test_quadtree_from_shapes() {
+        let shapes = vec![circ(pt(0.0, 0.0), 1.0).shape(), rt(3.0, 0.0, 4.0, 1.0).shape()];
+        let mut qt = QuadTree::from_shapes(&shapes);
+        assert_eq!(qt.shape_count(), 2);
+        assert!(qt.intersects(&pt(3.5, 0.5).shape(), ALL));
+        assert!(!qt.intersects(&pt(2.0, 0.0).shape(), ALL));
+        assert_relative_eq!(qt.dist(&pt(2.0, 0.0).shape(), ALL), 1.0);
+
+        let c = Compound::from_plain_shapes(&shapes);
+        assert!(c.contains(&pt(0.5, 0.0).shape(), ALL));
+        assert_eq!(c.tight_bounds(), Some(rt(-1.0, -1.0, 4.0, 1.0)));
+    }
+
+    #[test]
+    fn test_quadtree_dist_within() {
+        let shapes: Vec<_> =
+            (0..20).map(|i| circ(pt(f64::from(i) * 10.0, 0.0), 1.0).shape()).collect();
+        let mut qt = QuadTree::from_shapes(&shapes);
+        for _ in 0..TEST_THRESHOLD {
+            let probe = pt(50.0, 5.0).shape();
+            assert_relative_eq!(qt.dist_within(&probe, ALL, 4.0).unwrap(), 4.0);
+            assert_relative_eq!(qt.dist_within(&probe, ALL, 100.0).unwrap(), qt.dist(&probe, ALL));
+            assert_eq!(qt.dist_within(&probe, ALL, 3.9), None);
+        }
+        assert_eq!(QuadTree::empty().dist_within(&pt(0.0, 0.0).shape(), ALL, 1.0), None);
+    }
+
+    #[test]
+    fn test_quadtree_nearest() {
+        let mut qt = QuadTree::from_shapes(&[
+            rt(0.0, 0.0, 2.0, 2.0).shape(),
+            rt(10.0, 0.0, 12.0, 2.0).shape(),
+        ]);
+        for _ in 0..TEST_THRESHOLD {
+            let (idx, d) = qt.nearest(&pt(7.0, 1.0).shape(), ALL).unwrap();
+            assert_eq!(idx, 1);
+            assert_relative_eq!(d, 3.0);
+            assert_eq!(qt.nearest(&pt(1.0, 1.0).shape(), ALL), Some((0, 0.0)));
+        }
+        // Removed shapes are never returned.
+        qt.remove_shape(1);
+        let (idx, d) = qt.nearest(&pt(11.0, 1.0).shape(), ALL).unwrap();
+        assert_eq!(idx, 0);
+        assert_relative_eq!(d, 9.0);
+        qt.remove_shape(0);
+        assert_eq!(qt.nearest(&pt(11.0, 1.0).shape(), ALL), None);
+        assert_eq!(QuadTree::empty().nearest(&pt(0.0, 0.0).shape(), ALL), None);
+    }
+
+    #[test]
+    fn test_quadtree_k_nearest() {
+        let shapes: Vec<_> =
+            (0..20).map(|i| circ(pt(f64::from(i) * 10.0, 0.0), 1.0).shape()).collect();
+        let mut qt = QuadTree::from_shapes(&shapes);
+        let probe = pt(52.0, 0.0).shape();
+        for _ in 0..TEST_THRESHOLD {
+            let near = qt.k_nearest(&probe, 3, ALL);
+            let idxs: Vec<_> = near.iter().map(|v| v.0).collect();
+            assert_eq!(idxs, [5, 6, 4]);
+            assert_relative_eq!(near[0].1, 1.0);
+            assert_relative_eq!(near[1].1, 7.0);
+            assert_relative_eq!(near[2].1, 11.0);
+            assert_eq!(qt.k_nearest(&probe, 1, ALL), [qt.nearest(&probe, ALL).unwrap()]);
+        }
+        // Matches sorting all shapes by distance.
+        let all = qt.k_nearest(&probe, 100, ALL);
+        assert_eq!(all.len(), 20);
+        assert!(all.is_sorted_by(|a, b| a.1 <= b.1));
+        for (idx, d) in all {
+            assert_relative_eq!(d, qt.shapes()[idx].shape().dist_to_shape(&probe));
+        }
+        // Shapes inside the probe are at distance zero.
+        let big = circ(pt(50.0, 0.0), 12.0).shape();
+        let near: Vec<_> = qt.k_nearest(&big, 3, ALL).iter().map(|v| v.0).collect();
+        assert_eq!(near, [4, 5, 6]);
+        assert!(qt.k_nearest(&probe, 0, ALL).is_empty());
+        assert!(QuadTree::empty().k_nearest(&probe, 3, ALL).is_empty());
+        // Ties at the k-th place go to the lowest index, even if it is found
+        // last. Shape 0 straddles the centre so stays in the root node, which
+        // is searched after its children.
+        let mut shapes = vec![rt(-1.0, 10.0, 1.0, 11.0).shape()];
+        for (x, y) in [(6.0, -8.0), (-6.0, -8.0), (8.0, -6.0), (20.0, 20.0), (-20.0, -20.0)] {
+            shapes.push(pt(x, y).shape());
+        }
+        let mut ties = QuadTree::from_shapes(&shapes);
+        for _ in 0..TEST_THRESHOLD {
+            assert_eq!(ties.k_nearest(&pt(0.0, 0.0).shape(), 1, ALL), [(0, 10.0)]);
+            assert_eq!(ties.k_nearest(&pt(0.0, 0.0).shape(), 2, ALL), [(0, 10.0), (1, 10.0)]);
+        }
+        // Reusing a buffer gives the same result, and drops old contents.
+        let mut out = vec![(usize::MAX, 0.0); 5];
+        qt.k_nearest_into(&probe, 3, ALL, &mut out);
+        assert_eq!(out, qt.k_nearest(&probe, 3, ALL));
+        qt.k_nearest_into(&probe, 0, ALL, &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_quadtree_tri_mesh() {
+        // A strip of triangles along the x axis.
+        let mut shapes = Vec::new();
+        for i in 0..10 {
+            let x = f64::from(i) * 2.0;
+            shapes.push(ShapeInfo::anon(tri(pt(x, 0.0), pt(x + 1.0, 0.0), pt(x, 1.0)).shape()));
+        }
+        let mut qt = QuadTree::new(shapes);
+        for _ in 0..TEST_THRESHOLD {
+            let probe = tri(pt(6.0, 3.0), pt(7.0, 3.0), pt(6.5, 4.0)).shape();
+            assert_relative_eq!(qt.dist(&probe, ALL), 2.0);
+        }
+    }
+
     #[test]
     fn test_quadtree_poly() {
         let mut qt = QuadTree::new(vec![ShapeInfo::anon(
@@ -438,6 +1040,429 @@ mod tests {
         assert_relative_eq!(qt.dist(&pt(5.0, 1.0).shape(), ALL), 1.0);
     }
 
+    fn total_tests(qt: &QuadTree) -> usize {
+        qt.nodes.iter().flat_map(|n| &n.intersect).map(|v| v.tests).sum()
+    }
+
+    #[test]
+    fn test_quadtree_contains_pt_prunes() {
+        let shapes = (0..10)
+            .flat_map(|x| (0..10).map(move |y| (f64::from(x), f64::from(y))))
+            .map(|(x, y)| ShapeInfo::anon(rt(x + 0.1, y + 0.1, x + 0.9, y + 0.9).shape()))
+            .collect();
+        let mut qt = QuadTree::new(shapes);
+        // Push shapes down the tree.
+        for _ in 0..=MAX_DEPTH * TEST_THRESHOLD {
+            qt.contains(&pt(0.5, 0.5).shape(), ALL);
+            qt.contains(&pt(9.5, 9.5).shape(), ALL);
+            qt.contains(&pt(5.5, 3.5).shape(), ALL);
+            qt.contains(&pt(5.95, 3.5).shape(), ALL);
+        }
+
+        // Only shapes in nodes containing the point should be tested, rather than
+        // all 100 shapes.
+        let before = total_tests(&qt);
+        assert!(qt.contains(&pt(5.5, 3.5).shape(), ALL));
+        let after = total_tests(&qt);
+        assert!(after - before < 10, "{}", after - before);
+        assert!(!qt.contains(&pt(5.95, 3.5).shape(), ALL));
+        assert!(total_tests(&qt) - after < 10, "{}", total_tests(&qt) - after);
+    }
+
+    #[test]
+    fn test_quadtree_query_intersecting() {
+        let mut r = SmallRng::seed_from_u64(0);
+        let shapes: Vec<_> = (0..200)
+            .map(|_| {
+                let p = pt(r.gen_range(0.0..100.0), r.gen_range(0.0..100.0));
+                ShapeInfo::anon(circ(p, r.gen_range(0.5..3.0)).shape())
+            })
+            .collect();
+        let mut qt = QuadTree::new(shapes.clone());
+        let mut out = vec![usize::MAX; 3];
+        for _ in 0..50 {
+            let p = pt(r.gen_range(0.0..100.0), r.gen_range(0.0..100.0));
+            let probe = rt(p.x, p.y, p.x + 10.0, p.y + 5.0).shape();
+            let expected: Vec<_> = shapes
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.shape().intersects_shape(&probe))
+                .map(|(i, _)| i)
+                .collect();
+            let got = qt.query_intersecting(&probe, ALL);
+            assert_eq!(got, expected);
+            assert_eq!(got.is_empty(), !qt.intersects(&probe, ALL));
+            // Reusing a buffer gives the same result, and drops old contents.
+            qt.query_intersecting_into(&probe, ALL, &mut out);
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn test_quadtree_query_intersecting_rects() {
+        // Overlapping rects, with a large one spanning many nodes after
+        // push-down.
+        let mut qt = QuadTree::from_shapes(&[
+            rt(0.0, 0.0, 100.0, 100.0).shape(),
+            rt(10.0, 10.0, 30.0, 30.0).shape(),
+            rt(20.0, 20.0, 40.0, 40.0).shape(),
+            rt(70.0, 70.0, 90.0, 90.0).shape(),
+            rt(26.0, 5.0, 35.0, 14.0).shape(),
+        ]);
+        let selection = rt(15.0, 15.0, 25.0, 25.0).shape();
+        for _ in 0..TEST_THRESHOLD * 2 {
+            assert_eq!(qt.query_intersecting(&selection, ALL), [0, 1, 2]);
+            assert_eq!(
+                qt.query_intersecting(&rt(28.0, 12.0, 32.0, 22.0).shape(), ALL),
+                [0, 1, 2, 4]
+            );
+            assert_eq!(qt.query_intersecting(&pt(80.0, 80.0).shape(), ALL), [0, 3]);
+        }
+        assert!(qt.query_intersecting(&pt(200.0, 200.0).shape(), ALL).is_empty());
+        // Removed shapes are not returned.
+        qt.remove_shape(0);
+        assert_eq!(qt.query_intersecting(&selection, ALL), [1, 2]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_quadtree_par_bulk_load() {
+        let mut r = SmallRng::seed_from_u64(0);
+        let shapes: Vec<_> = (0..2000)
+            .map(|i| {
+                let p = pt(r.gen_range(0.0..1000.0), r.gen_range(0.0..1000.0));
+                let s = if i % 2 == 0 {
+                    circ(p, r.gen_range(0.5..10.0)).shape()
+                } else {
+                    rt(p.x, p.y, p.x + r.gen_range(0.5..20.0), p.y + r.gen_range(0.5..20.0)).shape()
+                };
+                ShapeInfo::anon(s)
+            })
+            .collect();
+        let mut serial = QuadTree::new(shapes.clone());
+        serial.optimize();
+        let mut par = QuadTree::par_bulk_load(shapes);
+        assert_eq!(par.bounds(), serial.bounds());
+        assert!(par.rts().len() > 1);
+        for _ in 0..200 {
+            let p = pt(r.gen_range(0.0..1000.0), r.gen_range(0.0..1000.0));
+            for probe in [p.shape(), rt(p.x, p.y, p.x + 30.0, p.y + 10.0).shape()] {
+                assert_eq!(
+                    par.query_intersecting(&probe, ALL),
+                    serial.query_intersecting(&probe, ALL)
+                );
+                assert_eq!(par.intersects(&probe, ALL), serial.intersects(&probe, ALL));
+                assert_eq!(par.contains(&probe, ALL), serial.contains(&probe, ALL));
+                assert_relative_eq!(par.dist(&probe, ALL), serial.dist(&probe, ALL));
+            }
+        }
+        assert_eq!(QuadTree::par_bulk_load(vec![]).shape_count(), 0);
+    }
+
+    #[test]
+    fn test_quadtree_optimize() {
+        let mut qt = QuadTree::with_bounds(&rt(0.0, 0.0, 10.0, 10.0));
+        for x in 0..10 {
+            for y in 0..10 {
+                let (x, y) = (f64::from(x), f64::from(y));
+                qt.add_shape(ShapeInfo::anon(rt(x + 0.1, y + 0.1, x + 0.9, y + 0.9).shape()))
+                    .unwrap();
+            }
+        }
+        qt.remove_shape(0);
+        assert_eq!(qt.rts().len(), 1);
+
+        qt.optimize();
+        assert!(qt.rts().len() > 1);
+        // Removed shapes stay removed.
+        assert!(!qt.contains(&pt(0.5, 0.5).shape(), ALL));
+        let before = total_tests(&qt);
+        assert!(qt.contains(&pt(5.5, 3.5).shape(), ALL));
+        assert!(total_tests(&qt) - before < 10, "{}", total_tests(&qt) - before);
+        assert!(!qt.contains(&pt(5.95, 3.5).shape(), ALL));
+        assert_relative_eq!(qt.dist(&pt(5.95, 3.5).shape(), ALL), 0.05, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_quadtree_self_intersections() {
+        // A cluster of overlapping rects in one corner, plus disjoint ones spread
+        // out so the tree splits.
+        let mut shapes = vec![
+            ShapeInfo::anon(rt(0.0, 0.0, 2.0, 2.0).shape()),
+            ShapeInfo::anon(rt(1.0, 1.0, 3.0, 3.0).shape()),
+            ShapeInfo::anon(rt(2.5, 0.0, 3.5, 1.5).shape()),
+            ShapeInfo::anon(rt(1.5, 1.5, 1.8, 1.8).shape()),
+        ];
+        for x in 0..8 {
+            for y in 0..8 {
+                let (x, y) = (f64::from(x) * 4.0 + 8.0, f64::from(y) * 4.0 + 8.0);
+                shapes.push(ShapeInfo::anon(rt(x, y, x + 1.0, y + 1.0).shape()));
+            }
+        }
+        let mut expected = Vec::new();
+        for i in 0..shapes.len() {
+            for j in i + 1..shapes.len() {
+                if shapes[i].shape().intersects_shape(shapes[j].shape()) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(expected, [(0, 1), (0, 3), (1, 2), (1, 3)]);
+
+        let mut qt = QuadTree::new(shapes);
+        assert_eq!(qt.self_intersections(ALL), expected);
+        qt.optimize();
+        assert!(qt.rts().len() > 1);
+        assert_eq!(qt.self_intersections(ALL), expected);
+        qt.remove_shape(1);
+        assert_eq!(qt.self_intersections(ALL), [(0, 3)]);
+    }
+
+    // Horizontal strip between |b| and |t|.
+    #[derive(Debug, Clone)]
+    struct Strip {
+        b: f64,
+        t: f64,
+    }
+
+    impl DynShape for Strip {
+        fn dyn_bounds(&self) -> Rt {
+            // Strips are infinite, but the quadtree needs finite bounds.
+            rt(-1000.0, self.b, 1000.0, self.t)
+        }
+
+        fn dyn_intersects_shape(&self, s: &Shape) -> bool {
+            let r = s.bounds();
+            r.b() <= self.t && r.t() >= self.b
+        }
+
+        fn dyn_contains_shape(&self, s: &Shape) -> bool {
+            let r = s.bounds();
+            r.b() >= self.b && r.t() <= self.t
+        }
+
+        fn dyn_dist_to_shape(&self, s: &Shape) -> f64 {
+            let r = s.bounds();
+            (r.b() - self.t).max(self.b - r.t()).max(0.0)
+        }
+
+        fn clone_box(&self) -> Box<dyn DynShape> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_quadtree_custom_shape() {
+        let strip = Shape::Custom(Box::new(Strip { b: 4.0, t: 5.0 }));
+        let mut shapes: Vec<_> = (0..10)
+            .map(|i| {
+                ShapeInfo::anon(
+                    rt(f64::from(i) * 10.0, 0.0, f64::from(i) * 10.0 + 1.0, 1.0).shape(),
+                )
+            })
+            .collect();
+        shapes.push(ShapeInfo::anon(strip.clone()));
+        let mut qt = QuadTree::new(shapes);
+
+        for _ in 0..=MAX_DEPTH * TEST_THRESHOLD {
+            assert!(qt.intersects(&pt(500.0, 4.5).shape(), ALL));
+            assert!(qt.intersects(&pt(20.5, 0.5).shape(), ALL));
+            assert!(!qt.intersects(&pt(500.0, 6.0).shape(), ALL));
+            assert!(!qt.intersects(&pt(15.0, 2.0).shape(), ALL));
+        }
+        assert!(qt.contains(&rt(-3.0, 4.2, 3.0, 4.8).shape(), ALL));
+        assert!(!qt.contains(&rt(-3.0, 4.2, 3.0, 5.8).shape(), ALL));
+        assert_eq!(qt.query_intersecting(&rt(15.0, 0.5, 35.0, 4.5).shape(), ALL), [2, 3, 10]);
+        assert_relative_eq!(qt.dist(&pt(15.0, 2.0).shape(), ALL), 2.0);
+
+        // Built in shapes test against custom shapes too.
+        assert!(circ(pt(0.0, 6.0), 1.5).shape().intersects_shape(&strip));
+        assert!(!circ(pt(0.0, 6.0), 0.5).shape().intersects_shape(&strip));
+        assert_relative_eq!(rt(0.0, 7.0, 1.0, 8.0).shape().dist_to_shape(&strip), 2.0);
+    }
+
+    // Wraps a probe shape, counting how many times it is tested.
+    #[derive(Debug, Clone)]
+    struct Counted {
+        s: Shape,
+        tests: Rc<Cell<usize>>,
+    }
+
+    impl DynShape for Counted {
+        fn dyn_bounds(&self) -> Rt {
+            self.s.bounds()
+        }
+
+        fn dyn_intersects_shape(&self, s: &Shape) -> bool {
+            self.tests.set(self.tests.get() + 1);
+            self.s.intersects_shape(s)
+        }
+
+        fn dyn_contains_shape(&self, s: &Shape) -> bool {
+            self.tests.set(self.tests.get() + 1);
+            self.s.contains_shape(s)
+        }
+
+        fn dyn_dist_to_shape(&self, s: &Shape) -> f64 {
+            self.tests.set(self.tests.get() + 1);
+            self.s.dist_to_shape(s)
+        }
+
+        fn clone_box(&self) -> Box<dyn DynShape> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_quadtree_cache() {
+        let mut qt = QuadTree::new(
+            (0..8)
+                .map(|i| ShapeInfo::anon(rt(f64::from(i), 0.0, f64::from(i) + 0.5, 1.0).shape()))
+                .collect(),
+        );
+        let tests = Rc::new(Cell::new(0));
+        let probe = Shape::Custom(Box::new(Counted {
+            s: circ(pt(3.0, 5.0), 1.0).shape(),
+            tests: Rc::clone(&tests),
+        }));
+
+        // Without a token, nothing is reused.
+        let d = qt.dist(&probe, ALL);
+        let first = tests.get();
+        assert!(first > 0);
+        assert_relative_eq!(qt.dist(&probe, ALL), d);
+        assert_eq!(tests.get(), 2 * first);
+
+        // The same probe again with a token reuses the cached results.
+        qt.set_probe_token(Some(1));
+        assert_relative_eq!(qt.dist(&probe, ALL), d);
+        let first = tests.get();
+        assert_relative_eq!(qt.dist(&probe, ALL), d);
+        assert_eq!(tests.get(), first);
+
+        // Changing the tree invalidates the cache.
+        qt.add_shape(ShapeInfo::anon(rt(3.0, 3.0, 4.0, 3.5).shape())).unwrap();
+        assert_relative_eq!(qt.dist(&probe, ALL), 0.5);
+        assert!(tests.get() > first);
+
+        // A different probe with a different token doesn't see stale results.
+        qt.set_probe_token(Some(2));
+        assert_relative_eq!(qt.dist(&circ(pt(3.0, 2.0), 0.5).shape(), ALL), 0.5);
+    }
+
+    #[test]
+    fn test_quadtree_cache_reused_slot() {
+        // Each probe is built in the same stack slot and has the same bounds,
+        // so must not be mistaken for the previous one.
+        let mut qt = QuadTree::from_shapes(&[rt(0.0, 0.0, 1.0, 1.0).shape()]);
+        let mut dists = vec![];
+        for (a, b) in [(pt(1.5, 0.5), pt(2.5, 2.5)), (pt(1.5, 2.5), pt(2.5, 0.5))] {
+            let probe = seg(a, b).shape();
+            assert!(!qt.intersects(&probe, ALL));
+            dists.push(qt.dist(&probe, ALL));
+        }
+        assert_relative_eq!(dists[0], 0.5);
+        assert_relative_eq!(dists[1], 1.25_f64.sqrt());
+    }
+
+    #[test]
+    fn test_quadtree_retain() {
+        let mut qt = QuadTree::new(vec![
+            ShapeInfo::new(rt(0.0, 0.0, 2.0, 2.0).shape(), Tag(1), Kinds(DenseBitSet::new())),
+            ShapeInfo::new(circ(pt(1.0, 1.0), 0.5).shape(), Tag(2), Kinds(DenseBitSet::new())),
+            ShapeInfo::new(rt(5.0, 5.0, 6.0, 6.0).shape(), Tag(1), Kinds(DenseBitSet::new())),
+        ]);
+        let tag1 = Query(TagQuery::Tag(Tag(1)), KindsQuery::All);
+        // Push shapes down into child nodes before removing.
+        for _ in 0..=TEST_THRESHOLD {
+            assert!(qt.intersects(&pt(1.0, 1.0).shape(), tag1));
+            assert!(qt.intersects(&pt(5.5, 5.5).shape(), tag1));
+        }
+
+        qt.retain(|s| s.tag() != Tag(1));
+        assert!(!qt.intersects(&pt(1.0, 1.0).shape(), tag1));
+        assert!(!qt.intersects(&pt(5.5, 5.5).shape(), ALL));
+        assert!(qt.intersects(&pt(1.0, 1.0).shape(), ALL));
+        assert!(!qt.intersects(&pt(0.1, 0.1).shape(), ALL));
+
+        // Freed slots are reused.
+        let idxs = qt.add_shape(ShapeInfo::anon(pt(5.5, 5.5).shape())).unwrap();
+        assert_eq!(idxs, vec![2]);
+        assert!(qt.intersects(&pt(5.5, 5.5).shape(), ALL));
+    }
+
+    #[test]
+    fn test_quadtree_bounds_policy() {
+        let mut qt = QuadTree::with_bounds(&rt(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(qt.bounds_policy(), BoundsPolicy::Fixed);
+        assert!(qt.add_shape(ShapeInfo::anon(rt(1.0, 1.0, 2.0, 2.0).shape())).is_ok());
+        assert_eq!(
+            qt.add_shape(ShapeInfo::anon(circ(pt(10.0, 10.0), 1.0).shape())),
+            Err(Error::OutOfBounds)
+        );
+        assert_eq!(qt.bounds(), rt(0.0, 0.0, 10.0, 10.0));
+        assert!(!qt.intersects(&pt(10.5, 10.5).shape(), ALL));
+
+        qt.set_bounds_policy(BoundsPolicy::Rebuild);
+        assert!(qt.add_shape(ShapeInfo::anon(circ(pt(10.0, 10.0), 1.0).shape())).is_ok());
+        assert_eq!(qt.bounds(), rt(1.0, 1.0, 11.0, 11.0));
+        assert!(qt.intersects(&pt(10.5, 10.5).shape(), ALL));
+
+        // Rebuilding keeps the probe token, and queries still see new shapes.
+        qt.set_probe_token(Some(7));
+        let probe = pt(20.5, 20.5).shape();
+        assert!(!qt.intersects(&probe, ALL));
+        assert!(qt.add_shape(ShapeInfo::anon(circ(pt(20.0, 20.0), 1.0).shape())).is_ok());
+        assert_eq!(qt.bounds(), rt(1.0, 1.0, 21.0, 21.0));
+        assert_eq!(qt.probe_token(), Some(7));
+        assert!(qt.intersects(&probe, ALL));
+
+        let mut qt = QuadTree::empty();
+        assert_eq!(qt.bounds_policy(), BoundsPolicy::Rebuild);
+        assert!(qt.add_shape(ShapeInfo::anon(rt(1.0, 1.0, 2.0, 2.0).shape())).is_ok());
+        assert_eq!(qt.bounds(), rt(1.0, 1.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn test_quadtree_empty() {
+        let p = pt(1.0, 1.0).shape();
+        let mut bounded = QuadTree::with_bounds(&rt(0.0, 0.0, 10.0, 10.0));
+        let mut unbounded = QuadTree::empty();
+        assert!(bounded.is_initialized());
+        assert!(!unbounded.is_initialized());
+        for qt in [&mut bounded, &mut unbounded] {
+            assert_eq!(qt.shape_count(), 0);
+            assert!(!qt.intersects(&p, ALL));
+            assert!(!qt.contains(&p, ALL));
+            assert_relative_eq!(qt.dist(&p, ALL), f64::MAX);
+        }
+
+        let idxs = unbounded.add_shape(ShapeInfo::anon(rt(0.0, 0.0, 2.0, 2.0).shape())).unwrap();
+        assert!(unbounded.is_initialized());
+        assert_eq!(unbounded.shape_count(), 1);
+        unbounded.remove_shape(idxs[0]);
+        assert!(unbounded.is_initialized());
+        assert_eq!(unbounded.shape_count(), 0);
+        assert_relative_eq!(unbounded.dist(&p, ALL), f64::MAX);
+    }
+
+    #[test]
+    fn test_quadtree_remove_twice() {
+        let mut qt = QuadTree::from_shapes(&[
+            rt(0.0, 0.0, 1.0, 1.0).shape(),
+            rt(2.0, 0.0, 3.0, 1.0).shape(),
+        ]);
+        qt.remove_shape(0);
+        qt.remove_shape(0);
+        assert_eq!(qt.shape_count(), 1);
+        // The freed slot is only handed out once.
+        let a = qt.add_shape(ShapeInfo::anon(rt(0.0, 2.0, 1.0, 3.0).shape())).unwrap();
+        let b = qt.add_shape(ShapeInfo::anon(rt(2.0, 2.0, 3.0, 3.0).shape())).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(qt.shape_count(), 3);
+        assert_eq!(qt.live_shapes().count(), 3);
+    }
+
     #[test]
     fn test_quadtree_poly2() {
         let poly = poly(&[
@@ -12,6 +12,18 @@ pub struct Tag(pub usize);
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Kinds(pub DenseBitSet);
 
+impl Kinds {
+    // Creates a kinds bitmask with each of |kinds| set. Kinds must be less
+    // than 64.
+    pub fn of(kinds: &[usize]) -> Self {
+        let bits = kinds.iter().fold(0, |bits, &k| {
+            assert!(k < 64, "kind {k} out of range");
+            bits | 1 << k
+        });
+        Self(DenseBitSet::from_integer(bits))
+    }
+}
+
 pub const NO_TAG: Tag = Tag(usize::MAX);
 pub const ALL: Query = Query(TagQuery::All, KindsQuery::All);
 
@@ -84,11 +96,25 @@ impl ShapeInfo {
     }
 }
 
+// Returns the index of the shape in |shapes| matching |q| that is closest to
+// |probe|, and the distance to it, or None if no shapes match. Ties go to the
+// lowest index. This is a linear scan, for collections too small to be worth
+// building a quadtree for.
+#[must_use]
+pub fn filtered_nearest(shapes: &[ShapeInfo], probe: &Shape, q: Query) -> Option<(usize, f64)> {
+    shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| matches_query(s, q))
+        .map(|(idx, s)| (idx, s.shape().dist_to_shape(probe)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
 // Split paths up so they are spread out more.
 // Split compound shapes up.
 pub fn decompose_shape(s: ShapeInfo) -> Vec<ShapeInfo> {
     let shapes = match s.shape {
-        Shape::Compound(s) => s.quadtree().shapes().iter().map(|v| v.shape.clone()).collect(),
+        Shape::Compound(s) => s.quadtree().live_shapes().map(|v| v.shape.clone()).collect(),
         Shape::Path(s) => s.caps().map(ShapeOps::shape).collect(),
         s => vec![s],
     };
@@ -150,3 +176,39 @@ pub fn cached_dist<S: ::std::hash::BuildHasher>(
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::qt::quadtree::QuadTree;
+    use crate::primitive::{circ, pt, rt};
+
+    #[test]
+    fn test_filtered_nearest() {
+        let shapes = vec![
+            ShapeInfo::new(rt(0.0, 0.0, 1.0, 1.0).shape(), Tag(1), Kinds::of(&[0])),
+            ShapeInfo::new(circ(pt(4.0, 0.0), 1.0).shape(), Tag(2), Kinds::of(&[1])),
+            ShapeInfo::new(rt(0.0, 5.0, 1.0, 6.0).shape(), Tag(1), Kinds::of(&[0, 1])),
+        ];
+        let probe = circ(pt(2.0, 0.5), 0.1).shape();
+        let queries = [
+            (ALL, Some(0)),
+            (Query(TagQuery::Tag(Tag(2)), KindsQuery::All), Some(1)),
+            (Query(TagQuery::Except(Tag(1)), KindsQuery::All), Some(1)),
+            (Query(TagQuery::All, KindsQuery::HasCommon(Kinds::of(&[1]))), Some(1)),
+            (Query(TagQuery::Tag(Tag(1)), KindsQuery::HasCommon(Kinds::of(&[1]))), Some(2)),
+            (Query(TagQuery::Tag(Tag(3)), KindsQuery::All), None),
+            (Query(TagQuery::All, KindsQuery::HasCommon(Kinds::of(&[2]))), None),
+        ];
+        let mut qt = QuadTree::new(shapes.clone());
+        for (q, expected) in queries {
+            let res = filtered_nearest(&shapes, &probe, q);
+            assert_eq!(res.map(|v| v.0), expected, "{q:?}");
+            // Agrees with the quadtree, which uses f64::MAX for no match.
+            assert_relative_eq!(res.map_or(f64::MAX, |v| v.1), qt.dist(&probe, q));
+        }
+    }
+}
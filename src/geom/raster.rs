@@ -0,0 +1,102 @@
+use crate::primitive::point::PtI;
+use crate::primitive::pti;
+use crate::primitive::segment::Segment;
+
+// Returns every grid cell that |s| passes through, in order from the start
+// of the segment. Cells are |cell| wide, and cell (i, j) covers
+// [i * cell, (i + 1) * cell) x [j * cell, (j + 1) * cell). This is a
+// supercover: if |s| passes exactly through a grid corner, both cells beside
+// the corner are included. |cell| must be positive.
+#[must_use]
+pub fn raster_cells(s: &Segment, cell: f64) -> Vec<PtI> {
+    assert!(cell > 0.0, "cell size must be positive");
+    let p0 = s.st() / cell;
+    let p1 = s.en() / cell;
+    let d = p1 - p0;
+    // Distance along the segment, as a fraction of its length, to the first
+    // grid line on each axis and between subsequent grid lines.
+    let axis = |p: f64, d: f64| {
+        let c = p.floor();
+        if d > 0.0 {
+            (1, (c + 1.0 - p) / d, 1.0 / d)
+        } else if d < 0.0 {
+            (-1, (p - c) / -d, -1.0 / d)
+        } else {
+            (0, f64::INFINITY, f64::INFINITY)
+        }
+    };
+    let (sx, mut tx, dtx) = axis(p0.x, d.x);
+    let (sy, mut ty, dty) = axis(p0.y, d.y);
+
+    let (mut x, mut y) = (p0.x.floor() as i64, p0.y.floor() as i64);
+    let (ex, ey) = (p1.x.floor() as i64, p1.y.floor() as i64);
+    // Each step moves one cell along one axis, so this bounds the walk even
+    // with rounding error.
+    let mut steps = (ex - x).abs() + (ey - y).abs();
+    let mut cells = vec![pti(x, y)];
+    while steps > 0 {
+        if tx < ty {
+            x += sx;
+            tx += dtx;
+            steps -= 1;
+        } else if ty < tx {
+            y += sy;
+            ty += dty;
+            steps -= 1;
+        } else {
+            // Passing exactly through a corner.
+            cells.push(pti(x + sx, y));
+            cells.push(pti(x, y + sy));
+            x += sx;
+            y += sy;
+            tx += dtx;
+            ty += dty;
+            steps -= 2;
+        }
+        cells.push(pti(x, y));
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::primitive::{pt, seg};
+
+    #[test]
+    fn test_raster_horizontal() {
+        let cells = raster_cells(&seg(pt(0.5, 0.5), pt(3.5, 0.5)), 1.0);
+        assert_eq!(cells, [pti(0, 0), pti(1, 0), pti(2, 0), pti(3, 0)]);
+        let cells = raster_cells(&seg(pt(7.0, -1.0), pt(-3.0, -1.0)), 2.0);
+        assert_eq!(
+            cells,
+            [pti(3, -1), pti(2, -1), pti(1, -1), pti(0, -1), pti(-1, -1), pti(-2, -1)]
+        );
+        assert_eq!(raster_cells(&seg(pt(0.5, 0.5), pt(0.5, 0.5)), 1.0), [pti(0, 0)]);
+    }
+
+    #[test]
+    fn test_raster_diagonal() {
+        let cells = raster_cells(&seg(pt(0.5, 0.5), pt(3.5, 3.5)), 1.0);
+        assert_eq!(cells.len(), 10);
+        assert_eq!(cells.first(), Some(&pti(0, 0)));
+        assert_eq!(cells.last(), Some(&pti(3, 3)));
+        for i in 0..3 {
+            for c in [pti(i, i), pti(i + 1, i), pti(i, i + 1)] {
+                assert!(cells.contains(&c), "{c:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_raster_steep() {
+        let cells = raster_cells(&seg(pt(0.5, 0.5), pt(1.5, 4.5)), 1.0);
+        assert_eq!(cells, [pti(0, 0), pti(0, 1), pti(0, 2), pti(1, 2), pti(1, 3), pti(1, 4)]);
+        // Reversed gives the same cells in reverse order.
+        let mut rev = raster_cells(&seg(pt(1.5, 4.5), pt(0.5, 0.5)), 1.0);
+        rev.reverse();
+        assert_eq!(rev, cells);
+    }
+}
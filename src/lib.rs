@@ -39,6 +39,7 @@
 #![allow(clippy::match_same_arms)]
 #![feature(array_chunks, array_windows, extract_if)]
 
+pub mod error;
 pub mod geom;
 pub mod primitive;
 pub mod tf;
@@ -1,19 +1,27 @@
+use std::f64::consts::PI;
+
 use derive_more::Display;
 
-use crate::geom::contains::{cap_contains_pt, cap_contains_rt};
+use crate::geom::contains::{
+    cap_contains_cap, cap_contains_circ, cap_contains_pt, cap_contains_rt, cap_contains_seg,
+};
+use crate::geom::convex::support_pts;
 use crate::geom::distance::{
-    cap_cap_dist, cap_circ_dist, cap_path_dist, cap_poly_dist, cap_rt_dist, cap_seg_dist,
+    cap_cap_dist, cap_circ_dist, cap_path_dist, cap_poly_dist, cap_pt_dist, cap_rt_dist,
+    cap_seg_dist, cap_tri_dist,
 };
 use crate::geom::intersects::{
     cap_intersects_cap, cap_intersects_circ, cap_intersects_path, cap_intersects_poly,
-    cap_intersects_rt, cap_intersects_tri,
+    cap_intersects_rt, cap_intersects_seg, cap_intersects_tri,
 };
+use crate::geom::math::lt;
 use crate::primitive::circle::Circle;
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
 use crate::primitive::shape::Shape;
-use crate::primitive::{ShapeOps, circ, seg};
+use crate::primitive::triangle::Tri;
+use crate::primitive::{Convex, ShapeOps, circ, pt, seg, tri};
 
 #[must_use]
 #[derive(Debug, Display, Copy, Clone)]
@@ -46,6 +54,18 @@ impl Capsule {
         self.en - self.st
     }
 
+    // Normalized direction from st to en, or None if the length is zero.
+    #[must_use]
+    pub fn unit_dir(&self) -> Option<Pt> {
+        let dir = self.dir();
+        (!dir.is_zero()).then(|| dir.norm())
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f64 {
+        self.dir().mag()
+    }
+
     pub fn st_cap(&self) -> Circle {
         circ(self.st(), self.r())
     }
@@ -69,6 +89,81 @@ impl Capsule {
     pub fn seg(&self) -> Segment {
         seg(self.st, self.en)
     }
+
+    // Returns a capsule with the same radius along a new spine.
+    pub const fn with_spine(&self, st: Pt, en: Pt) -> Self {
+        Self::new(st, en, self.r)
+    }
+
+    // Shortens the spine by |by| at both ends, e.g. to pull a trace back from
+    // a junction. Returns None if |by| is negative, since that would extend
+    // the spine, or if the ends would cross over each other.
+    #[must_use]
+    pub fn trimmed(&self, by: f64) -> Option<Self> {
+        if by < 0.0 || lt(self.length(), 2.0 * by) {
+            return None;
+        }
+        let d = self.unit_dir().map_or(Pt::zero(), |d| d * by);
+        Some(self.with_spine(self.st + d, self.en - d))
+    }
+
+    // Triangulates the capsule as a rectangular body plus two end caps, each
+    // approximated by |arc_segments| triangles.
+    #[must_use]
+    pub fn triangulate(&self, arc_segments: usize) -> Vec<Tri> {
+        let arc_segments = arc_segments.max(1);
+        let n = self.normal();
+        let mut tris = Vec::new();
+        if !self.dir().is_zero() {
+            tris.push(tri(self.st - n, self.en - n, self.en + n));
+            tris.push(tri(self.st - n, self.en + n, self.st + n));
+        }
+        // Fan out each cap from its centre.
+        for (p, from) in [(self.en, -n), (self.st, n)] {
+            let arc: Vec<_> = self.half_arc(p, from, arc_segments).collect();
+            tris.extend(arc.array_windows::<2>().map(|v| tri(p, v[0], v[1])));
+        }
+        tris
+    }
+
+    // Returns the CCW outline of the capsule, with each end cap approximated
+    // by |arc_segments| segments.
+    #[must_use]
+    pub fn outline(&self, arc_segments: usize) -> Vec<Pt> {
+        let arc_segments = arc_segments.max(1);
+        let n = self.normal();
+        self.half_arc(self.en, -n, arc_segments)
+            .chain(self.half_arc(self.st, n, arc_segments))
+            .collect()
+    }
+
+    // Left facing normal with length r. Degenerate capsules are circles, so
+    // just pick an arbitrary normal.
+    fn normal(&self) -> Pt {
+        let dir = self.dir();
+        if dir.is_zero() { pt(0.0, self.r) } else { dir.perp() * self.r }
+    }
+
+    // Points on the half turn CCW arc around |p| starting from |p + from|.
+    fn half_arc(&self, p: Pt, from: Pt, arc_segments: usize) -> impl Iterator<Item = Pt> {
+        let st_angle = from.y.atan2(from.x);
+        let r = self.r;
+        (0..=arc_segments).map(move |i| {
+            let a = st_angle + PI * i as f64 / arc_segments as f64;
+            p + pt(a.cos(), a.sin()) * r
+        })
+    }
+}
+
+impl Convex for Capsule {
+    fn support(&self, dir: Pt) -> Pt {
+        let p = support_pts(&[self.st(), self.en()], dir);
+        if dir.is_zero() { p } else { p + self.r() * dir.norm() }
+    }
+
+    fn center(&self) -> Pt {
+        (self.st() + self.en()) / 2.0
+    }
 }
 
 impl ShapeOps for Capsule {
@@ -85,28 +180,33 @@ impl ShapeOps for Capsule {
         match s {
             Shape::Capsule(s) => cap_intersects_cap(self, s),
             Shape::Circle(s) => cap_intersects_circ(self, s),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => cap_intersects_path(self, s),
             Shape::Point(s) => cap_contains_pt(self, s),
             Shape::Polygon(s) => cap_intersects_poly(self, s),
             Shape::Rect(s) => cap_intersects_rt(self, s),
-            Shape::Segment(_) => todo!(),
+            Shape::Segment(s) => cap_intersects_seg(self, s),
             Shape::Tri(s) => cap_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
+            Shape::Capsule(s) => cap_contains_cap(self, s),
+            Shape::Circle(s) => cap_contains_circ(self, s),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(s) => cap_contains_pt(self, s),
             Shape::Polygon(_) => todo!(),
             Shape::Rect(s) => cap_contains_rt(self, s),
-            Shape::Segment(_) => todo!(),
+            Shape::Segment(s) => cap_contains_seg(self, s),
             Shape::Tri(_) => todo!(),
         }
     }
@@ -115,14 +215,79 @@ impl ShapeOps for Capsule {
         match s {
             Shape::Capsule(s) => cap_cap_dist(self, s),
             Shape::Circle(s) => cap_circ_dist(self, s),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => cap_path_dist(self, s),
-            Shape::Point(_) => todo!(),
+            Shape::Point(s) => cap_pt_dist(self, s),
             Shape::Polygon(s) => cap_poly_dist(self, s),
             Shape::Rect(s) => cap_rt_dist(self, s),
             Shape::Segment(s) => cap_seg_dist(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => cap_tri_dist(self, s),
+        }
+        .unwrap_or(f64::NAN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::geom::contains::tri_contains_pt;
+    use crate::primitive::{cap, path};
+
+    fn tris_contain(tris: &[Tri], p: Pt) -> bool {
+        tris.iter().any(|t| tri_contains_pt(t, &p))
+    }
+
+    #[test]
+    fn test_unit_dir() {
+        let c = cap(pt(0.0, 0.0), pt(0.0, -2.0), 1.0);
+        assert_relative_eq!(c.unit_dir().unwrap(), pt(0.0, -1.0));
+        assert_relative_eq!(c.length(), 2.0);
+        assert!(cap(pt(1.0, 1.0), pt(1.0, 1.0), 1.0).unit_dir().is_none());
+    }
+
+    #[test]
+    fn test_trimmed() {
+        let c = cap(pt(0.0, 0.0), pt(10.0, 0.0), 1.0);
+        let t = c.trimmed(2.0).unwrap();
+        assert_relative_eq!(t.st(), pt(2.0, 0.0));
+        assert_relative_eq!(t.en(), pt(8.0, 0.0));
+        assert_relative_eq!(t.length(), 6.0);
+        assert_relative_eq!(t.r(), 1.0);
+        // Trimming to nothing leaves a circle, but trimming more fails.
+        assert_relative_eq!(c.trimmed(5.0).unwrap().length(), 0.0);
+        assert!(c.trimmed(6.0).is_none());
+        assert!(c.trimmed(-1.0).is_none());
+
+        let s = c.with_spine(pt(1.0, 1.0), pt(1.0, 3.0));
+        assert_relative_eq!(s.dir(), pt(0.0, 2.0));
+        assert_relative_eq!(s.r(), 1.0);
+    }
+
+    #[test]
+    fn test_cap_triangulate() {
+        let c = cap(pt(1.0, 1.0), pt(4.0, 5.0), 0.5);
+        let tris = c.triangulate(64);
+        let area: f64 = tris.iter().map(Tri::area).sum();
+        assert_relative_eq!(area, PI * 0.25 + 5.0, epsilon = 1e-2);
+        assert!(tris.iter().all(|t| t.area() > 0.0));
+        // Offset slightly from the spine to avoid sampling on triangle edges.
+        let off = c.dir().perp() * 0.01;
+        for t in [-0.05, 0.1, 0.3, 0.7, 0.9, 1.05] {
+            assert!(tris_contain(&tris, c.st() + c.dir() * t + off), "{t}");
+        }
+    }
+
+    #[test]
+    fn test_path_triangulate() {
+        let p = path(&[pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 2.0)], 1.0);
+        let tris = p.triangulate(16);
+        assert_eq!(tris.len(), 2 * (2 + 2 * 16));
+        for q in [pt(0.5, 0.0), pt(2.0, 0.5), pt(2.0, 1.5), pt(2.3, -0.4)] {
+            assert!(tris_contain(&tris, q), "{q}");
         }
     }
 }
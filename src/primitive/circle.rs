@@ -1,15 +1,24 @@
+use std::f64::consts::PI;
+
 use crate::geom::contains::{circ_contains_pt, circ_contains_rt};
 use crate::geom::distance::{
-    cap_circ_dist, circ_circ_dist, circ_path_dist, circ_poly_dist, circ_rt_dist,
+    cap_circ_dist, circ_circ_dist, circ_path_dist, circ_poly_dist, circ_pt_dist, circ_rt_dist,
+    circ_seg_dist, circ_tri_dist,
 };
 use crate::geom::intersects::{
-    circ_intersects_circ, circ_intersects_path, circ_intersects_poly, circ_intersects_rt,
-    circ_intersects_tri,
+    cap_intersects_circ, circ_intersects_circ, circ_intersects_path, circ_intersects_poly,
+    circ_intersects_rt, circ_intersects_seg, circ_intersects_tri,
 };
 use crate::primitive::point::Pt;
+use crate::primitive::polygon::edges;
 use crate::primitive::rect::Rt;
 use crate::primitive::shape::Shape;
-use crate::primitive::{ShapeOps, rt};
+use crate::primitive::triangle::Tri;
+use crate::primitive::{Convex, ShapeOps, pt, rt, tri};
+
+// Upper bound on Circle::arc_segments, so that small tolerances don't lead to
+// huge allocations.
+pub const MAX_ARC_SEGMENTS: usize = 1 << 16;
 
 #[must_use]
 #[derive(Debug, Copy, Clone)]
@@ -31,6 +40,44 @@ impl Circle {
     pub const fn p(&self) -> Pt {
         self.p
     }
+
+    // Number of segments needed to approximate the circle so that no point
+    // on the approximation is further than |tol| from the circle. Capped at
+    // MAX_ARC_SEGMENTS, so |tol| is not met if it is tiny relative to the
+    // radius.
+    #[must_use]
+    pub fn arc_segments(&self, tol: f64) -> usize {
+        assert!(tol > 0.0, "tolerance must be positive");
+        let cos = (1.0 - tol / self.r).clamp(-1.0, 1.0);
+        ((PI / cos.acos()).ceil() as usize).clamp(3, MAX_ARC_SEGMENTS)
+    }
+
+    // Returns a CCW polygon with |n| vertices on the circle.
+    #[must_use]
+    pub fn outline(&self, n: usize) -> Vec<Pt> {
+        (0..n)
+            .map(|i| {
+                let a = 2.0 * PI * i as f64 / n as f64;
+                self.p + pt(a.cos(), a.sin()) * self.r
+            })
+            .collect()
+    }
+
+    // Triangulates the circle as a fan of |n| triangles around the centre.
+    #[must_use]
+    pub fn triangulate(&self, n: usize) -> Vec<Tri> {
+        edges(&self.outline(n)).map(|[&p0, &p1]| tri(self.p, p0, p1)).collect()
+    }
+}
+
+impl Convex for Circle {
+    fn support(&self, dir: Pt) -> Pt {
+        if dir.is_zero() { self.p() } else { self.p() + self.r() * dir.norm() }
+    }
+
+    fn center(&self) -> Pt {
+        self.p()
+    }
 }
 
 impl ShapeOps for Circle {
@@ -44,24 +91,29 @@ impl ShapeOps for Circle {
 
     fn intersects_shape(&self, s: &Shape) -> bool {
         match s {
-            Shape::Capsule(_) => todo!(),
+            Shape::Capsule(s) => cap_intersects_circ(s, self),
             Shape::Circle(s) => circ_intersects_circ(self, s),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => circ_intersects_path(self, s),
             Shape::Point(s) => circ_contains_pt(self, s),
             Shape::Polygon(s) => circ_intersects_poly(self, s),
             Shape::Rect(s) => circ_intersects_rt(self, s),
-            Shape::Segment(_) => todo!(),
+            Shape::Segment(s) => circ_intersects_seg(self, s),
             Shape::Tri(s) => circ_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
             Shape::Capsule(_) => todo!(),
             Shape::Circle(_) => todo!(),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(s) => circ_contains_pt(self, s),
@@ -76,14 +128,16 @@ impl ShapeOps for Circle {
         match s {
             Shape::Capsule(s) => cap_circ_dist(s, self),
             Shape::Circle(s) => circ_circ_dist(self, s),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => circ_path_dist(self, s),
-            Shape::Point(_) => todo!(),
+            Shape::Point(s) => circ_pt_dist(self, s),
             Shape::Polygon(s) => circ_poly_dist(self, s),
             Shape::Rect(s) => circ_rt_dist(self, s),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => circ_seg_dist(self, s),
+            Shape::Tri(s) => circ_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
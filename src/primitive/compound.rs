@@ -1,5 +1,6 @@
 use std::cell::{Ref, RefCell};
 
+use crate::error::Result;
 use crate::geom::qt::quadtree::{QuadTree, ShapeIdx};
 use crate::geom::qt::query::{ALL, Query, ShapeInfo};
 use crate::primitive::ShapeOps;
@@ -19,11 +20,16 @@ impl Compound {
         Self { qt: RefCell::new(QuadTree::empty()) }
     }
 
+    // Creates a compound of |shapes|, each with no tag or kinds.
+    pub fn from_plain_shapes(shapes: &[Shape]) -> Self {
+        Self { qt: RefCell::new(QuadTree::from_shapes(shapes)) }
+    }
+
     pub fn with_bounds(r: &Rt) -> Self {
         Self { qt: RefCell::new(QuadTree::with_bounds(r)) }
     }
 
-    pub fn add_shape(&self, shape: ShapeInfo) -> Vec<ShapeIdx> {
+    pub fn add_shape(&self, shape: ShapeInfo) -> Result<Vec<ShapeIdx>> {
         self.qt.borrow_mut().add_shape(shape)
     }
 
@@ -45,6 +51,18 @@ impl Compound {
         self.qt.borrow_mut().dist(s, q)
     }
 
+    // Rebuilds the spatial index, e.g. after adding many shapes one by one.
+    pub fn optimize(&self) {
+        self.qt.borrow_mut().optimize();
+    }
+
+    // Returns the exact extent of the shapes, or None if there are none. This
+    // may be smaller than bounds, which is the region covered by the tree.
+    #[must_use]
+    pub fn tight_bounds(&self) -> Option<Rt> {
+        self.qt.borrow().tight_bounds()
+    }
+
     pub fn quadtree(&self) -> Ref<'_, QuadTree> {
         self.qt.borrow()
     }
@@ -66,10 +84,61 @@ impl ShapeOps for Compound {
     // N.B. this will check if any one shape in the compound contains |s|.
     // If |s| is covered using multiple shapes then that won't be detected.
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         self.qt.borrow_mut().contains(s, ALL)
     }
 
     fn dist_to_shape(&self, s: &Shape) -> f64 {
         self.qt.borrow_mut().dist(s, ALL)
     }
+
+    fn dist_to_shape_within(&self, s: &Shape, cutoff: f64) -> Option<f64> {
+        self.qt.borrow_mut().dist_within(s, ALL, cutoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::primitive::{circ, pt, rt};
+
+    #[test]
+    fn test_compound_tight_bounds() {
+        let mut c = Compound::with_bounds(&rt(-100.0, -100.0, 100.0, 100.0));
+        assert_eq!(c.tight_bounds(), None);
+        c.add_shape(ShapeInfo::anon(circ(pt(1.0, 1.0), 1.0).shape())).unwrap();
+        let idx = c.add_shape(ShapeInfo::anon(rt(5.0, 2.0, 7.0, 3.0).shape())).unwrap();
+        c.add_shape(ShapeInfo::anon(pt(3.0, -4.0).shape())).unwrap();
+        assert_eq!(c.bounds(), rt(-100.0, -100.0, 100.0, 100.0));
+        assert_eq!(c.tight_bounds(), Some(rt(0.0, -4.0, 7.0, 3.0)));
+        // Removed shapes don't count.
+        c.remove_shape(idx[0]);
+        assert_eq!(c.tight_bounds(), Some(rt(0.0, -4.0, 3.0, 2.0)));
+    }
+
+    #[test]
+    fn test_compound_optimize() {
+        let c = Compound::empty();
+        for i in 0..50 {
+            let x = f64::from(i);
+            c.add_shape(ShapeInfo::anon(circ(pt(x, x), 0.4).shape())).unwrap();
+        }
+        assert_eq!(c.quadtree().rts().len(), 1);
+        c.optimize();
+        assert!(c.quadtree().rts().len() > 1);
+
+        assert!(c.intersects_shape(&pt(10.0, 10.3).shape()));
+        assert!(!c.intersects_shape(&pt(10.0, 10.5).shape()));
+        assert!(c.contains_shape(&pt(49.0, 49.2).shape()));
+        assert_relative_eq!(
+            c.dist_to_shape(&circ(pt(20.0, 21.0), 0.1).shape()),
+            0.5,
+            epsilon = 1e-9
+        );
+    }
 }
@@ -0,0 +1,136 @@
+use crate::geom::clip::clip_pts_left_of;
+use crate::geom::math::{cross_at, orientation};
+use crate::primitive::line_shape::Line;
+use crate::primitive::point::Pt;
+use crate::primitive::polygon::Poly;
+use crate::primitive::segment::Segment;
+use crate::primitive::{line, poly, seg};
+
+// The closed half-plane on one side of |line|. Points on |line| are contained.
+#[must_use]
+#[derive(Debug, Copy, Clone)]
+pub struct HalfPlane {
+    line: Line,
+    keep_left: bool,
+}
+
+impl HalfPlane {
+    pub const fn new(line: Line, keep_left: bool) -> Self {
+        Self { line, keep_left }
+    }
+
+    pub const fn line(&self) -> Line {
+        self.line
+    }
+
+    #[must_use]
+    pub const fn keep_left(&self) -> bool {
+        self.keep_left
+    }
+
+    // Returns the boundary line oriented so the kept side is on its left.
+    fn left_line(&self) -> Line {
+        if self.keep_left { self.line } else { line(self.line.en(), self.line.st()) }
+    }
+
+    #[must_use]
+    pub fn contains_pt(&self, p: Pt) -> bool {
+        orientation(&self.left_line(), p) >= 0
+    }
+
+    // Clips |p| to this half-plane. Non-convex polygons are clipped per
+    // triangle, so they may come back as several pieces.
+    #[must_use]
+    pub fn clip_poly(&self, p: &Poly) -> Vec<Poly> {
+        let l = self.left_line();
+        let clip = |pts: &[Pt]| {
+            let p = poly(&clip_pts_left_of(pts, &l));
+            (p.pts().len() >= 3).then_some(p)
+        };
+        if p.pts().iter().all(|&v| self.contains_pt(v)) {
+            vec![p.clone()]
+        } else if p.is_convex() {
+            clip(p.pts()).into_iter().collect()
+        } else {
+            p.tri().iter().filter_map(|t| clip(t.pts())).collect()
+        }
+    }
+
+    // Clips |s| to this half-plane, returning None if no part of it remains.
+    #[must_use]
+    pub fn clip_seg(&self, s: &Segment) -> Option<Segment> {
+        let (st_in, en_in) = (self.contains_pt(s.st()), self.contains_pt(s.en()));
+        match (st_in, en_in) {
+            (true, true) => Some(*s),
+            (false, false) => None,
+            _ => {
+                let l = self.left_line();
+                let d0 = cross_at(l.st(), l.en(), s.st());
+                let d1 = cross_at(l.st(), l.en(), s.en());
+                let p = s.st() + s.dir() * (d0 / (d0 - d1));
+                Some(if st_in { seg(s.st(), p) } else { seg(p, s.en()) })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::geom::math::pt_eq;
+    use crate::primitive::triangle::Tri;
+    use crate::primitive::{pt, square};
+
+    #[test]
+    fn test_contains_pt() {
+        let h = HalfPlane::new(line(pt(0.0, 0.0), pt(1.0, 0.0)), true);
+        assert!(h.contains_pt(pt(0.0, 1.0)));
+        assert!(h.contains_pt(pt(5.0, 0.0)));
+        assert!(!h.contains_pt(pt(0.0, -1.0)));
+
+        let h = HalfPlane::new(line(pt(0.0, 0.0), pt(1.0, 0.0)), false);
+        assert!(!h.contains_pt(pt(0.0, 1.0)));
+        assert!(h.contains_pt(pt(5.0, 0.0)));
+        assert!(h.contains_pt(pt(0.0, -1.0)));
+    }
+
+    #[test]
+    fn test_clip_poly_diagonal() {
+        let h = HalfPlane::new(line(pt(0.0, 0.0), pt(2.0, 2.0)), true);
+        let clipped = h.clip_poly(&square(0.0, 0.0, 2.0));
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(clipped[0].pts().len(), 3);
+        assert_relative_eq!(clipped[0].tri().iter().map(Tri::area).sum::<f64>(), 2.0);
+        assert!(clipped[0].pts().iter().all(|&v| h.contains_pt(v)));
+    }
+
+    #[test]
+    fn test_clip_poly_keep_all() {
+        let h = HalfPlane::new(line(pt(-1.0, 0.0), pt(-1.0, 1.0)), false);
+        let clipped = h.clip_poly(&square(0.0, 0.0, 2.0));
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(clipped[0].pts(), square(0.0, 0.0, 2.0).pts());
+
+        let h = HalfPlane::new(line(pt(-1.0, 0.0), pt(-1.0, 1.0)), true);
+        assert!(h.clip_poly(&square(0.0, 0.0, 2.0)).is_empty());
+    }
+
+    #[test]
+    fn test_clip_seg() {
+        let h = HalfPlane::new(line(pt(1.0, 0.0), pt(1.0, 1.0)), false);
+        let s = h.clip_seg(&seg(pt(0.0, 0.0), pt(4.0, 2.0))).unwrap();
+        assert!(pt_eq(s.st(), pt(1.0, 0.5)));
+        assert!(pt_eq(s.en(), pt(4.0, 2.0)));
+
+        let s = h.clip_seg(&seg(pt(4.0, 2.0), pt(0.0, 0.0))).unwrap();
+        assert!(pt_eq(s.st(), pt(4.0, 2.0)));
+        assert!(pt_eq(s.en(), pt(1.0, 0.5)));
+
+        assert!(h.clip_seg(&seg(pt(0.0, 0.0), pt(0.5, 3.0))).is_none());
+        let s = seg(pt(2.0, 0.0), pt(3.0, 3.0));
+        let clipped = h.clip_seg(&s).unwrap();
+        assert!(pt_eq(clipped.st(), s.st()) && pt_eq(clipped.en(), s.en()));
+    }
+}
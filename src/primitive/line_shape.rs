@@ -1,5 +1,5 @@
-use crate::geom::distance::line_pt_dist;
-use crate::geom::intersects::{line_intersects_line, line_intersects_seg};
+use crate::geom::distance::{line_line_dist, line_shape_dist};
+use crate::geom::intersects::{line_intersects_line, line_intersects_seg, line_intersects_tri};
 use crate::primitive::ShapeOps;
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
@@ -29,9 +29,13 @@ impl Line {
         self.en - self.st
     }
 
-    // Projects |p| onto this line.
+    // Projects |p| onto this line. Degenerate lines with zero length project
+    // everything onto their start point.
     pub fn project(&self, p: Pt) -> Pt {
         let dir = self.dir();
+        if dir.is_zero() {
+            return self.st;
+        }
         let k = dir.dot(p - self.st) / dir.mag2();
         self.st + k * dir
     }
@@ -52,21 +56,26 @@ impl ShapeOps for Line {
             Shape::Capsule(_) => todo!(),
             Shape::Circle(_) => todo!(),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(s) => line_intersects_line(self, s),
             Shape::Path(_) => todo!(),
             Shape::Point(_) => todo!(),
             Shape::Polygon(_) => todo!(),
             Shape::Rect(_) => todo!(),
             Shape::Segment(s) => line_intersects_seg(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => line_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
             Shape::Capsule(_) => todo!(),
             Shape::Circle(_) => todo!(),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(_) => todo!(),
@@ -79,17 +88,10 @@ impl ShapeOps for Line {
 
     fn dist_to_shape(&self, s: &Shape) -> f64 {
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
-            Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
-            Shape::Point(s) => line_pt_dist(self, s),
-            Shape::Polygon(_) => todo!(),
-            Shape::Rect(_) => todo!(),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Line(s) => line_line_dist(self, s),
+            s => line_shape_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
 
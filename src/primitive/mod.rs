@@ -1,3 +1,7 @@
+use std::fmt::Debug;
+
+use crate::geom::distance::bounds_dist_lower_bound;
+use crate::geom::math::EP;
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
 use crate::primitive::line_shape::Line;
@@ -12,6 +16,7 @@ use crate::primitive::triangle::Tri;
 pub mod capsule;
 pub mod circle;
 pub mod compound;
+pub mod half_plane;
 pub mod line_shape;
 pub mod path_shape;
 pub mod point;
@@ -20,6 +25,7 @@ pub mod rect;
 pub mod segment;
 pub mod shape;
 pub mod triangle;
+pub mod var_path;
 
 pub trait ShapeOps {
     fn bounds(&self) -> Rt;
@@ -28,8 +34,106 @@ pub trait ShapeOps {
     fn intersects_shape(&self, s: &Shape) -> bool;
     // Returns true iff |s| is fully contained within this shape.
     fn contains_shape(&self, s: &Shape) -> bool;
-    // Returns the minimum distance between the two shapes.
+    // Returns the minimum distance between the two shapes, or NaN if it is not
+    // finite, e.g. for shapes with NaN coordinates. See checked_dist.
     fn dist_to_shape(&self, s: &Shape) -> f64;
+    // Returns the distance to |s| if it is at most |cutoff|, otherwise None.
+    // Shapes whose bounds are further apart than |cutoff| are rejected without
+    // computing the exact distance.
+    fn dist_to_shape_within(&self, s: &Shape, cutoff: f64) -> Option<f64> {
+        if bounds_dist_lower_bound(&self.bounds(), &s.bounds()) > cutoff {
+            return None;
+        }
+        let d = self.dist_to_shape(s);
+        (d <= cutoff).then_some(d)
+    }
+}
+
+// Object safe version of ShapeOps, for user defined shapes. These can be stored
+// in a Shape via Shape::Custom, e.g. to add them to a quadtree. Built in shapes
+// test against custom shapes by calling these methods with themselves.
+pub trait DynShape: Debug {
+    fn dyn_bounds(&self) -> Rt;
+    // Returns true iff the two shapes have a non-zero intersection.
+    fn dyn_intersects_shape(&self, s: &Shape) -> bool;
+    // Returns true iff |s| is fully contained within this shape.
+    fn dyn_contains_shape(&self, s: &Shape) -> bool;
+    // Returns the minimum distance between the two shapes.
+    fn dyn_dist_to_shape(&self, s: &Shape) -> f64;
+    fn clone_box(&self) -> Box<dyn DynShape>;
+
+    // Returns true iff this shape is fully contained within |s|. By default
+    // this checks if |s| contains the bounds, which may miss some cases.
+    fn dyn_contained_by(&self, s: &Shape) -> bool {
+        s.contains_shape(&self.dyn_bounds().shape())
+    }
+
+    // Outline of the shape, following the conventions of Shape::to_polyline.
+    // By default this is the bounds, which is only exact for rects.
+    fn dyn_to_polyline(&self, _tol: f64) -> Vec<Vec<Pt>> {
+        let b = self.dyn_bounds();
+        if b.is_empty() { vec![] } else { vec![b.pts().to_vec()] }
+    }
+
+    // Applies |f| to the shape, like Shape::map_points. By default this maps
+    // the outline from dyn_to_polyline, giving a polygon.
+    fn dyn_map_points(&self, f: &dyn Fn(Pt) -> Pt) -> Shape {
+        let rings: Vec<Vec<Pt>> =
+            self.dyn_to_polyline(EP).iter().map(|r| r.iter().map(|&p| f(p)).collect()).collect();
+        match rings.split_first() {
+            Some((pts, holes)) => Poly::new_with_holes(pts, holes).shape(),
+            None => poly(&[]).shape(),
+        }
+    }
+}
+
+impl Clone for Box<dyn DynShape> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl DynShape for Shape {
+    fn dyn_bounds(&self) -> Rt {
+        self.bounds()
+    }
+
+    fn dyn_intersects_shape(&self, s: &Shape) -> bool {
+        self.intersects_shape(s)
+    }
+
+    fn dyn_contains_shape(&self, s: &Shape) -> bool {
+        self.contains_shape(s)
+    }
+
+    fn dyn_dist_to_shape(&self, s: &Shape) -> f64 {
+        self.dist_to_shape(s)
+    }
+
+    fn clone_box(&self) -> Box<dyn DynShape> {
+        Box::new(self.clone())
+    }
+
+    fn dyn_contained_by(&self, s: &Shape) -> bool {
+        s.contains_shape(self)
+    }
+
+    fn dyn_to_polyline(&self, tol: f64) -> Vec<Vec<Pt>> {
+        self.to_polyline(tol)
+    }
+
+    fn dyn_map_points(&self, f: &dyn Fn(Pt) -> Pt) -> Shape {
+        self.map_points(f)
+    }
+}
+
+// Convex shapes, described by their support function. Lets algorithms like
+// GJK be generic rather than matching on Shape.
+pub trait Convex {
+    // Returns the point of the shape furthest in direction |dir|.
+    fn support(&self, dir: Pt) -> Pt;
+    // Returns a point inside the shape.
+    fn center(&self) -> Pt;
 }
 
 pub fn cap(st: Pt, en: Pt, r: f64) -> Capsule {
@@ -60,6 +164,12 @@ pub fn poly(pts: &[Pt]) -> Poly {
     Poly::new(pts)
 }
 
+// Square polygon with bottom left corner (|x|, |y|) and side |w|, for tests.
+#[cfg(test)]
+pub fn square(x: f64, y: f64, w: f64) -> Poly {
+    poly(&[pt(x, y), pt(x + w, y), pt(x + w, y + w), pt(x, y + w)])
+}
+
 pub const fn rt(l: f64, b: f64, r: f64, t: f64) -> Rt {
     Rt::new(l, b, r, t)
 }
@@ -1,17 +1,22 @@
 use std::ops::Index;
 
 use crate::geom::bounds::pt_cloud_bounds;
-use crate::geom::contains::{path_contains_rt, path_contains_seg};
+use crate::geom::contains::{
+    path_contains_cap, path_contains_circ, path_contains_pt, path_contains_rt, path_contains_seg,
+};
 use crate::geom::convex::remove_collinear;
-use crate::geom::distance::{cap_path_dist, circ_path_dist, path_poly_dist, rt_path_dist};
+use crate::geom::distance::{
+    cap_path_dist, circ_path_dist, path_poly_dist, path_tri_dist, rt_path_dist,
+};
 use crate::geom::intersects::{
     cap_intersects_path, circ_intersects_path, path_intersects_path, path_intersects_poly,
-    path_intersects_rt,
+    path_intersects_rt, path_intersects_seg, path_intersects_tri,
 };
 use crate::primitive::capsule::Capsule;
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
 use crate::primitive::shape::Shape;
+use crate::primitive::triangle::Tri;
 use crate::primitive::{ShapeOps, cap};
 
 #[must_use]
@@ -31,7 +36,7 @@ impl std::fmt::Debug for Path {
 impl Path {
     pub fn new(pts: &[Pt], r: f64) -> Self {
         let pts = remove_collinear(pts);
-        let bounds = pt_cloud_bounds(&pts).inset(-r / 2.0, -r / 2.0);
+        let bounds = pt_cloud_bounds(&pts).inset(-r, -r);
         Self { pts, r, bounds }
     }
 
@@ -53,10 +58,24 @@ impl Path {
         self.pts.array_windows::<2>().map(move |v| cap(v[0], v[1], self.r))
     }
 
+    // Length of the centerline, ignoring the width. Zero for paths with fewer
+    // than two points.
+    #[must_use]
+    pub fn length(&self) -> f64 {
+        self.pts.array_windows::<2>().map(|v| v[0].dist(v[1])).sum()
+    }
+
     #[must_use]
     pub const fn r(&self) -> f64 {
         self.r
     }
+
+    // Triangulates each capsule of the path. Joints are covered by the round
+    // caps of adjacent capsules, so triangles overlap there.
+    #[must_use]
+    pub fn triangulate(&self, arc_segments: usize) -> Vec<Tri> {
+        self.caps().flat_map(|cap| cap.triangulate(arc_segments)).collect()
+    }
 }
 
 impl ShapeOps for Path {
@@ -72,25 +91,30 @@ impl ShapeOps for Path {
         match s {
             Shape::Capsule(s) => cap_intersects_path(s, self),
             Shape::Circle(s) => circ_intersects_path(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.clone().shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => path_intersects_path(self, s),
-            Shape::Point(_) => todo!(),
+            Shape::Point(s) => path_contains_pt(self, s),
             Shape::Polygon(s) => path_intersects_poly(self, s),
             Shape::Rect(s) => path_intersects_rt(self, s),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => path_intersects_seg(self, s),
+            Shape::Tri(s) => path_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
+            Shape::Capsule(s) => path_contains_cap(self, s),
+            Shape::Circle(s) => path_contains_circ(self, s),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
+            Shape::Point(s) => path_contains_pt(self, s),
             Shape::Polygon(_) => todo!(),
             Shape::Rect(s) => path_contains_rt(self, s),
             Shape::Segment(s) => path_contains_seg(self, s),
@@ -102,15 +126,17 @@ impl ShapeOps for Path {
         match s {
             Shape::Capsule(s) => cap_path_dist(s, self),
             Shape::Circle(s) => circ_path_dist(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => return s.dist_to_shape(&self.clone().shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(_) => todo!(),
             Shape::Polygon(s) => path_poly_dist(self, s),
             Shape::Rect(s) => rt_path_dist(s, self),
             Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => path_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
 
@@ -121,3 +147,18 @@ impl Index<usize> for Path {
         &self.pts[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use crate::primitive::{path, pt};
+
+    #[test]
+    fn test_length() {
+        let p = path(&[pt(0.0, 0.0), pt(3.0, 4.0), pt(3.0, 6.0)], 1.0);
+        assert_relative_eq!(p.length(), 7.0);
+        assert_relative_eq!(path(&[pt(1.0, 1.0)], 1.0).length(), 0.0);
+        assert_relative_eq!(path(&[], 1.0).length(), 0.0);
+    }
+}
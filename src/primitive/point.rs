@@ -4,8 +4,14 @@ use derive_more::Display;
 use nalgebra::{Vector2, vector};
 use serde::{Deserialize, Serialize};
 
-use crate::geom::contains::{cap_contains_pt, circ_contains_pt, poly_contains_pt};
-use crate::geom::distance::{line_pt_dist, poly_pt_dist, pt_pt_dist, pt_rt_dist, pt_seg_dist};
+use crate::geom::contains::{
+    cap_contains_pt, circ_contains_pt, path_contains_pt, poly_contains_pt, tri_contains_pt,
+};
+use crate::geom::distance::{
+    cap_pt_dist, circ_pt_dist, line_pt_dist, poly_pt_dist, pt_pt_dist, pt_rt_dist, pt_seg_dist,
+    pt_tri_dist,
+};
+use crate::geom::math::pt_eq;
 use crate::primitive::rect::Rt;
 use crate::primitive::shape::Shape;
 use crate::primitive::{ShapeOps, pt, pti, rt};
@@ -80,6 +86,11 @@ impl Pt {
     pub fn clamp(&self, r: &Rt) -> Pt {
         pt(self.x.clamp(r.l(), r.r()), self.y.clamp(r.b(), r.t()))
     }
+
+    // Component-wise reciprocal. Zero components map to 1, matching `Pt / Pt`.
+    pub fn recip(&self) -> Pt {
+        pt(1.0, 1.0) / self
+    }
 }
 
 impl AbsDiffEq for Pt {
@@ -124,45 +135,57 @@ impl ShapeOps for Pt {
         match s {
             Shape::Capsule(s) => cap_contains_pt(s, self),
             Shape::Circle(s) => circ_contains_pt(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
+            Shape::Path(s) => path_contains_pt(s, self),
+            Shape::Point(s) => pt_eq(*self, *s),
             Shape::Polygon(s) => poly_contains_pt(s, self),
             Shape::Rect(s) => s.contains(*self),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => s.contains(*self),
+            Shape::Tri(s) => tri_contains_pt(s, self),
         }
     }
 
+    // A point only contains the empty set and shapes which degenerate to that
+    // same point, e.g. a zero radius circle.
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
+        let contains_bounds =
+            |b: Rt| b.is_empty() || (pt_eq(b.bl(), *self) && pt_eq(b.tr(), *self));
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
-            Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
-            Shape::Polygon(_) => todo!(),
-            Shape::Rect(_) => todo!(),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            // Bounds of the compound's quadtree may be larger than its shapes.
+            Shape::Compound(s) => s.tight_bounds().is_none_or(contains_bounds),
+            Shape::Line(_) => false,
+            Shape::Capsule(_)
+            | Shape::Circle(_)
+            | Shape::Custom(_)
+            | Shape::Path(_)
+            | Shape::Point(_)
+            | Shape::Polygon(_)
+            | Shape::Rect(_)
+            | Shape::Segment(_)
+            | Shape::Tri(_) => contains_bounds(s.bounds()),
         }
     }
 
     fn dist_to_shape(&self, s: &Shape) -> f64 {
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
+            Shape::Capsule(s) => cap_pt_dist(s, self),
+            Shape::Circle(s) => circ_pt_dist(s, self),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
             Shape::Line(s) => line_pt_dist(s, self),
             Shape::Path(_) => todo!(),
             Shape::Point(s) => pt_pt_dist(self, s),
             Shape::Polygon(s) => poly_pt_dist(s, self),
             Shape::Rect(s) => pt_rt_dist(self, s),
             Shape::Segment(s) => pt_seg_dist(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => pt_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
 
@@ -176,6 +199,14 @@ impl_op_ex!(-= |a: &mut Pt, b: &Pt| { a.x -= b.x; a.y -= b.y; });
 impl_op_ex_commutative!(*|a: &Pt, b: &f64| -> Pt { pt(a.x * b, a.y * b) });
 impl_op_ex_commutative!(/|a: &Pt, b: &f64| -> Pt { pt(a.x / b, a.y / b) });
 
+// Component-wise division. A zero component in the denominator leaves the
+// numerator component unchanged, i.e. a degenerate axis is treated as a scale
+// of 1 rather than producing infinities or NaN.
+impl_op_ex!(/|a: &Pt, b: &Pt| -> Pt {
+    let div = |n: f64, d: f64| if d == 0.0 { n } else { n / d };
+    pt(div(a.x, b.x), div(a.y, b.y))
+});
+
 #[must_use]
 #[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone, Display, Serialize, Deserialize)]
 #[display("({x}, {y})")]
@@ -223,3 +254,60 @@ impl_op_ex!(-= |a: &mut PtI, b: &PtI| { a.x -= b.x; a.y -= b.y; });
 
 impl_op_ex_commutative!(*|a: &PtI, b: &i64| -> PtI { pti(a.x * b, a.y * b) });
 impl_op_ex_commutative!(/|a: &PtI, b: &i64| -> PtI { pti(a.x / b, a.y / b) });
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::qt::query::ShapeInfo;
+    use crate::primitive::compound::Compound;
+    use crate::primitive::{circ, line, path, seg, tri};
+
+    #[test]
+    fn test_div_pt() {
+        assert_eq!(pt(6.0, 8.0) / pt(2.0, 4.0), pt(3.0, 2.0));
+        assert_eq!(pt(6.0, 8.0) / pt(0.0, 4.0), pt(6.0, 2.0));
+        assert_eq!(pt(6.0, 8.0) / pt(0.0, 0.0), pt(6.0, 8.0));
+    }
+
+    #[test]
+    fn test_contains_shape() {
+        let p = pt(1.0, 2.0);
+        assert!(p.contains_shape(&pt(1.0, 2.0).shape()));
+        assert!(!p.contains_shape(&pt(1.0, 2.5).shape()));
+        assert!(p.contains_shape(&Rt::empty().shape()));
+        assert!(p.contains_shape(&circ(p, 0.0).shape()));
+        assert!(!p.contains_shape(&circ(p, 0.5).shape()));
+        assert!(p.contains_shape(&seg(p, p).shape()));
+        assert!(!p.contains_shape(&seg(p, pt(2.0, 2.0)).shape()));
+        assert!(!p.contains_shape(&line(p, pt(2.0, 2.0)).shape()));
+        assert!(p.contains_shape(&Compound::empty().shape()));
+        let c = Compound::with_bounds(&rt(0.0, 0.0, 4.0, 4.0));
+        c.add_shape(ShapeInfo::anon(p.shape())).unwrap();
+        assert!(p.contains_shape(&c.shape()));
+    }
+
+    #[test]
+    fn test_intersects_shape() {
+        let p = pt(1.0, 2.0);
+        assert!(p.intersects_shape(&pt(1.0, 2.0).shape()));
+        assert!(!p.intersects_shape(&pt(1.0, 2.1).shape()));
+        // On a segment endpoint, and in the middle.
+        assert!(p.intersects_shape(&seg(pt(1.0, 2.0), pt(3.0, 4.0)).shape()));
+        assert!(pt(2.0, 3.0).intersects_shape(&seg(pt(1.0, 2.0), pt(3.0, 4.0)).shape()));
+        assert!(!pt(2.0, 3.1).intersects_shape(&seg(pt(1.0, 2.0), pt(3.0, 4.0)).shape()));
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0)).shape();
+        assert!(pt(1.0, 1.0).intersects_shape(&t));
+        assert!(!pt(2.1, 2.0).intersects_shape(&t));
+        let path = path(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0)], 0.5).shape();
+        assert!(pt(4.5, 2.0).intersects_shape(&path));
+        assert!(!pt(3.0, 1.0).intersects_shape(&path));
+    }
+
+    #[test]
+    fn test_recip() {
+        assert_eq!(pt(2.0, -4.0).recip(), pt(0.5, -0.25));
+        assert_eq!(pt(0.0, 4.0).recip(), pt(1.0, 0.25));
+    }
+}
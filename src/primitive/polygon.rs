@@ -3,26 +3,42 @@ use std::ops::Index;
 
 use earcutr::earcut;
 
+use crate::geom::area::pts_signed_area;
 use crate::geom::bounds::pt_cloud_bounds;
+use crate::geom::clip::clip_convex_pts_rt;
 use crate::geom::contains::{
-    poly_contains_cap, poly_contains_circ, poly_contains_path, poly_contains_pt, poly_contains_rt,
-    poly_contains_seg,
+    poly_contains_cap, poly_contains_circ, poly_contains_path, poly_contains_poly,
+    poly_contains_pt, poly_contains_rt, poly_contains_seg,
 };
-use crate::geom::convex::{ensure_ccw, is_convex_ccw, remove_collinear};
+use crate::geom::convex::{ensure_ccw, ensure_cw, is_convex_ccw, remove_collinear, support_pts};
 use crate::geom::distance::{
-    cap_poly_dist, circ_poly_dist, path_poly_dist, poly_pt_dist, poly_rt_dist,
+    cap_poly_dist, circ_poly_dist, path_poly_dist, poly_poly_dist, poly_pt_dist, poly_rt_dist,
+    poly_seg_dist, poly_tri_dist, pt_seg_dist,
 };
 use crate::geom::intersects::{
-    cap_intersects_poly, circ_intersects_poly, path_intersects_poly, poly_intersects_rt,
+    cap_intersects_poly, circ_intersects_poly, path_intersects_poly, poly_intersects_poly,
+    poly_intersects_rt, poly_intersects_seg, poly_intersects_tri, seg_intersects_seg,
 };
+use crate::geom::math::{EP, eq, lt, orientation};
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
+use crate::primitive::segment::Segment;
 use crate::primitive::shape::Shape;
 use crate::primitive::triangle::Tri;
-use crate::primitive::{ShapeOps, tri};
+use crate::primitive::{Convex, ShapeOps, rt, seg, tri};
 
-// Represents a simple non-convex polygon.
-// Stored in CCW order.
+// A vertex or edge of a polygon. Edge |i| goes from vertex |i| to vertex
+// |i + 1|, wrapping around.
+#[must_use]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FeatureRef {
+    Vertex(usize),
+    Edge(usize),
+}
+
+// Represents a simple non-convex polygon, optionally with holes.
+// Stored in CCW order, with holes in CW order. Queries treat holes as outside
+// the polygon.
 // TODO: make polygons use quadtree?
 #[must_use]
 #[derive(Debug, Clone)]
@@ -30,27 +46,78 @@ pub struct Poly {
     pts: Vec<Pt>,
     tri: Vec<Tri>,
     tri_idx: Vec<u32>,
+    holes: Vec<Vec<Pt>>,
+    verts: Vec<Pt>, // Outer points followed by each hole, for tri_idx.
     is_convex: bool,
+    was_reversed: bool, // Whether the outer points were given in CW order.
 }
 
 impl Poly {
     pub fn new(pts: &[Pt]) -> Self {
+        Self::new_with_holes(pts, &[])
+    }
+
+    // Creates a polygon with holes. The outer ring is made CCW and holes are
+    // made CW, as triangulation requires, so rings with the wrong winding are
+    // corrected automatically. Holes must lie inside the outer ring and not
+    // overlap each other.
+    pub fn new_with_holes(pts: &[Pt], holes: &[Vec<Pt>]) -> Self {
         let mut pts = remove_collinear(pts);
-        ensure_ccw(&mut pts);
-        let verts: Vec<f64> = pts.iter().flat_map(|v| [v.x, v.y]).collect();
-        let tri_idx: Vec<_> = earcut(&verts, &[], 2).unwrap().iter().map(|&v| v as u32).collect();
+        let was_reversed = ensure_ccw(&mut pts);
+        let holes: Vec<_> = holes
+            .iter()
+            .map(|h| {
+                let mut h = remove_collinear(h);
+                ensure_cw(&mut h);
+                h
+            })
+            .filter(|h| h.len() >= 3)
+            .collect();
+        let mut verts = pts.clone();
+        let mut hole_idx = Vec::new();
+        for h in &holes {
+            hole_idx.push(verts.len());
+            verts.extend_from_slice(h);
+        }
+        let coords: Vec<f64> = verts.iter().flat_map(|v| [v.x, v.y]).collect();
+        let tri_idx: Vec<_> =
+            earcut(&coords, &hole_idx, 2).unwrap().iter().map(|&v| v as u32).collect();
         let tri = tri_idx
             .array_chunks::<3>()
-            .map(|v| tri(pts[v[0] as usize], pts[v[1] as usize], pts[v[2] as usize]))
+            .map(|v| tri(verts[v[0] as usize], verts[v[1] as usize], verts[v[2] as usize]))
             .collect();
-        let is_convex = is_convex_ccw(&pts);
-        Self { pts, tri, tri_idx, is_convex }
+        let is_convex = holes.is_empty() && is_convex_ccw(&pts);
+        Self { pts, tri, tri_idx, holes, verts, is_convex, was_reversed }
     }
 
+    // Points of the outer ring.
     pub fn pts(&self) -> &[Pt] {
         &self.pts
     }
 
+    // Points of each hole, in CW order.
+    #[must_use]
+    pub fn holes(&self) -> &[Vec<Pt>] {
+        &self.holes
+    }
+
+    // The outer ring followed by each hole.
+    pub fn rings(&self) -> impl Iterator<Item = &[Pt]> + '_ {
+        std::iter::once(self.pts.as_slice()).chain(self.holes.iter().map(Vec::as_slice))
+    }
+
+    // Edges of the outer ring and all holes.
+    pub fn outline_segs(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.rings().flat_map(|r| edges(r).map(|[&p0, &p1]| seg(p0, p1)))
+    }
+
+    // Returns a polygon with |f| applied to every point, including holes.
+    pub fn map_pts(&self, f: &dyn Fn(Pt) -> Pt) -> Poly {
+        let map = |pts: &[Pt]| pts.iter().map(|&v| f(v)).collect::<Vec<_>>();
+        let holes: Vec<_> = self.holes.iter().map(|h| map(h)).collect();
+        Poly::new_with_holes(&map(&self.pts), &holes)
+    }
+
     pub fn edges(&self) -> EdgeIterator<'_> {
         edges(&self.pts)
     }
@@ -59,6 +126,15 @@ impl Poly {
         &self.tri
     }
 
+    // Vertices that tri_idx() indexes into. This is pts() followed by each
+    // of holes(), i.e. after collinear points are removed and the winding is
+    // fixed, so it may differ from the points passed to new().
+    pub fn vertex_buffer(&self) -> &[Pt] {
+        &self.verts
+    }
+
+    // Triangle indices into vertex_buffer(), three per triangle, in the same
+    // order as tri().
     #[must_use]
     pub fn tri_idx(&self) -> &[u32] {
         &self.tri_idx
@@ -68,6 +144,284 @@ impl Poly {
     pub fn is_convex(&self) -> bool {
         self.is_convex
     }
+
+    // Whether the outer points passed to new() were CW and so were reversed.
+    // Importers can use this to tell shells from holes in the source data.
+    #[must_use]
+    pub fn was_reversed(&self) -> bool {
+        self.was_reversed
+    }
+
+    // Lengths of the edges of the outer ring and all holes.
+    fn edge_lengths(&self) -> impl Iterator<Item = f64> + '_ {
+        self.outline_segs().map(|s| s.length())
+    }
+
+    // Signed area enclosed by the polygon, less the area of its holes. Since the
+    // outer ring is stored CCW and holes CW, this is never negative. Degenerate
+    // polygons with fewer than three points have zero area.
+    #[must_use]
+    pub fn signed_area(&self) -> f64 {
+        let holes: f64 = self.holes.iter().map(|h| pts_signed_area(h)).sum();
+        pts_signed_area(&self.pts) + holes
+    }
+
+    // Unsigned area enclosed by the polygon, less the area of its holes.
+    #[must_use]
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    // Whether the outer ring is simple, i.e. no two non-adjacent edges touch.
+    // Triangulation and containment assume this, so check it for untrusted
+    // input. Holes are not checked.
+    #[must_use]
+    pub fn is_simple(&self) -> bool {
+        let segs: Vec<_> = self.edges().map(|[&a, &b]| seg(a, b)).collect();
+        let n = segs.len();
+        for i in 0..n {
+            // Skip the next edge, and the last edge for the first, which share an
+            // endpoint with edge |i|.
+            for j in (i + 2)..n {
+                let adjacent = i == 0 && j == n - 1;
+                if !adjacent && seg_intersects_seg(&segs[i], &segs[j]) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Length of the outer ring. Holes are not included.
+    #[must_use]
+    pub fn perimeter(&self) -> f64 {
+        self.edges().map(|[a, b]| a.dist(*b)).sum()
+    }
+
+    // Points every |spacing| along the outer ring, starting at its first point
+    // and going CCW. The last gap, back to the first point, may be shorter.
+    // For circles, see Circle::outline.
+    #[must_use]
+    pub fn boundary_points(&self, spacing: f64) -> Vec<Pt> {
+        assert!(spacing > 0.0, "spacing must be positive");
+        let mut out = Vec::new();
+        // Distance along the current edge of the next point.
+        let mut next = 0.0;
+        for [&a, &b] in self.edges() {
+            let len = a.dist(b);
+            // Points within tolerance of the end of the edge go on the next one,
+            // so rounding doesn't produce near-duplicates at vertices.
+            while lt(next, len) {
+                out.push(a + (b - a) * (next / len));
+                next += spacing;
+            }
+            next -= len;
+        }
+        out
+    }
+
+    // Sum of the signed exterior angles of the outer ring, in degrees. This is
+    // 360 for simple polygons, since they are stored CCW. Other values, e.g. 0
+    // for a figure eight, indicate self-intersection. Duplicate vertices are
+    // skipped, since a zero-length edge has no direction.
+    #[must_use]
+    pub fn turning_sum_deg(&self) -> f64 {
+        let dirs: Vec<Pt> = self.edges().map(|[a, b]| *b - *a).filter(|d| !d.is_zero()).collect();
+        let turns = edges(&dirs).map(|[d0, d1]| d0.cross(*d1).atan2(d0.dot(*d1)));
+        turns.sum::<f64>().to_degrees()
+    }
+
+    // Length of the shortest edge, including holes. Very short edges can break
+    // triangulation and make distance queries unstable. Returns None if the
+    // polygon has no points.
+    #[must_use]
+    pub fn min_edge_length(&self) -> Option<f64> {
+        self.edge_lengths().min_by(f64::total_cmp)
+    }
+
+    // Length of the longest edge, including holes. Returns None if the polygon
+    // has no points.
+    #[must_use]
+    pub fn max_edge_length(&self) -> Option<f64> {
+        self.edge_lengths().max_by(f64::total_cmp)
+    }
+
+    // Whether any two vertices, including those of holes, are within |tol| of
+    // each other. Such near-duplicates usually come from malformed input, e.g.
+    // a closing point repeated or rounding when importing.
+    #[must_use]
+    pub fn has_duplicate_vertices(&self, tol: f64) -> bool {
+        let mut verts = self.verts.clone();
+        verts.sort_by(|a, b| a.x.total_cmp(&b.x));
+        // Sweep in x, only comparing against vertices within |tol| in x.
+        for (i, a) in verts.iter().enumerate() {
+            for b in verts[i + 1..].iter().take_while(|b| b.x - a.x <= tol) {
+                if a.dist(*b) <= tol {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // Tests containment of |p| against the stored triangulation. This can be
+    // faster for polygons with few triangles, e.g. convex ones. Agrees with
+    // poly_contains_pt, which is canonical, on points off the boundary. Unlike
+    // it, every point on the boundary is contained.
+    #[must_use]
+    pub fn contains_pt_via_tris(&self, p: &Pt) -> bool {
+        // Triangles are CCW, so |p| is in one iff it is not strictly right of
+        // any of its edges.
+        let in_tri = |t: &Tri| t.segs().iter().all(|s| orientation(&s.line(), *p) >= 0);
+        self.bounds().contains(*p) && self.tri.iter().any(in_tri)
+    }
+
+    // Returns the polygon as an Rt if it is an axis-aligned rectangle. Since
+    // collinear points are removed on construction, this is exactly when there
+    // are four vertices and every edge is horizontal or vertical.
+    #[must_use]
+    pub fn as_rect(&self) -> Option<Rt> {
+        let axis_aligned = self.edges().all(|[a, b]| eq(a.x, b.x) || eq(a.y, b.y));
+        (self.pts.len() == 4 && self.holes.is_empty() && axis_aligned).then(|| self.bounds())
+    }
+
+    // Returns a point inside the polygon, e.g. for placing a label. This is the
+    // area centroid if it is inside, otherwise the centroid of the largest
+    // triangle, which handles concave polygons where the centroid is outside.
+    // Returns None for degenerate polygons with no area.
+    #[must_use]
+    pub fn interior_point(&self) -> Option<Pt> {
+        let largest = self.tri.iter().max_by(|a, b| a.area().total_cmp(&b.area()))?;
+        let centroid = self.centroid()?;
+        if poly_contains_pt(self, &centroid) { Some(centroid) } else { Some(tri_centroid(largest)) }
+    }
+
+    // Returns the area centroid, accounting for holes. This is not necessarily
+    // inside the polygon; see interior_point. Returns None if there is no area.
+    #[must_use]
+    pub fn centroid(&self) -> Option<Pt> {
+        self.area_moment().map(|(_, c, _)| c)
+    }
+
+    // Returns the area, area centroid, and polar second moment of area about
+    // the centroid, including holes. Multiply the last by density to get the
+    // rotational inertia of a uniform body. Returns None if there is no area.
+    #[must_use]
+    pub fn area_moment(&self) -> Option<(f64, Pt, f64)> {
+        let (mut area, mut c, mut j) = (0.0, Pt::zero(), 0.0);
+        // Holes are CW, so their contributions are negative.
+        for ring in self.rings() {
+            for [a, b] in edges(ring) {
+                let cross = a.cross(*b);
+                area += cross / 2.0;
+                c += (*a + *b) * cross / 6.0;
+                j += (a.mag2() + a.dot(*b) + b.mag2()) * cross / 12.0;
+            }
+        }
+        if eq(area, 0.0) {
+            return None;
+        }
+        let c = c / area;
+        // Parallel axis theorem to move from the origin to the centroid.
+        Some((area, c, j - area * c.mag2()))
+    }
+
+    // Returns the vertex or edge closest to |p|, and the distance to it.
+    // Vertices win ties, so points closest to an edge endpoint snap to the
+    // vertex. Returns None if the polygon has no points.
+    #[must_use]
+    pub fn nearest_feature(&self, p: Pt) -> Option<(FeatureRef, f64)> {
+        let (v, vd) =
+            self.pts.iter().map(|v| v.dist(p)).enumerate().min_by(|a, b| a.1.total_cmp(&b.1))?;
+        let edge = self
+            .edges()
+            .map(|[&a, &b]| pt_seg_dist(&p, &seg(a, b)).unwrap_or(f64::NAN))
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        Some(match edge {
+            Some((e, ed)) if lt(ed, vd) => (FeatureRef::Edge(e), ed),
+            _ => (FeatureRef::Vertex(v), vd),
+        })
+    }
+
+    // Clips this polygon to |r|. Convex polygons produce at most one polygon.
+    // Non-convex polygons are clipped per triangle, so may produce many.
+    #[must_use]
+    pub fn clip_to_rect(&self, r: &Rt) -> Vec<Poly> {
+        if !self.bounds().intersects(r) {
+            return vec![];
+        }
+        if r.contains_rt(&self.bounds()) {
+            return vec![self.clone()];
+        }
+        if self.is_convex() {
+            clip_convex_pts_rt(self.pts(), r).into_iter().collect()
+        } else {
+            self.tri().iter().filter_map(|t| clip_convex_pts_rt(t.pts(), r)).collect()
+        }
+    }
+
+    // Decomposes the polygon into axis-aligned rects on a grid with spacing
+    // |grid|, e.g. for rasterizing keepouts. Only grid cells fully inside the
+    // polygon are covered, so the rects stay inside it and approximate it
+    // better as |grid| shrinks. Cells are merged into runs along each row, and
+    // runs with the same extent in consecutive rows are merged together.
+    #[must_use]
+    pub fn to_rects(&self, grid: f64) -> Vec<Rt> {
+        assert!(grid > 0.0, "grid must be positive");
+        let b = self.bounds();
+        let (x0, x1) = ((b.l() / grid).floor() as i64, (b.r() / grid).ceil() as i64);
+        let (y0, y1) = ((b.b() / grid).floor() as i64, (b.t() / grid).ceil() as i64);
+        let mut rects = Vec::new();
+        // Rects ending at the current row, which may be extended upwards.
+        let mut open: Vec<Rt> = Vec::new();
+        for y in y0..y1 {
+            let (bot, top) = (y as f64 * grid, (y + 1) as f64 * grid);
+            let mut next = Vec::new();
+            let mut st = None;
+            for x in x0..=x1 {
+                let cell = rt(x as f64 * grid, bot, (x + 1) as f64 * grid, top);
+                // Cells may lie along the boundary, which poly_contains_rt only
+                // partly includes, so test them slightly shrunk.
+                let inside = x < x1 && poly_contains_rt(self, &cell.inset(2.0 * EP, 2.0 * EP));
+                match (inside, st) {
+                    (true, None) => st = Some(cell.l()),
+                    (false, Some(l)) => {
+                        st = None;
+                        let r = cell.l();
+                        let run = match open.iter().position(|o| eq(o.l(), l) && eq(o.r(), r)) {
+                            Some(i) => open.swap_remove(i).united(&rt(l, bot, r, top)),
+                            None => rt(l, bot, r, top),
+                        };
+                        next.push(run);
+                    }
+                    _ => {}
+                }
+            }
+            rects.append(&mut open);
+            open = next;
+        }
+        rects.append(&mut open);
+        rects
+    }
+}
+
+fn tri_centroid(t: &Tri) -> Pt {
+    (t[0] + t[1] + t[2]) / 3.0
+}
+
+// For non-convex polygons this describes the convex hull. An empty polygon has
+// no support point or center, so both panic for it.
+impl Convex for Poly {
+    fn support(&self, dir: Pt) -> Pt {
+        assert!(!self.pts.is_empty(), "support of empty polygon");
+        support_pts(&self.pts, dir)
+    }
+
+    fn center(&self) -> Pt {
+        assert!(!self.pts.is_empty(), "center of empty polygon");
+        self.pts.iter().fold(Pt::zero(), |a, &b| a + b) / self.pts.len() as f64
+    }
 }
 
 impl ShapeOps for Poly {
@@ -83,26 +437,31 @@ impl ShapeOps for Poly {
         match s {
             Shape::Capsule(s) => cap_intersects_poly(s, self),
             Shape::Circle(s) => circ_intersects_poly(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.clone().shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => path_intersects_poly(s, self),
             Shape::Point(s) => poly_contains_pt(self, s),
-            Shape::Polygon(_) => todo!(),
+            Shape::Polygon(s) => poly_intersects_poly(self, s),
             Shape::Rect(s) => poly_intersects_rt(self, s),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => poly_intersects_seg(self, s),
+            Shape::Tri(s) => poly_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
             Shape::Capsule(s) => poly_contains_cap(self, s),
             Shape::Circle(s) => poly_contains_circ(self, s),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => poly_contains_path(self, s),
             Shape::Point(s) => poly_contains_pt(self, s),
-            Shape::Polygon(_) => todo!(),
+            Shape::Polygon(s) => poly_contains_poly(self, s),
             Shape::Rect(s) => poly_contains_rt(self, s),
             Shape::Segment(s) => poly_contains_seg(self, s),
             Shape::Tri(_) => todo!(),
@@ -113,15 +472,25 @@ impl ShapeOps for Poly {
         match s {
             Shape::Capsule(s) => cap_poly_dist(s, self),
             Shape::Circle(s) => circ_poly_dist(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => return s.dist_to_shape(&self.clone().shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.clone().shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => path_poly_dist(s, self),
             Shape::Point(s) => poly_pt_dist(self, s),
-            Shape::Polygon(_) => todo!(),
+            Shape::Polygon(s) => poly_poly_dist(self, s),
             Shape::Rect(s) => poly_rt_dist(self, s),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => poly_seg_dist(self, s),
+            Shape::Tri(s) => poly_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
+    }
+}
+
+// Lets polygons be built directly from point iterators, e.g. via collect().
+impl FromIterator<Pt> for Poly {
+    fn from_iter<I: IntoIterator<Item = Pt>>(iter: I) -> Self {
+        let pts: Vec<_> = iter.into_iter().collect();
+        Self::new(&pts)
     }
 }
 
@@ -162,3 +531,425 @@ impl<'a> Iterator for EdgeIterator<'a> {
 pub fn edges(pts: &[Pt]) -> EdgeIterator<'_> {
     EdgeIterator::new(pts)
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::{assert_relative_eq, relative_eq};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::distance::{poly_outline_pt_dist, poly_pt_dist};
+    use crate::geom::math::EP;
+    use crate::primitive::{poly, pt, square};
+
+    fn area(polys: &[Poly]) -> f64 {
+        polys.iter().flat_map(Poly::tri).map(Tri::area).sum()
+    }
+
+    #[test]
+    fn test_area() {
+        // Given CW, but stored CCW.
+        let p = poly(&[pt(0.0, 0.0), pt(0.0, 2.0), pt(3.0, 2.0), pt(3.0, 0.0)]);
+        assert_relative_eq!(p.signed_area(), 6.0);
+        assert_relative_eq!(p.area(), 6.0);
+
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        assert_relative_eq!(l.area(), 12.0);
+        assert_relative_eq!(l.area(), area(std::slice::from_ref(&l)), epsilon = EP);
+
+        let ring = Poly::new_with_holes(
+            &[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0), pt(0.0, 4.0)],
+            &[vec![pt(1.0, 1.0), pt(2.0, 1.0), pt(2.0, 2.0), pt(1.0, 2.0)]],
+        );
+        assert_relative_eq!(ring.signed_area(), 15.0);
+
+        assert_relative_eq!(poly(&[]).area(), 0.0);
+        assert_relative_eq!(poly(&[pt(0.0, 0.0), pt(1.0, 1.0)]).signed_area(), 0.0);
+    }
+
+    #[test]
+    fn test_centroid() {
+        // L-shape as a 4x2 rect with centroid (2, 1) plus a 2x2 square with
+        // centroid (1, 3), weighted by area.
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        let c = l.centroid().unwrap();
+        assert_relative_eq!(c.x, (8.0 * 2.0 + 4.0 * 1.0) / 12.0, epsilon = EP);
+        assert_relative_eq!(c.y, (8.0 * 1.0 + 4.0 * 3.0) / 12.0, epsilon = EP);
+
+        let t = poly(&[pt(0.0, 0.0), pt(3.0, 0.0), pt(0.0, 3.0)]);
+        let c = t.centroid().unwrap();
+        assert_relative_eq!(c.x, 1.0, epsilon = EP);
+        assert_relative_eq!(c.y, 1.0, epsilon = EP);
+
+        assert_eq!(poly(&[pt(0.0, 0.0), pt(1.0, 0.0)]).centroid(), None);
+        assert_eq!(poly(&[]).centroid(), None);
+    }
+
+    #[test]
+    fn test_is_simple() {
+        assert!(poly(&[pt(0.0, 0.0), pt(2.0, 0.0), pt(3.0, 2.0), pt(0.0, 1.0)]).is_simple());
+        assert!(poly(&[pt(0.0, 0.0), pt(1.0, 0.0), pt(0.0, 1.0)]).is_simple());
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        assert!(l.is_simple());
+
+        let bowtie = poly(&[pt(0.0, 0.0), pt(2.0, 2.0), pt(2.0, 0.0), pt(0.0, 2.0)]);
+        assert!(!bowtie.is_simple());
+        // Non-adjacent edges touching at a vertex.
+        let pinched = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(2.0, 2.0),
+            pt(4.0, 4.0),
+            pt(0.0, 4.0),
+            pt(2.0, 2.0),
+        ]);
+        assert!(!pinched.is_simple());
+    }
+
+    #[test]
+    fn test_perimeter() {
+        let square = square(0.0, 0.0, 1.0);
+        assert_relative_eq!(square.perimeter(), 4.0);
+        let t = poly(&[pt(0.0, 0.0), pt(3.0, 0.0), pt(3.0, 4.0)]);
+        assert_relative_eq!(t.perimeter(), 12.0);
+        assert_relative_eq!(poly(&[]).perimeter(), 0.0);
+    }
+
+    #[test]
+    fn test_boundary_points() {
+        let square = square(0.0, 0.0, 1.0);
+        assert_eq!(square.boundary_points(1.0), square.pts());
+        assert_eq!(
+            square.boundary_points(0.5),
+            [
+                pt(0.0, 0.0),
+                pt(0.5, 0.0),
+                pt(1.0, 0.0),
+                pt(1.0, 0.5),
+                pt(1.0, 1.0),
+                pt(0.5, 1.0),
+                pt(0.0, 1.0),
+                pt(0.0, 0.5),
+            ]
+        );
+        // Spacing doesn't divide the edges, so points wrap around corners.
+        let pts = square.boundary_points(0.3);
+        assert_eq!(pts.len(), 14);
+        assert!(pts.iter().all(|p| relative_eq!(poly_outline_pt_dist(&square, p).unwrap(), 0.0)));
+        let pts = square.boundary_points(0.1);
+        assert_eq!(pts.len(), 40);
+        for [a, b] in pts.array_windows::<2>() {
+            assert!(a.dist(*b) <= 0.1 + EP);
+        }
+        assert_eq!(square.boundary_points(10.0), [pt(0.0, 0.0)]);
+        assert!(poly(&[]).boundary_points(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_turning_sum_deg() {
+        let square = [pt(0.0, 0.0), pt(1.0, 0.0), pt(1.0, 1.0), pt(0.0, 1.0)];
+        assert_relative_eq!(poly(&square).turning_sum_deg(), 360.0, epsilon = EP);
+        // CW input is stored CCW.
+        let cw: Vec<_> = square.iter().rev().copied().collect();
+        assert_relative_eq!(poly(&cw).turning_sum_deg(), 360.0, epsilon = EP);
+
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        assert_relative_eq!(l.turning_sum_deg(), 360.0, epsilon = EP);
+
+        let eight = poly(&[pt(0.0, 0.0), pt(2.0, 2.0), pt(2.0, 0.0), pt(0.0, 2.0)]);
+        assert_relative_eq!(eight.turning_sum_deg(), 0.0, epsilon = EP);
+    }
+
+    #[test]
+    fn test_area_moment() {
+        let square = square(0.0, 0.0, 1.0);
+        let (area, c, j) = square.area_moment().unwrap();
+        assert_relative_eq!(area, 1.0, epsilon = EP);
+        assert_relative_eq!(c.x, 0.5, epsilon = EP);
+        assert_relative_eq!(c.y, 0.5, epsilon = EP);
+        assert_relative_eq!(j, 1.0 / 6.0, epsilon = EP);
+
+        // 2x1 rect: J = w*h*(w^2 + h^2)/12.
+        let r = poly(&[pt(1.0, 1.0), pt(1.0, 2.0), pt(3.0, 2.0), pt(3.0, 1.0)]);
+        let (area, c, j) = r.area_moment().unwrap();
+        assert_relative_eq!(area, 2.0, epsilon = EP);
+        assert_relative_eq!(c.x, 2.0, epsilon = EP);
+        assert_relative_eq!(c.y, 1.5, epsilon = EP);
+        assert_relative_eq!(j, 2.0 * 5.0 / 12.0, epsilon = EP);
+
+        // Square ring: subtract the hole's moment.
+        let ring = Poly::new_with_holes(
+            &[pt(-2.0, -2.0), pt(2.0, -2.0), pt(2.0, 2.0), pt(-2.0, 2.0)],
+            &[vec![pt(-1.0, -1.0), pt(1.0, -1.0), pt(1.0, 1.0), pt(-1.0, 1.0)]],
+        );
+        let (area, c, j) = ring.area_moment().unwrap();
+        assert_relative_eq!(area, 12.0, epsilon = EP);
+        assert_relative_eq!(c.mag(), 0.0, epsilon = EP);
+        assert_relative_eq!(j, 16.0 * 32.0 / 12.0 - 4.0 * 8.0 / 12.0, epsilon = EP);
+
+        assert_eq!(poly(&[pt(0.0, 0.0), pt(1.0, 1.0), pt(2.0, 2.0)]).area_moment(), None);
+    }
+
+    #[test]
+    fn test_contains_pt_via_tris() {
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        let interior = [pt(1.0, 1.0), pt(3.0, 1.0), pt(1.0, 3.0), pt(1.9, 1.9)];
+        let boundary = [pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 2.0), pt(3.0, 2.0), pt(2.0, 3.0)];
+        let exterior = [pt(3.0, 3.0), pt(2.1, 2.1), pt(-1.0, 1.0), pt(5.0, 1.0)];
+        for p in &interior {
+            assert!(l.contains_pt_via_tris(p), "{p}");
+            assert!(poly_contains_pt(&l, p), "{p}");
+        }
+        // Only contains_pt_via_tris includes the whole boundary.
+        for p in &boundary {
+            assert!(l.contains_pt_via_tris(p), "{p}");
+        }
+        for p in &exterior {
+            assert!(!l.contains_pt_via_tris(p), "{p}");
+            assert!(!poly_contains_pt(&l, p), "{p}");
+        }
+    }
+
+    #[test]
+    fn test_interior_point() {
+        // C-shape, whose centroid is in the gap.
+        let c = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 1.0),
+            pt(1.0, 1.0),
+            pt(1.0, 3.0),
+            pt(4.0, 3.0),
+            pt(4.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        assert!(!poly_contains_pt(&c, &pt(1.5, 2.0)));
+        let p = c.interior_point().unwrap();
+        assert!(poly_contains_pt(&c, &p), "{p}");
+
+        // Convex polygons give the centroid.
+        let p = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(0.0, 2.0)]).interior_point();
+        assert_eq!(p, Some(pt(2.0, 1.0)));
+        assert_eq!(poly(&[]).interior_point(), None);
+    }
+
+    #[test]
+    fn test_nearest_feature() {
+        let p = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(0.0, 2.0)]);
+        assert_eq!(p.nearest_feature(pt(4.5, 2.5)), Some((FeatureRef::Vertex(2), 0.5_f64.sqrt())));
+        assert_eq!(p.nearest_feature(pt(4.0, 0.0)), Some((FeatureRef::Vertex(1), 0.0)));
+        assert_eq!(p.nearest_feature(pt(2.0, -1.0)), Some((FeatureRef::Edge(0), 1.0)));
+        assert_eq!(p.nearest_feature(pt(2.0, 1.5)), Some((FeatureRef::Edge(2), 0.5)));
+        assert_eq!(p.nearest_feature(pt(-0.25, 1.0)), Some((FeatureRef::Edge(3), 0.25)));
+        assert_eq!(poly(&[]).nearest_feature(pt(0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_new_with_holes() {
+        let outer = [pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0), pt(0.0, 4.0)];
+        // Hole given CCW, so it needs to be flipped.
+        let hole = vec![pt(1.0, 1.0), pt(3.0, 1.0), pt(3.0, 3.0), pt(1.0, 3.0)];
+        let mut cw = hole.clone();
+        cw.reverse();
+        let p = Poly::new_with_holes(&outer, &[hole]);
+        assert_eq!(p.holes(), [cw]);
+        assert!(!p.is_convex());
+        assert_eq!(p.as_rect(), None);
+
+        // Triangles exclude the hole.
+        assert_relative_eq!(p.tri().iter().map(Tri::area).sum::<f64>(), 12.0);
+        assert_eq!(p.vertex_buffer().len(), 8);
+        for (p0, d) in [(pt(2.0, 2.0), 1.0), (pt(1.5, 2.5), 0.5)] {
+            assert!(!poly_contains_pt(&p, &p0), "{p0}");
+            assert!(!p.contains_pt_via_tris(&p0), "{p0}");
+            assert_relative_eq!(poly_pt_dist(&p, &p0).unwrap(), d);
+        }
+        for p0 in [pt(0.5, 2.0), pt(3.5, 3.5)] {
+            assert!(poly_contains_pt(&p, &p0), "{p0}");
+            assert!(p.contains_pt_via_tris(&p0), "{p0}");
+        }
+        // The hole's boundary is part of the polygon's boundary.
+        for p0 in [pt(1.0, 2.0), pt(3.0, 3.0)] {
+            assert!(p.contains_pt_via_tris(&p0), "{p0}");
+        }
+        assert!(poly_contains_pt(&p, &p.interior_point().unwrap()));
+
+        // Outer ring given CW is also fixed.
+        let mut rev = outer;
+        rev.reverse();
+        let q = Poly::new_with_holes(&rev, p.holes());
+        assert_eq!(q.pts(), p.pts());
+        assert!(!poly_contains_pt(&q, &pt(2.0, 2.0)));
+    }
+
+    #[test]
+    fn test_was_reversed() {
+        let mut pts = vec![pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(2.0, 1.0), pt(0.0, 2.0)];
+        let ccw = poly(&pts);
+        assert!(!ccw.was_reversed());
+        pts.reverse();
+        let cw = poly(&pts);
+        assert!(cw.was_reversed());
+        assert_eq!(cw.pts(), ccw.pts());
+    }
+
+    #[test]
+    fn test_edge_quality() {
+        let clean = square(0.0, 0.0, 2.0);
+        assert_eq!(clean.min_edge_length(), Some(2.0));
+        assert_eq!(clean.max_edge_length(), Some(2.0));
+        assert!(!clean.has_duplicate_vertices(0.01));
+
+        let dup = poly(&[pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 2.0), pt(1.995, 2.004), pt(0.0, 2.0)]);
+        assert!(dup.has_duplicate_vertices(0.01));
+        assert!(!dup.has_duplicate_vertices(0.001));
+        assert_relative_eq!(dup.min_edge_length().unwrap(), pt(0.005, 0.004).mag());
+        assert_eq!(dup.max_edge_length(), Some(2.0));
+
+        // Holes count too.
+        let holed = Poly::new_with_holes(
+            clean.pts(),
+            &[vec![pt(0.5, 0.5), pt(0.5, 1.5), pt(0.503, 1.5), pt(1.5, 0.5)]],
+        );
+        assert!(holed.has_duplicate_vertices(0.01));
+        assert_relative_eq!(holed.min_edge_length().unwrap(), 0.003, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let pts = [pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 1.0), pt(0.0, 1.0)];
+        let p: Poly = (0..4).map(|i| pts[i]).collect();
+        assert_eq!(p.pts(), poly(&pts).pts());
+        assert_eq!(p.tri_idx(), poly(&pts).tri_idx());
+    }
+
+    #[test]
+    fn test_vertex_buffer() {
+        // Clockwise with a collinear point, so the buffer differs from the input.
+        let p = poly(&[
+            pt(0.0, 0.0),
+            pt(0.0, 4.0),
+            pt(2.0, 4.0),
+            pt(2.0, 2.0),
+            pt(3.0, 2.0),
+            pt(4.0, 2.0),
+            pt(4.0, 0.0),
+        ]);
+        assert_eq!(p.vertex_buffer().len(), 6);
+        assert_eq!(p.tri_idx().len(), p.tri().len() * 3);
+        let vb = p.vertex_buffer();
+        for (idx, t) in p.tri_idx().chunks_exact(3).zip(p.tri()) {
+            let rebuilt = tri(vb[idx[0] as usize], vb[idx[1] as usize], vb[idx[2] as usize]);
+            assert_eq!(rebuilt.pts(), t.pts());
+        }
+    }
+
+    #[test]
+    fn test_as_rect() {
+        let sq = poly(&[pt(1.0, 1.0), pt(3.0, 1.0), pt(3.0, 2.0), pt(1.0, 2.0)]);
+        assert_eq!(sq.as_rect(), Some(rt(1.0, 1.0, 3.0, 2.0)));
+
+        // Collinear points are removed, so this is still a rectangle.
+        let cw = poly(&[pt(1.0, 1.0), pt(1.0, 2.0), pt(2.0, 2.0), pt(3.0, 2.0), pt(3.0, 1.0)]);
+        assert_eq!(cw.as_rect(), Some(rt(1.0, 1.0, 3.0, 2.0)));
+
+        let rotated = poly(&[pt(1.0, 0.0), pt(2.0, 1.0), pt(1.0, 2.0), pt(0.0, 1.0)]);
+        assert_eq!(rotated.as_rect(), None);
+
+        let tri = poly(&[pt(0.0, 0.0), pt(1.0, 0.0), pt(0.0, 1.0)]);
+        assert_eq!(tri.as_rect(), None);
+    }
+
+    #[test]
+    fn test_to_rects() {
+        let r = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(0.0, 2.0)]);
+        assert_eq!(r.to_rects(1.0), [rt(0.0, 0.0, 4.0, 2.0)]);
+        assert_eq!(r.to_rects(0.5), [rt(0.0, 0.0, 4.0, 2.0)]);
+
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        let rects = l.to_rects(1.0);
+        assert_eq!(rects, [rt(0.0, 0.0, 4.0, 2.0), rt(0.0, 2.0, 2.0, 4.0)]);
+
+        // Rects stay inside and approach the area as the grid shrinks.
+        let t = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0)]);
+        let mut prev = 0.0;
+        for grid in [1.0, 0.5, 0.1] {
+            let rects = t.to_rects(grid);
+            assert!(rects.iter().all(|r| poly_contains_rt(&t, &r.inset(2.0 * EP, 2.0 * EP))));
+            let covered: f64 = rects.iter().map(Rt::area).sum();
+            assert!(covered > prev && covered <= 8.0);
+            prev = covered;
+        }
+        assert_relative_eq!(prev, 8.0, epsilon = 0.5);
+    }
+
+    #[test]
+    fn test_clip_to_rect() {
+        let r = rt(1.0, 1.0, 3.0, 3.0);
+        let p = poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0)]);
+        let clipped = p.clip_to_rect(&r);
+        assert_eq!(clipped.len(), 1);
+        assert_relative_eq!(area(&clipped), 2.0);
+        assert!(clipped[0].pts().iter().all(|&v| r.contains(v)));
+
+        let inside = poly(&[pt(1.5, 1.5), pt(2.5, 1.5), pt(2.0, 2.5)]);
+        let clipped = inside.clip_to_rect(&r);
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(clipped[0].pts(), inside.pts());
+
+        // Non-convex L-shape keeps only the part inside |r|.
+        let l = poly(&[
+            pt(0.0, 0.0),
+            pt(4.0, 0.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 4.0),
+            pt(0.0, 4.0),
+        ]);
+        let clipped = l.clip_to_rect(&r);
+        assert_relative_eq!(area(&clipped), 3.0);
+        assert!(clipped.iter().flat_map(Poly::pts).all(|&v| r.contains(v)));
+    }
+}
@@ -5,18 +5,21 @@ use crate::geom::contains::{
     rt_contains_cap, rt_contains_circ, rt_contains_path, rt_contains_poly, rt_contains_seg,
     rt_contains_tri,
 };
+use crate::geom::convex::support_pts;
 use crate::geom::distance::{
     cap_rt_dist, circ_rt_dist, poly_rt_dist, pt_rt_dist, rt_path_dist, rt_rt_dist, rt_seg_dist,
+    rt_tri_dist,
 };
 use crate::geom::intersects::{
     cap_intersects_rt, circ_intersects_rt, path_intersects_rt, poly_intersects_rt,
     rt_intersects_rt, rt_intersects_seg, rt_intersects_tri,
 };
-use crate::geom::math::{eq, ge, gt, le, lt};
+use crate::geom::math::{eq, ge, le, lt};
 use crate::primitive::point::{Pt, PtI};
 use crate::primitive::segment::Segment;
 use crate::primitive::shape::Shape;
-use crate::primitive::{ShapeOps, pt, pti, rt, seg};
+use crate::primitive::{Convex, ShapeOps, pt, pti, rt, seg};
+use crate::tf::Tf;
 
 #[must_use]
 #[derive(Debug, Copy, Clone, Display)]
@@ -117,6 +120,29 @@ impl Rt {
         [self.bl(), self.br(), self.tr(), self.tl()]
     }
 
+    // Returns quadrant |i|, in the same order as pts: bl, br, tr, tl. Panics
+    // if |i| is not in 0..4.
+    pub fn quadrant(&self, i: usize) -> Rt {
+        match i {
+            0 => self.bl_quadrant(),
+            1 => self.br_quadrant(),
+            2 => self.tr_quadrant(),
+            3 => self.tl_quadrant(),
+            _ => panic!("quadrant index {i} out of range"),
+        }
+    }
+
+    // Returns the quadrants in the same order as pts: bl, br, tr, tl.
+    pub fn quadrants(&self) -> [Rt; 4] {
+        [self.bl_quadrant(), self.br_quadrant(), self.tr_quadrant(), self.tl_quadrant()]
+    }
+
+    // Returns the corners in the same order as pts after applying |tf|. Unlike
+    // Tf::rt, this always gives the quad, even if |tf| rotates the rectangle.
+    pub fn transformed_corners(&self, tf: &Tf) -> [Pt; 4] {
+        self.pts().map(|p| tf.pt(p))
+    }
+
     pub fn segs(&self) -> [Segment; 4] {
         let pts = self.pts();
         [seg(pts[0], pts[1]), seg(pts[1], pts[2]), seg(pts[2], pts[3]), seg(pts[3], pts[0])]
@@ -131,6 +157,17 @@ impl Rt {
         self.w() * self.h()
     }
 
+    // Length of the diagonal from bl to tr.
+    #[must_use]
+    pub fn diagonal(&self) -> f64 {
+        pt(self.w(), self.h()).mag()
+    }
+
+    #[must_use]
+    pub fn perimeter(&self) -> f64 {
+        2.0 * (self.w() + self.h())
+    }
+
     // Insetting a rectangle more than its size will produce a rectangle
     // containing the single center point.
     pub fn inset(&self, dx: f64, dy: f64) -> Rt {
@@ -139,6 +176,22 @@ impl Rt {
         rt(self.l + wsub, self.b + hsub, self.r - wsub, self.t - hsub)
     }
 
+    // Grows the rectangle by |dx| and |dy| on each side. Negative values
+    // shrink it, clamping to the center point like inset. Empty rectangles
+    // stay empty.
+    pub fn inflated(&self, dx: f64, dy: f64) -> Rt {
+        if self.is_empty() { *self } else { self.inset(-dx, -dy) }
+    }
+
+    // Returns the medial segment along the long axis of the rectangle. A
+    // capsule around it with radius half the short side fills the rectangle.
+    // Squares produce a zero length segment at the center.
+    pub fn centerline(&self) -> Segment {
+        let d = self.w().min(self.h()) / 2.0;
+        let c = self.inset(d, d);
+        seg(c.bl(), c.tr())
+    }
+
     #[must_use]
     pub fn contains(&self, p: Pt) -> bool {
         ge(p.x, self.l()) && ge(p.y, self.b()) && le(p.x, self.r()) && le(p.y, self.t())
@@ -151,7 +204,35 @@ impl Rt {
 
     #[must_use]
     pub fn intersects(&self, r: &Rt) -> bool {
-        le(self.l(), r.r()) && ge(self.r(), r.l()) && gt(self.t(), r.b()) && le(self.b(), r.t())
+        le(self.l(), r.r()) && ge(self.r(), r.l()) && ge(self.t(), r.b()) && le(self.b(), r.t())
+    }
+
+    // Returns the overlap of the two rects, or None if they don't intersect or
+    // either is empty. Since rects are closed, rects which only touch give a
+    // zero width or height rect along the shared edge or corner.
+    #[must_use]
+    pub fn intersection(&self, rect: &Rt) -> Option<Rt> {
+        if self.is_empty() || rect.is_empty() || !self.intersects(rect) {
+            return None;
+        }
+        let l = self.l.max(rect.l);
+        let b = self.b.max(rect.b);
+        // Rects touching to within tolerance may be very slightly apart.
+        let r = self.r().min(rect.r()).max(l);
+        let t = self.t().min(rect.t()).max(b);
+        Some(rt(l, b, r, t))
+    }
+
+    // Restricts this rect to lie within |bounds| by clamping its corners into
+    // |bounds|. Returns None if either rect is empty or they don't overlap, in
+    // which case nothing of this rect would remain. This gives the same result
+    // as `intersection`, but reads better when clipping to e.g. a viewport.
+    #[must_use]
+    pub fn clamped(&self, bounds: &Rt) -> Option<Rt> {
+        if self.is_empty() || bounds.is_empty() || !self.intersects(bounds) {
+            return None;
+        }
+        Some(Rt::enclosing(self.bl().clamp(bounds), self.tr().clamp(bounds)))
     }
 
     pub fn united(&self, rect: &Rt) -> Rt {
@@ -196,6 +277,16 @@ impl PartialEq for Rt {
     }
 }
 
+impl Convex for Rt {
+    fn support(&self, dir: Pt) -> Pt {
+        support_pts(&self.pts(), dir)
+    }
+
+    fn center(&self) -> Pt {
+        self.center()
+    }
+}
+
 impl ShapeOps for Rt {
     fn bounds(&self) -> Rt {
         *self
@@ -209,7 +300,8 @@ impl ShapeOps for Rt {
         match s {
             Shape::Capsule(s) => cap_intersects_rt(s, self),
             Shape::Circle(s) => circ_intersects_rt(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => path_intersects_rt(s, self),
             Shape::Point(s) => self.contains(*s),
@@ -221,10 +313,19 @@ impl ShapeOps for Rt {
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
+        // Every shape lies within its bounds, so containing the bounds is
+        // enough. Lines are unbounded and have empty bounds, so skip them.
+        if !matches!(s, Shape::Line(_)) && self.contains_rt(&s.bounds()) {
+            return true;
+        }
         match s {
             Shape::Capsule(s) => rt_contains_cap(self, s),
             Shape::Circle(s) => rt_contains_circ(self, s),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => rt_contains_path(self, s),
             Shape::Point(s) => self.contains(*s),
@@ -239,15 +340,17 @@ impl ShapeOps for Rt {
         match s {
             Shape::Capsule(s) => cap_rt_dist(s, self),
             Shape::Circle(s) => circ_rt_dist(s, self),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(s) => rt_path_dist(self, s),
             Shape::Point(s) => pt_rt_dist(s, self),
             Shape::Polygon(s) => poly_rt_dist(s, self),
             Shape::Rect(s) => rt_rt_dist(self, s),
             Shape::Segment(s) => rt_seg_dist(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => rt_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
 
@@ -349,3 +452,132 @@ impl_op_ex_commutative!(-|a: &RtI, b: &PtI| -> RtI { RtI::new(a.x - b.x, a.y - b
 impl_op_ex_commutative!(*|a: &RtI, b: &i64| -> RtI {
     RtI::new(a.x * b, a.y * b, a.w * b, a.h * b)
 });
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::primitive::{circ, poly, pt};
+
+    #[test]
+    fn test_intersection() {
+        let r = rt(0.0, 0.0, 4.0, 2.0);
+        assert_eq!(r.intersection(&rt(3.0, 1.0, 6.0, 5.0)), Some(rt(3.0, 1.0, 4.0, 2.0)));
+        assert_eq!(rt(3.0, 1.0, 6.0, 5.0).intersection(&r), Some(rt(3.0, 1.0, 4.0, 2.0)));
+        // Containment.
+        assert_eq!(r.intersection(&rt(1.0, 0.5, 2.0, 1.5)), Some(rt(1.0, 0.5, 2.0, 1.5)));
+        assert_eq!(r.intersection(&r), Some(r));
+        // Touching along an edge or at a corner.
+        assert_eq!(r.intersection(&rt(4.0, 1.0, 5.0, 3.0)), Some(rt(4.0, 1.0, 4.0, 2.0)));
+        assert_eq!(r.intersection(&rt(4.0, 2.0, 5.0, 3.0)), Some(rt(4.0, 2.0, 4.0, 2.0)));
+        // Disjoint or empty.
+        assert_eq!(r.intersection(&rt(5.0, 0.0, 6.0, 2.0)), None);
+        assert_eq!(r.intersection(&Rt::empty()), None);
+        assert_eq!(Rt::empty().intersection(&r), None);
+    }
+
+    #[test]
+    fn test_clamped() {
+        let bounds = rt(0.0, 0.0, 10.0, 5.0);
+        assert_eq!(rt(-2.0, 1.0, 3.0, 8.0).clamped(&bounds), Some(rt(0.0, 1.0, 3.0, 5.0)));
+        assert_eq!(rt(1.0, 1.0, 2.0, 2.0).clamped(&bounds), Some(rt(1.0, 1.0, 2.0, 2.0)));
+        assert_eq!(rt(-1.0, -1.0, 11.0, 6.0).clamped(&bounds), Some(bounds));
+        assert_eq!(rt(10.0, 2.0, 12.0, 3.0).clamped(&bounds), Some(rt(10.0, 2.0, 10.0, 3.0)));
+        assert_eq!(rt(11.0, 2.0, 12.0, 3.0).clamped(&bounds), None);
+        assert_eq!(Rt::empty().clamped(&bounds), None);
+        assert_eq!(bounds.clamped(&Rt::empty()), None);
+        let r = rt(-3.0, 2.0, 4.0, 9.0);
+        assert_eq!(r.clamped(&bounds), r.intersection(&bounds));
+    }
+
+    #[test]
+    fn test_diagonal_perimeter() {
+        let r = rt(1.0, 2.0, 4.0, 6.0);
+        assert_relative_eq!(r.diagonal(), 5.0);
+        assert_relative_eq!(r.perimeter(), 14.0);
+        assert_relative_eq!(rt(1.0, 1.0, 1.0, 1.0).diagonal(), 0.0);
+    }
+
+    #[test]
+    fn test_quadrants() {
+        let r = rt(-1.0, 2.0, 3.0, 4.0);
+        let q = r.quadrants();
+        assert_eq!(q, [r.bl_quadrant(), r.br_quadrant(), r.tr_quadrant(), r.tl_quadrant()]);
+        for (i, v) in q.iter().enumerate() {
+            assert_eq!(r.quadrant(i), *v);
+            // Each quadrant holds the matching corner of the parent.
+            assert_eq!(v.pts()[i], r.pts()[i]);
+        }
+        // Quadrants tile the parent.
+        assert_relative_eq!(q.iter().map(Rt::area).sum::<f64>(), r.area());
+        assert_eq!(q.iter().fold(Rt::empty(), |a, b| a.united(b)), r);
+    }
+
+    #[test]
+    fn test_transformed_corners() {
+        let r = rt(0.0, 0.0, 1.0, 1.0);
+        let pts = r.transformed_corners(&Tf::rotate(90.0));
+        let expected = [pt(0.0, 0.0), pt(0.0, 1.0), pt(-1.0, 1.0), pt(-1.0, 0.0)];
+        for (a, b) in pts.iter().zip(expected) {
+            assert_relative_eq!(a.x, b.x, epsilon = 1e-9);
+            assert_relative_eq!(a.y, b.y, epsilon = 1e-9);
+        }
+
+        let tf = Tf::translate(pt(2.0, 3.0));
+        assert_eq!(
+            r.transformed_corners(&tf),
+            [pt(2.0, 3.0), pt(3.0, 3.0), pt(3.0, 4.0), pt(2.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_contains_shape_fast_path() {
+        let pts: Vec<_> = (0..12)
+            .map(|i| {
+                let a = 2.0 * PI * f64::from(i) / 12.0;
+                pt(5.0 + 2.0 * a.cos(), 5.0 + 2.0 * a.sin())
+            })
+            .collect();
+        let p = poly(&pts);
+        for r in [
+            rt(0.0, 0.0, 10.0, 10.0),
+            rt(3.0, 3.0, 7.0, 7.0),
+            rt(3.5, 3.0, 7.0, 7.0),
+            rt(0.0, 0.0, 5.0, 5.0),
+            rt(8.0, 8.0, 9.0, 9.0),
+        ] {
+            assert_eq!(r.contains_shape(&p.clone().shape()), rt_contains_poly(&r, &p), "{r}");
+        }
+        assert!(rt(0.0, 0.0, 10.0, 10.0).contains_shape(&circ(pt(5.0, 5.0), 5.0).shape()));
+        assert!(!rt(0.0, 0.0, 10.0, 10.0).contains_shape(&circ(pt(5.0, 5.0), 5.1).shape()));
+    }
+
+    #[test]
+    fn test_centerline() {
+        let wide = rt(0.0, 0.0, 10.0, 2.0).centerline();
+        assert_eq!(wide.st(), pt(1.0, 1.0));
+        assert_eq!(wide.en(), pt(9.0, 1.0));
+
+        let tall = rt(1.0, -4.0, 3.0, 4.0).centerline();
+        assert_eq!(tall.st(), pt(2.0, -3.0));
+        assert_eq!(tall.en(), pt(2.0, 3.0));
+
+        let square = rt(0.0, 0.0, 2.0, 2.0).centerline();
+        assert_eq!(square.st(), square.en());
+    }
+
+    #[test]
+    fn test_inflated() {
+        let r = rt(0.0, 0.0, 1.0, 1.0);
+        let inflated = r.inflated(1.0, 1.0);
+        assert_eq!(inflated, rt(-1.0, -1.0, 2.0, 2.0));
+        assert_eq!(inflated.center(), r.center());
+        assert_eq!(r.inflated(-0.25, 0.5), rt(0.25, -0.5, 0.75, 1.5));
+        assert_eq!(r.inflated(-2.0, -2.0), rt(0.5, 0.5, 0.5, 0.5));
+        assert!(Rt::empty().inflated(1.0, 1.0).is_empty());
+    }
+}
@@ -1,13 +1,19 @@
 use derive_more::Display;
 
-use crate::geom::distance::{cap_seg_dist, pt_seg_dist, rt_seg_dist, seg_seg_dist};
-use crate::geom::intersects::{line_intersects_seg, rt_intersects_seg, seg_intersects_seg};
+use crate::geom::distance::{
+    cap_seg_dist, circ_seg_dist, line_pt_dist, poly_seg_dist, pt_seg_dist, rt_seg_dist,
+    seg_seg_dist, seg_tri_dist,
+};
+use crate::geom::intersects::{
+    cap_intersects_seg, circ_intersects_seg, line_intersects_seg, path_intersects_seg,
+    poly_intersects_seg, rt_intersects_seg, seg_intersects_seg, seg_intersects_tri,
+};
 use crate::geom::math::is_collinear;
 use crate::primitive::line_shape::Line;
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
 use crate::primitive::shape::Shape;
-use crate::primitive::{ShapeOps, line};
+use crate::primitive::{ShapeOps, line, seg};
 
 #[must_use]
 #[derive(Debug, Display, Copy, Clone)]
@@ -34,6 +40,18 @@ impl Segment {
         self.en - self.st
     }
 
+    // Normalized direction from st to en, or None if the length is zero.
+    #[must_use]
+    pub fn unit_dir(&self) -> Option<Pt> {
+        let dir = self.dir();
+        (!dir.is_zero()).then(|| dir.norm())
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f64 {
+        self.dir().mag()
+    }
+
     pub const fn line(&self) -> Line {
         line(self.st, self.en)
     }
@@ -55,24 +73,29 @@ impl ShapeOps for Segment {
 
     fn intersects_shape(&self, s: &Shape) -> bool {
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
+            Shape::Capsule(s) => cap_intersects_seg(s, self),
+            Shape::Circle(s) => circ_intersects_seg(s, self),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
             Shape::Line(s) => line_intersects_seg(s, self),
-            Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
-            Shape::Polygon(_) => todo!(),
+            Shape::Path(s) => path_intersects_seg(s, self),
+            Shape::Point(s) => self.contains(*s),
+            Shape::Polygon(s) => poly_intersects_seg(s, self),
             Shape::Rect(s) => rt_intersects_seg(s, self),
             Shape::Segment(s) => seg_intersects_seg(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => seg_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
             Shape::Capsule(_) => todo!(),
             Shape::Circle(_) => todo!(),
             Shape::Compound(_) => todo!(),
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(_) => todo!(),
@@ -86,15 +109,158 @@ impl ShapeOps for Segment {
     fn dist_to_shape(&self, s: &Shape) -> f64 {
         match s {
             Shape::Capsule(s) => cap_seg_dist(s, self),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
+            Shape::Circle(s) => circ_seg_dist(s, self),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
             Shape::Line(_) => todo!(),
             Shape::Path(_) => todo!(),
             Shape::Point(s) => pt_seg_dist(s, self),
-            Shape::Polygon(_) => todo!(),
+            Shape::Polygon(s) => poly_seg_dist(s, self),
             Shape::Rect(s) => rt_seg_dist(s, self),
             Shape::Segment(s) => seg_seg_dist(self, s),
-            Shape::Tri(_) => todo!(),
+            Shape::Tri(s) => seg_tri_dist(self, s),
+        }
+        .unwrap_or(f64::NAN)
+    }
+}
+
+// Removes segments whose endpoints are within |tol| of an earlier segment's,
+// in either direction. The first of each set of duplicates is kept, and the
+// order is otherwise preserved.
+#[must_use]
+pub fn dedup_segments(segs: &[Segment], tol: f64) -> Vec<Segment> {
+    let near = |a: Pt, b: Pt| a.dist(b) <= tol;
+    let same = |a: &Segment, b: &Segment| {
+        (near(a.st, b.st) && near(a.en, b.en)) || (near(a.st, b.en) && near(a.en, b.st))
+    };
+    // Duplicates have leftmost endpoints within |tol| in x, so sort by that
+    // and only compare against nearby segments. Segments are visited in their
+    // original order, so an earlier duplicate has always been decided.
+    let key = |s: &Segment| s.st.x.min(s.en.x);
+    let mut order: Vec<_> = (0..segs.len()).collect();
+    order.sort_by(|&a, &b| key(&segs[a]).total_cmp(&key(&segs[b])).then(a.cmp(&b)));
+    let mut pos = vec![0; segs.len()];
+    for (i, &a) in order.iter().enumerate() {
+        pos[a] = i;
+    }
+    let mut keep = vec![false; segs.len()];
+    for a in 0..segs.len() {
+        let near_key = |&&b: &&usize| (key(&segs[a]) - key(&segs[b])).abs() <= tol;
+        let below = order[..pos[a]].iter().rev().take_while(near_key);
+        let above = order[pos[a] + 1..].iter().take_while(near_key);
+        keep[a] = !below.chain(above).any(|&b| b < a && keep[b] && same(&segs[a], &segs[b]));
+    }
+    segs.iter().zip(keep).filter_map(|(s, keep)| keep.then_some(*s)).collect()
+}
+
+// Returns |a| and |b| as one segment if they are collinear and overlap or
+// touch, to within |tol|.
+fn merge_collinear(a: &Segment, b: &Segment, tol: f64) -> Option<Segment> {
+    let (u, v) = if a.length() >= b.length() { (a, b) } else { (b, a) };
+    let d = u.dir();
+    if d.is_zero() {
+        return (u.st.dist(v.st) <= tol).then_some(*u);
+    }
+    let near = |p: &Pt| line_pt_dist(&u.line(), p).is_some_and(|d| d <= tol);
+    if !near(&v.st) || !near(&v.en) {
+        return None;
+    }
+    // Parameterise |v| along |u|, which spans [0, 1].
+    let t = |p: Pt| d.dot(p - u.st) / d.mag2();
+    let (lo, hi) = (t(v.st).min(t(v.en)), t(v.st).max(t(v.en)));
+    let slack = tol / d.mag();
+    if lo > 1.0 + slack || hi < -slack {
+        return None;
+    }
+    Some(seg(u.st + d * lo.min(0.0), u.st + d * hi.max(1.0)))
+}
+
+// Like dedup_segments, but also merges collinear segments which overlap or
+// touch into a single segment. This is quadratic in the number of segments.
+#[must_use]
+pub fn merge_collinear_segments(segs: &[Segment], tol: f64) -> Vec<Segment> {
+    let mut out: Vec<Segment> = Vec::new();
+    for s in segs {
+        let mut s = *s;
+        // Merging can make |s| reach segments it didn't before, so keep going
+        // until nothing else merges.
+        while let Some(i) = out.iter().position(|o| merge_collinear(o, &s, tol).is_some()) {
+            s = merge_collinear(&out.swap_remove(i), &s, tol).unwrap();
         }
+        out.push(s);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::math::EP;
+    use crate::primitive::{pt, seg};
+
+    fn assert_segs_eq(a: &[Segment], b: &[Segment]) {
+        assert_eq!(a.len(), b.len(), "{a:?} {b:?}");
+        for (a, b) in a.iter().zip(b) {
+            assert_relative_eq!(a.st(), b.st(), epsilon = EP);
+            assert_relative_eq!(a.en(), b.en(), epsilon = EP);
+        }
+    }
+
+    #[test]
+    fn test_dedup_segments() {
+        let a = seg(pt(0.0, 0.0), pt(2.0, 1.0));
+        let b = seg(pt(5.0, 5.0), pt(5.0, 6.0));
+        let segs = [a, seg(a.en(), a.st()), b, seg(pt(0.01, 0.0), pt(2.0, 1.01)), a];
+        assert_segs_eq(&dedup_segments(&segs, 0.1), &[a, b]);
+        assert_segs_eq(&dedup_segments(&segs, 0.0), &[a, b, segs[3]]);
+        // Overlapping but different segments are kept.
+        let c = seg(pt(1.0, 0.0), pt(3.0, 0.0));
+        let d = seg(pt(0.0, 0.0), pt(2.0, 0.0));
+        assert_segs_eq(&dedup_segments(&[c, d], 0.1), &[c, d]);
+        // The first duplicate is kept even if it is not the leftmost.
+        let e = seg(pt(0.05, 0.0), pt(2.0, 1.0));
+        assert_segs_eq(&dedup_segments(&[e, a], 0.1), &[e]);
+        assert_segs_eq(&dedup_segments(&[a, e], 0.1), &[a]);
+        assert!(dedup_segments(&[], 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_merge_collinear_segments() {
+        let a = seg(pt(0.0, 0.0), pt(2.0, 0.0));
+        let b = seg(pt(3.0, 0.0), pt(1.0, 0.0));
+        assert_segs_eq(&merge_collinear_segments(&[a, b], EP), &[seg(pt(0.0, 0.0), pt(3.0, 0.0))]);
+        // Touching at an endpoint, then a segment bridging two others.
+        let c = seg(pt(2.0, 0.0), pt(4.0, 0.0));
+        assert_segs_eq(&merge_collinear_segments(&[a, c], EP), &[seg(pt(0.0, 0.0), pt(4.0, 0.0))]);
+        let e = seg(pt(5.0, 0.0), pt(6.0, 0.0));
+        let bridge = seg(pt(1.5, 0.0), pt(5.5, 0.0));
+        assert_segs_eq(
+            &merge_collinear_segments(&[a, e, bridge], EP),
+            &[seg(pt(0.0, 0.0), pt(6.0, 0.0))],
+        );
+        // Parallel, separated, or at an angle are left alone.
+        let p = seg(pt(0.0, 1.0), pt(2.0, 1.0));
+        let g = seg(pt(2.5, 0.0), pt(4.0, 0.0));
+        let r = seg(pt(1.0, 0.0), pt(1.0, 2.0));
+        assert_segs_eq(&merge_collinear_segments(&[a, p, g, r], EP), &[a, p, g, r]);
+        // Reversed duplicates merge to one.
+        assert_segs_eq(&merge_collinear_segments(&[a, seg(a.en(), a.st())], EP), &[a]);
+    }
+
+    #[test]
+    fn test_unit_dir() {
+        let s = seg(pt(1.0, 1.0), pt(4.0, 5.0));
+        let u = s.unit_dir().unwrap();
+        assert_relative_eq!(u.mag(), 1.0);
+        assert_relative_eq!(u, pt(0.6, 0.8));
+        assert_relative_eq!(s.length(), 5.0);
+        assert_relative_eq!(s.st() + u * s.length(), s.en());
+
+        let s = seg(pt(1.0, 1.0), pt(1.0, 1.0));
+        assert!(s.unit_dir().is_none());
+        assert_relative_eq!(s.length(), 0.0);
     }
 }
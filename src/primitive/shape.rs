@@ -1,15 +1,24 @@
-use crate::geom::math::eq;
+use std::f64::consts::PI;
+
+use ahash::HashMap;
+
+use crate::geom::area::pts_signed_area;
+use crate::geom::distance::{
+    bounds_dist_lower_bound, poly_outline_pt_dist, polyline_pt_dist, pt_seg_dist,
+};
+use crate::geom::math::{EP, eq};
+use crate::geom::qt::query::ShapeInfo;
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
 use crate::primitive::compound::Compound;
 use crate::primitive::line_shape::Line;
 use crate::primitive::path_shape::Path;
 use crate::primitive::point::Pt;
-use crate::primitive::polygon::Poly;
+use crate::primitive::polygon::{Poly, edges};
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
 use crate::primitive::triangle::Tri;
-use crate::primitive::{ShapeOps, poly};
+use crate::primitive::{DynShape, ShapeOps, cap, circ, line, path, poly, seg, tri};
 use crate::tf::Tf;
 
 #[must_use]
@@ -18,6 +27,7 @@ pub enum Shape {
     Capsule(Capsule),
     Circle(Circle),
     Compound(Box<Compound>),
+    Custom(Box<dyn DynShape>),
     Line(Line),
     Path(Path),
     Point(Pt),
@@ -27,6 +37,23 @@ pub enum Shape {
     Tri(Tri),
 }
 
+// A simple bounding shape, e.g. for a bounding volume hierarchy.
+#[must_use]
+#[derive(Debug, Copy, Clone)]
+pub enum BoundPrimitive {
+    Circle(Circle),
+    Rect(Rt),
+}
+
+// Where a point lies relative to a shape.
+#[must_use]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PointClass {
+    Inside,
+    OnBoundary,
+    Outside,
+}
+
 impl Shape {
     pub fn filled(self) -> Shape {
         match self {
@@ -41,6 +68,448 @@ impl Shape {
     pub fn apply(&mut self, tf: &Tf) {
         *self = tf.shape(self);
     }
+
+    // Flattens the shape into closed rings, approximating curves to within
+    // |tol|. Outer rings are CCW and come before any CW holes. Rings from
+    // paths and compounds may overlap, so fill using the nonzero rule.
+    // Points and segments produce degenerate rings and lines produce nothing.
+    #[must_use]
+    pub fn to_polyline(&self, tol: f64) -> Vec<Vec<Pt>> {
+        match self {
+            Shape::Capsule(s) => vec![s.outline(s.st_cap().arc_segments(tol).div_ceil(2))],
+            Shape::Circle(s) => vec![s.outline(s.arc_segments(tol))],
+            Shape::Compound(s) => {
+                s.quadtree().live_shapes().flat_map(|v| v.shape().to_polyline(tol)).collect()
+            }
+            Shape::Custom(s) => s.dyn_to_polyline(tol),
+            Shape::Line(_) => vec![],
+            Shape::Path(s) => s.caps().flat_map(|v| v.shape().to_polyline(tol)).collect(),
+            Shape::Point(s) => vec![vec![*s]],
+            Shape::Polygon(s) => {
+                let mut rings = vec![s.pts().to_vec()];
+                rings.extend_from_slice(s.holes());
+                rings
+            }
+            Shape::Rect(s) => vec![s.pts().to_vec()],
+            Shape::Segment(s) => vec![vec![s.st(), s.en()]],
+            Shape::Tri(s) => vec![s.pts().to_vec()],
+        }
+    }
+
+    // Triangulates the filled area of the shape. Curved boundaries are
+    // approximated to within |tol|. Shapes without area produce nothing.
+    #[must_use]
+    pub fn triangulate(&self, tol: f64) -> Vec<Tri> {
+        match self {
+            Shape::Capsule(s) => s.triangulate(s.st_cap().arc_segments(tol).div_ceil(2)),
+            Shape::Circle(s) => s.triangulate(s.arc_segments(tol)),
+            Shape::Compound(s) => {
+                s.quadtree().live_shapes().flat_map(|v| v.shape().triangulate(tol)).collect()
+            }
+            Shape::Custom(s) => rings_to_polys(s.dyn_to_polyline(tol))
+                .iter()
+                .flat_map(|p| p.tri().to_vec())
+                .collect(),
+            Shape::Line(_) | Shape::Point(_) | Shape::Segment(_) => vec![],
+            Shape::Path(s) => s
+                .caps()
+                .flat_map(|v| v.triangulate(v.st_cap().arc_segments(tol).div_ceil(2)))
+                .collect(),
+            Shape::Polygon(s) => s.tri().to_vec(),
+            Shape::Rect(s) if s.is_empty() => vec![],
+            Shape::Rect(s) => vec![tri(s.bl(), s.br(), s.tr()), tri(s.bl(), s.tr(), s.tl())],
+            Shape::Tri(s) => vec![*s],
+        }
+    }
+
+    // Applies |f| to every defining point of the shape. Radii are kept as is,
+    // so circles, capsules and paths are only mapped correctly if |f| is
+    // locally a rigid motion near them. Rects become polygons unless their
+    // mapped corners are still axis-aligned.
+    pub fn map_points<F: Fn(Pt) -> Pt>(&self, f: F) -> Shape {
+        self.map_points_dyn(&f)
+    }
+
+    fn map_points_dyn(&self, f: &dyn Fn(Pt) -> Pt) -> Shape {
+        let map = |pts: &[Pt]| pts.iter().map(|&v| f(v)).collect::<Vec<_>>();
+        match self {
+            Shape::Capsule(s) => cap(f(s.st()), f(s.en()), s.r()).shape(),
+            Shape::Circle(s) => circ(f(s.p()), s.r()).shape(),
+            Shape::Compound(s) => {
+                let c = Compound::empty();
+                for v in s.quadtree().live_shapes() {
+                    let shape = v.shape().map_points_dyn(f);
+                    // Empty compounds rebuild their bounds, so this can't fail.
+                    c.add_shape(ShapeInfo::new(shape, v.tag(), v.kinds())).unwrap();
+                }
+                c.shape()
+            }
+            Shape::Custom(s) => s.dyn_map_points(f),
+            Shape::Line(s) => line(f(s.st()), f(s.en())).shape(),
+            Shape::Path(s) => path(&map(s.pts()), s.r()).shape(),
+            Shape::Point(s) => f(*s).shape(),
+            Shape::Polygon(s) => s.map_pts(f).shape(),
+            Shape::Rect(s) => {
+                let p = poly(&map(&s.pts()));
+                p.as_rect().map_or_else(|| p.shape(), ShapeOps::shape)
+            }
+            Shape::Segment(s) => seg(f(s.st()), f(s.en())).shape(),
+            Shape::Tri(s) => {
+                let [a, b, c] = *s.pts();
+                tri(f(a), f(b), f(c)).shape()
+            }
+        }
+    }
+
+    // Like contains_shape, but with the boundary of this shape excluded, so |s|
+    // must not touch it. Shapes without area contain nothing. For paths and
+    // compounds, |s| must be inside a single capsule or child shape. Custom
+    // shapes use their outline from DynShape::dyn_to_polyline as the boundary.
+    #[must_use]
+    pub fn contains_shape_open(&self, s: &Shape) -> bool {
+        match self {
+            Shape::Capsule(c) => {
+                let r = c.r() - OPEN_EP;
+                r > 0.0 && cap(c.st(), c.en(), r).contains_shape(s)
+            }
+            Shape::Circle(c) => {
+                let r = c.r() - OPEN_EP;
+                r > 0.0 && circ(c.p(), r).contains_shape(s)
+            }
+            Shape::Compound(c) => {
+                c.quadtree().live_shapes().any(|v| v.shape().contains_shape_open(s))
+            }
+            Shape::Custom(c) => {
+                c.dyn_contains_shape(s)
+                    && !c
+                        .dyn_to_polyline(EP)
+                        .iter()
+                        .flat_map(|ring| edges(ring))
+                        .any(|[&a, &b]| seg(a, b).intersects_shape(s))
+            }
+            Shape::Path(p) => p.caps().any(|c| c.shape().contains_shape_open(s)),
+            Shape::Line(_) | Shape::Point(_) | Shape::Segment(_) => false,
+            Shape::Polygon(_) | Shape::Rect(_) | Shape::Tri(_) => {
+                self.contains_shape(s)
+                    && !self
+                        .to_polyline(EP)
+                        .iter()
+                        .flat_map(|ring| edges(ring))
+                        .any(|[&a, &b]| seg(a, b).intersects_shape(s))
+            }
+        }
+    }
+
+    // Like intersects_shape, but with the boundary of this shape excluded, so
+    // shapes which only touch it do not intersect. Shapes without area
+    // intersect nothing. Custom shapes are treated as the polygon given by
+    // their outline from DynShape::dyn_to_polyline.
+    #[must_use]
+    pub fn intersects_shape_open(&self, s: &Shape) -> bool {
+        if let Shape::Point(_) = s {
+            return self.contains_shape_open(s);
+        }
+        match self {
+            Shape::Capsule(c) => {
+                let r = c.r() - OPEN_EP;
+                r > 0.0 && cap(c.st(), c.en(), r).intersects_shape(s)
+            }
+            Shape::Circle(c) => {
+                let r = c.r() - OPEN_EP;
+                r > 0.0 && circ(c.p(), r).intersects_shape(s)
+            }
+            Shape::Compound(c) => {
+                c.quadtree().live_shapes().any(|v| v.shape().intersects_shape_open(s))
+            }
+            Shape::Custom(c) => rings_to_polys(c.dyn_to_polyline(EP))
+                .into_iter()
+                .any(|p| p.shape().intersects_shape_open(s)),
+            Shape::Path(p) => p.caps().any(|c| c.shape().intersects_shape_open(s)),
+            Shape::Line(_) | Shape::Point(_) | Shape::Segment(_) => false,
+            // Eroding each triangle leaves a gap along the internal edges of
+            // the triangulation, which is covered by thin capsules.
+            Shape::Polygon(p) => {
+                p.tri().iter().filter_map(eroded_tri).any(|t| t.intersects_shape(s))
+                    || open_diagonals(p).iter().any(|c| c.intersects_shape(s))
+            }
+            Shape::Rect(r) => {
+                r.w() > 2.0 * OPEN_EP
+                    && r.h() > 2.0 * OPEN_EP
+                    && r.inset(OPEN_EP, OPEN_EP).intersects_shape(s)
+            }
+            Shape::Tri(t) => eroded_tri(t).is_some_and(|t| t.intersects_shape(s)),
+        }
+    }
+
+    // Returns a rigid transform, i.e. a rotation and translation, mapping this
+    // shape onto |other| if they are congruent to within |tol|. Supports
+    // circles and polygonal shapes: rects, triangles, and polygons without
+    // holes. Polygonal shapes can be compared with each other, since e.g. a
+    // rotated rect is a polygon.
+    #[must_use]
+    pub fn is_congruent(&self, other: &Shape, tol: f64) -> Option<Tf> {
+        if let (Shape::Circle(a), Shape::Circle(b)) = (self, other) {
+            return ((a.r() - b.r()).abs() <= tol).then(|| Tf::translate(b.p() - a.p()));
+        }
+        congruent_rings(&self.congruence_ring()?, &other.congruence_ring()?, tol)
+    }
+
+    fn congruence_ring(&self) -> Option<Vec<Pt>> {
+        match self {
+            Shape::Polygon(s) if s.holes().is_empty() => Some(s.pts().to_vec()),
+            Shape::Rect(s) if !s.is_empty() => Some(s.pts().to_vec()),
+            Shape::Tri(s) => Some(s.pts().to_vec()),
+            _ => None,
+        }
+    }
+
+    // Returns the unsigned distance from |p| to the outline of the shape, even
+    // if |p| is inside it. Returns None for empty and zero-area shapes, for paths
+    // and compounds since their outline is a union of several shapes, for
+    // custom shapes, and if the distance is not finite.
+    #[must_use]
+    pub fn dist_to_boundary(&self, p: Pt) -> Option<f64> {
+        match self {
+            Shape::Capsule(s) => pt_seg_dist(&p, &s.seg()).map(|d| (d - s.r()).abs()),
+            Shape::Circle(s) => Some((p.dist(s.p()) - s.r()).abs()),
+            Shape::Compound(_) | Shape::Custom(_) | Shape::Line(_) | Shape::Path(_) => None,
+            Shape::Point(_) | Shape::Segment(_) => None,
+            Shape::Polygon(s) if s.pts().is_empty() => None,
+            Shape::Polygon(s) => poly_outline_pt_dist(s, &p),
+            Shape::Rect(s) if s.is_empty() => None,
+            Shape::Rect(s) => polyline_pt_dist(&s.pts(), &p),
+            Shape::Tri(s) => polyline_pt_dist(s.pts(), &p),
+        }
+    }
+
+    // Classifies |p| as inside, outside, or on the boundary of the shape, to
+    // within EP. Shapes without area are all boundary, so points on them are
+    // on the boundary. Paths and compounds are classified piece by piece, so
+    // |p| is inside if it is inside any piece, and on the boundary if it is on
+    // the boundary of some piece but inside none. A point where two pieces
+    // only touch is on the boundary. Custom shapes use their outline from
+    // DynShape::dyn_to_polyline to find the boundary.
+    pub fn classify_point(&self, p: Pt) -> PointClass {
+        if self.is_empty_set() {
+            return PointClass::Outside;
+        }
+        let union = |classes: &mut dyn Iterator<Item = PointClass>| {
+            classes.fold(PointClass::Outside, |acc, c| match (acc, c) {
+                (PointClass::Inside, _) | (_, PointClass::Inside) => PointClass::Inside,
+                (PointClass::OnBoundary, _) | (_, PointClass::OnBoundary) => PointClass::OnBoundary,
+                _ => PointClass::Outside,
+            })
+        };
+        match self {
+            Shape::Compound(s) => {
+                union(&mut s.quadtree().live_shapes().map(|v| v.shape().classify_point(p)))
+            }
+            Shape::Custom(s) => {
+                let outline = s.dyn_to_polyline(EP);
+                if outline.iter().any(|ring| polyline_pt_dist(ring, &p).is_some_and(|d| eq(d, 0.0)))
+                {
+                    PointClass::OnBoundary
+                } else if s.dyn_contains_shape(&p.shape()) {
+                    PointClass::Inside
+                } else {
+                    PointClass::Outside
+                }
+            }
+            Shape::Path(s) => union(&mut s.caps().map(|c| c.shape().classify_point(p))),
+            Shape::Line(_) | Shape::Point(_) | Shape::Segment(_) => {
+                if eq(self.dist_to_shape(&p.shape()), 0.0) {
+                    PointClass::OnBoundary
+                } else {
+                    PointClass::Outside
+                }
+            }
+            Shape::Capsule(_)
+            | Shape::Circle(_)
+            | Shape::Polygon(_)
+            | Shape::Rect(_)
+            | Shape::Tri(_) => {
+                if self.dist_to_boundary(p).is_some_and(|d| eq(d, 0.0)) {
+                    PointClass::OnBoundary
+                } else if self.contains_shape(&p.shape()) {
+                    PointClass::Inside
+                } else {
+                    PointClass::Outside
+                }
+            }
+        }
+    }
+
+    // Whether the shape contains no points at all, e.g. an empty rect or a
+    // polygon with no vertices. The empty set is contained by every shape.
+    #[must_use]
+    pub fn is_empty_set(&self) -> bool {
+        match self {
+            Shape::Compound(s) => s.tight_bounds().is_none(),
+            Shape::Path(s) => s.is_empty(),
+            Shape::Polygon(s) => s.pts().is_empty(),
+            Shape::Rect(s) => s.is_empty(),
+            Shape::Capsule(_)
+            | Shape::Circle(_)
+            | Shape::Custom(_)
+            | Shape::Line(_)
+            | Shape::Point(_)
+            | Shape::Segment(_)
+            | Shape::Tri(_) => false,
+        }
+    }
+
+    // Returns whichever of the bounding rect and a bounding circle has the
+    // smaller area. Circles bound themselves exactly. The bounding circle is
+    // centered on the bounds, so it is not always the smallest possible.
+    // Compounds, custom shapes and lines are bounded by their bounding rect.
+    pub fn tightest_bound(&self) -> BoundPrimitive {
+        let b = self.bounds();
+        let enclosing = |pts: &[Pt], r: f64| {
+            let c = b.center();
+            circ(c, pts.iter().map(|p| p.dist(c)).fold(0.0, f64::max) + r)
+        };
+        let circle = match self {
+            Shape::Capsule(s) => enclosing(&[s.st(), s.en()], s.r()),
+            Shape::Circle(s) => return BoundPrimitive::Circle(*s),
+            Shape::Compound(_) | Shape::Custom(_) | Shape::Line(_) => {
+                return BoundPrimitive::Rect(b);
+            }
+            Shape::Path(s) => enclosing(s.pts(), s.r()),
+            Shape::Point(s) => enclosing(&[*s], 0.0),
+            Shape::Polygon(s) => enclosing(s.pts(), 0.0),
+            Shape::Rect(s) => enclosing(&s.pts(), 0.0),
+            Shape::Segment(s) => enclosing(&[s.st(), s.en()], 0.0),
+            Shape::Tri(s) => enclosing(s.pts(), 0.0),
+        };
+        if circle.r() * circle.r() * PI < b.area() {
+            BoundPrimitive::Circle(circle)
+        } else {
+            BoundPrimitive::Rect(b)
+        }
+    }
+
+    // Sort key for painter's algorithm style ordering: (bottom, left) of the
+    // bounds. Lines are unbounded so they get -infinity and sort first.
+    #[must_use]
+    pub fn render_key(&self) -> (f64, f64) {
+        if matches!(self, Shape::Line(_)) {
+            return (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        }
+        let b = self.bounds();
+        (b.b(), b.l())
+    }
+
+    // Returns the union of the bounds of both shapes, or None if either is
+    // unbounded (e.g. a Line).
+    #[must_use]
+    pub fn combined_bounds(&self, other: &Shape) -> Option<Rt> {
+        if matches!(self, Shape::Line(_)) || matches!(other, Shape::Line(_)) {
+            return None;
+        }
+        Some(self.bounds().united(&other.bounds()))
+    }
+}
+
+// How far inside the boundary a shape must be to count as strictly inside for
+// open queries. Since comparisons allow EP of error, shrinking by EP alone
+// would still treat the boundary as inside.
+const OPEN_EP: f64 = 2.0 * EP;
+
+// Shrinks |t| by OPEN_EP towards its incenter, or returns None if it is too
+// thin to have any interior.
+fn eroded_tri(t: &Tri) -> Option<Tri> {
+    let [a, b, c] = *t.pts();
+    let (la, lb, lc) = (b.dist(c), c.dist(a), a.dist(b));
+    let perimeter = la + lb + lc;
+    let inradius = 2.0 * t.area() / perimeter;
+    if inradius.is_nan() || inradius <= OPEN_EP {
+        return None;
+    }
+    let incenter = (a * la + b * lb + c * lc) / perimeter;
+    let k = 1.0 - OPEN_EP / inradius;
+    let f = |p: Pt| incenter + (p - incenter) * k;
+    Some(tri(f(a), f(b), f(c)))
+}
+
+// Capsules of radius OPEN_EP along the internal edges of the triangulation of
+// |p|, shortened so they stay OPEN_EP away from the boundary.
+fn open_diagonals(p: &Poly) -> Vec<Capsule> {
+    let verts = p.vertex_buffer();
+    let key = |a: u32, b: u32| (a.min(b), a.max(b));
+    let mut counts: HashMap<(u32, u32), usize> = HashMap::default();
+    for t in p.tri_idx().chunks_exact(3) {
+        for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+            *counts.entry(key(a, b)).or_default() += 1;
+        }
+    }
+    // Edges in one triangle are on the boundary, and those in two are internal.
+    let boundary: Vec<_> = counts.iter().filter(|&(_, &n)| n == 1).map(|(&e, _)| e).collect();
+    // How far along |dir| from vertex |v| to go to be 2 * OPEN_EP from the
+    // boundary edges at |v|.
+    let inset = |v: u32, dir: Pt| {
+        let sin = boundary
+            .iter()
+            .filter_map(|&(a, b)| {
+                let other = if v == a {
+                    b
+                } else if v == b {
+                    a
+                } else {
+                    return None;
+                };
+                Some(verts[other as usize] - verts[v as usize])
+            })
+            .map(|w| if w.dot(dir) <= 0.0 { 1.0 } else { w.cross(dir).abs() / w.mag() })
+            .fold(1.0, f64::min);
+        2.0 * OPEN_EP / sin
+    };
+    let mut caps = Vec::new();
+    for (&(a, b), _) in counts.iter().filter(|&(_, &n)| n == 2) {
+        let (pa, pb) = (verts[a as usize], verts[b as usize]);
+        let len = pa.dist(pb);
+        let dir = (pb - pa) / len;
+        let (ta, tb) = (inset(a, dir), inset(b, -dir));
+        if ta + tb < len {
+            caps.push(cap(pa + dir * ta, pb - dir * tb, OPEN_EP));
+        }
+    }
+    caps
+}
+
+// Groups rings from to_polyline into polygons: each CCW ring starts a polygon
+// and the CW rings after it are its holes. Degenerate rings are skipped.
+fn rings_to_polys(rings: Vec<Vec<Pt>>) -> Vec<Poly> {
+    let mut polys: Vec<(Vec<Pt>, Vec<Vec<Pt>>)> = Vec::new();
+    for ring in rings {
+        let area = pts_signed_area(&ring);
+        if ring.len() < 3 || eq(area, 0.0) {
+            continue;
+        }
+        match polys.last_mut() {
+            Some((_, holes)) if area < 0.0 => holes.push(ring),
+            _ => polys.push((ring, vec![])),
+        }
+    }
+    polys.iter().map(|(pts, holes)| Poly::new_with_holes(pts, holes)).collect()
+}
+
+// Finds a rigid transform mapping the CCW ring |a| onto |b|, trying each
+// vertex of |b| as the image of the first vertex of |a|.
+fn congruent_rings(a: &[Pt], b: &[Pt], tol: f64) -> Option<Tf> {
+    let n = a.len();
+    if n < 2 || n != b.len() {
+        return None;
+    }
+    let da = a[1] - a[0];
+    (0..n).find_map(|off| {
+        let db = b[(off + 1) % n] - b[off];
+        if (da.mag() - db.mag()).abs() > tol {
+            return None;
+        }
+        let angle = db.y.atan2(db.x) - da.y.atan2(da.x);
+        let tf = Tf::translate(b[off]) * Tf::rotate(angle.to_degrees()) * Tf::translate(-a[0]);
+        (0..n).all(|i| tf.pt(a[i]).dist(b[(i + off) % n]) <= tol).then_some(tf)
+    })
 }
 
 impl ShapeOps for Shape {
@@ -49,6 +518,7 @@ impl ShapeOps for Shape {
             Shape::Capsule(s) => s.bounds(),
             Shape::Circle(s) => s.bounds(),
             Shape::Compound(s) => s.bounds(),
+            Shape::Custom(s) => s.dyn_bounds(),
             Shape::Line(s) => s.bounds(),
             Shape::Path(s) => s.bounds(),
             Shape::Point(s) => s.bounds(),
@@ -68,6 +538,7 @@ impl ShapeOps for Shape {
             Shape::Capsule(us) => us.intersects_shape(s),
             Shape::Circle(us) => us.intersects_shape(s),
             Shape::Compound(us) => us.intersects_shape(s),
+            Shape::Custom(us) => us.dyn_intersects_shape(s),
             Shape::Line(us) => us.intersects_shape(s),
             Shape::Path(us) => us.intersects_shape(s),
             Shape::Point(us) => us.intersects_shape(s),
@@ -83,6 +554,7 @@ impl ShapeOps for Shape {
             Shape::Capsule(us) => us.contains_shape(s),
             Shape::Circle(us) => us.contains_shape(s),
             Shape::Compound(us) => us.contains_shape(s),
+            Shape::Custom(us) => us.dyn_contains_shape(s),
             Shape::Line(us) => us.contains_shape(s),
             Shape::Path(us) => us.contains_shape(s),
             Shape::Point(us) => us.contains_shape(s),
@@ -98,6 +570,7 @@ impl ShapeOps for Shape {
             Shape::Capsule(us) => us.dist_to_shape(s),
             Shape::Circle(us) => us.dist_to_shape(s),
             Shape::Compound(us) => us.dist_to_shape(s),
+            Shape::Custom(us) => us.dyn_dist_to_shape(s),
             Shape::Line(us) => us.dist_to_shape(s),
             Shape::Path(us) => us.dist_to_shape(s),
             Shape::Point(us) => us.dist_to_shape(s),
@@ -107,4 +580,515 @@ impl ShapeOps for Shape {
             Shape::Tri(us) => us.dist_to_shape(s),
         }
     }
+
+    fn dist_to_shape_within(&self, s: &Shape, cutoff: f64) -> Option<f64> {
+        // Compounds prune their quadtree search using |cutoff|.
+        if let Shape::Compound(us) = self {
+            return us.dist_to_shape_within(s, cutoff);
+        }
+        if bounds_dist_lower_bound(&self.bounds(), &s.bounds()) > cutoff {
+            return None;
+        }
+        let d = self.dist_to_shape(s);
+        (d <= cutoff).then_some(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::math::EP;
+    use crate::primitive::circle::MAX_ARC_SEGMENTS;
+    use crate::primitive::compound::Compound;
+    use crate::primitive::polygon::edges;
+    use crate::primitive::{cap, circ, line, path, poly, pt, rt, seg, tri};
+
+    fn signed_area(pts: &[Pt]) -> f64 {
+        edges(pts).map(|[p0, p1]| p0.cross(*p1)).sum::<f64>() / 2.0
+    }
+
+    #[test]
+    fn test_to_polyline() {
+        let square = poly(&[pt(0.0, 0.0), pt(0.0, 2.0), pt(2.0, 2.0), pt(2.0, 0.0)]).shape();
+        let rings = square.to_polyline(0.1);
+        assert_eq!(rings.len(), 1);
+        assert_relative_eq!(signed_area(&rings[0]), 4.0);
+
+        let c = circ(pt(1.0, 1.0), 2.0);
+        let rings = c.shape().to_polyline(0.01);
+        assert_eq!(rings.len(), 1);
+        assert!(signed_area(&rings[0]) > 0.0);
+        for [p0, p1] in edges(&rings[0]) {
+            let mid = (*p0 + *p1) / 2.0;
+            assert!(c.p().dist(mid) > c.r() - 0.01);
+        }
+        // Tiny tolerances are capped rather than saturating to usize::MAX.
+        assert_eq!(c.arc_segments(1e-300), MAX_ARC_SEGMENTS);
+        assert_eq!(c.shape().to_polyline(1e-300)[0].len(), MAX_ARC_SEGMENTS);
+
+        let rings = cap(pt(0.0, 0.0), pt(3.0, 1.0), 0.5).shape().to_polyline(0.01);
+        assert_eq!(rings.len(), 1);
+        assert!(signed_area(&rings[0]) > 0.0);
+
+        let mut compound = Compound::empty();
+        compound.add_shape(ShapeInfo::anon(square)).unwrap();
+        compound.add_shape(ShapeInfo::anon(rt(3.0, 3.0, 4.0, 4.0).shape())).unwrap();
+        let rings = compound.clone().shape().to_polyline(0.1);
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|r| signed_area(r) > 0.0));
+        // Removed children are not flattened.
+        compound.remove_shape(1);
+        assert_eq!(compound.shape().to_polyline(0.1).len(), 1);
+
+        // Holes come after the outer ring, with opposite winding, whatever
+        // winding they were given in.
+        let holed = Poly::new_with_holes(
+            &[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0), pt(0.0, 4.0)],
+            &[vec![pt(1.0, 1.0), pt(3.0, 1.0), pt(3.0, 3.0), pt(1.0, 3.0)]],
+        )
+        .shape();
+        let rings = holed.to_polyline(0.1);
+        assert_eq!(rings.len(), 2);
+        assert_relative_eq!(signed_area(&rings[0]), 16.0);
+        assert_relative_eq!(signed_area(&rings[1]), -4.0);
+        let compound = Compound::from_plain_shapes(&[rt(10.0, 0.0, 11.0, 1.0).shape(), holed]);
+        let areas: Vec<_> =
+            compound.shape().to_polyline(0.1).iter().map(|r| signed_area(r)).collect();
+        assert_eq!(areas, [1.0, 16.0, -4.0]);
+        assert!(line(pt(0.0, 0.0), pt(1.0, 0.0)).shape().to_polyline(0.1).is_empty());
+    }
+
+    #[test]
+    fn test_triangulate() {
+        let r = rt(1.0, 2.0, 4.0, 3.0);
+        let tris = r.shape().triangulate(0.1);
+        assert_eq!(tris.len(), 2);
+        assert_relative_eq!(tris.iter().map(Tri::area).sum::<f64>(), r.area());
+
+        let c = circ(pt(1.0, 1.0), 2.0);
+        let area: f64 = c.shape().triangulate(0.001).iter().map(Tri::area).sum();
+        assert_relative_eq!(area, PI * 4.0, max_relative = 0.01);
+        assert!(area < PI * 4.0);
+
+        let c = cap(pt(0.0, 0.0), pt(3.0, 0.0), 0.5);
+        let area: f64 = c.shape().triangulate(0.001).iter().map(Tri::area).sum();
+        assert_relative_eq!(area, 3.0 + PI * 0.25, max_relative = 0.01);
+
+        let mut compound = Compound::empty();
+        compound.add_shape(ShapeInfo::anon(r.shape())).unwrap();
+        compound.add_shape(ShapeInfo::anon(rt(5.0, 5.0, 6.0, 6.0).shape())).unwrap();
+        assert_eq!(compound.clone().shape().triangulate(0.1).len(), 4);
+        // Removed children are not triangulated.
+        compound.remove_shape(0);
+        let tris = compound.shape().triangulate(0.1);
+        assert_eq!(tris.len(), 2);
+        assert_relative_eq!(tris.iter().map(Tri::area).sum::<f64>(), 1.0);
+
+        assert!(line(pt(0.0, 0.0), pt(1.0, 0.0)).shape().triangulate(0.1).is_empty());
+        assert!(pt(0.0, 0.0).shape().triangulate(0.1).is_empty());
+        assert!(Rt::empty().shape().triangulate(0.1).is_empty());
+    }
+
+    #[test]
+    fn test_map_points() {
+        let f = |p: Pt| pt(p.x * p.x, p.y + p.x);
+        let p = poly(&[pt(1.0, 0.0), pt(2.0, 0.0), pt(3.0, 2.0), pt(1.0, 1.0)]);
+        let Shape::Polygon(mapped) = p.shape().map_points(f) else { panic!("expected polygon") };
+        assert_eq!(mapped.pts(), &[pt(1.0, 1.0), pt(4.0, 2.0), pt(9.0, 5.0), pt(1.0, 2.0)]);
+
+        // Rects stay rects under axis-aligned maps, otherwise become polygons.
+        let r = rt(1.0, 1.0, 2.0, 3.0).shape();
+        let Shape::Rect(mapped) = r.map_points(|p| pt(p.x * 2.0, p.y + 1.0)) else {
+            panic!("expected rect")
+        };
+        assert_eq!(mapped, rt(2.0, 2.0, 4.0, 4.0));
+        assert!(matches!(r.map_points(f), Shape::Polygon(_)));
+
+        let Shape::Circle(c) = circ(pt(2.0, 1.0), 0.5).shape().map_points(f) else {
+            panic!("expected circle")
+        };
+        assert_eq!(c.p(), pt(4.0, 3.0));
+        assert_relative_eq!(c.r(), 0.5);
+
+        let mut compound = Compound::empty();
+        compound.add_shape(ShapeInfo::anon(pt(1.0, 1.0).shape())).unwrap();
+        compound.add_shape(ShapeInfo::anon(pt(3.0, 0.0).shape())).unwrap();
+        let mapped = compound.clone().shape().map_points(f);
+        assert_eq!(mapped.bounds(), rt(1.0, 2.0, 9.0, 3.0));
+
+        // Removed children are not brought back.
+        compound.remove_shape(1);
+        let Shape::Compound(mapped) = compound.shape().map_points(f) else {
+            panic!("expected compound")
+        };
+        assert_eq!(mapped.quadtree().shape_count(), 1);
+        assert_eq!(mapped.tight_bounds(), Some(rt(1.0, 2.0, 1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_dist_to_boundary() {
+        let c = circ(pt(1.0, 1.0), 2.0).shape();
+        assert_relative_eq!(c.dist_to_boundary(pt(1.0, 1.0)).unwrap(), 2.0);
+        assert_relative_eq!(c.dist_to_boundary(pt(1.0, 4.0)).unwrap(), 1.0);
+
+        let r = rt(0.0, 0.0, 4.0, 2.0).shape();
+        assert_relative_eq!(r.dist_to_boundary(pt(3.9, 1.0)).unwrap(), 0.1, epsilon = EP);
+        assert_relative_eq!(r.dist_to_boundary(pt(2.0, 1.0)).unwrap(), 1.0);
+        assert_relative_eq!(r.dist_to_boundary(pt(5.0, 1.0)).unwrap(), 1.0);
+        assert_relative_eq!(r.dist_to_shape(&pt(3.9, 1.0).shape()), 0.0);
+
+        let c = cap(pt(0.0, 0.0), pt(4.0, 0.0), 1.0).shape();
+        assert_relative_eq!(c.dist_to_boundary(pt(2.0, 0.25)).unwrap(), 0.75);
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0)).shape();
+        assert_relative_eq!(t.dist_to_boundary(pt(1.0, 2.0)).unwrap(), 0.5_f64.sqrt());
+
+        assert!(pt(0.0, 0.0).shape().dist_to_boundary(pt(1.0, 1.0)).is_none());
+        assert!(line(pt(0.0, 0.0), pt(1.0, 0.0)).shape().dist_to_boundary(pt(1.0, 1.0)).is_none());
+        assert!(Rt::empty().shape().dist_to_boundary(pt(1.0, 1.0)).is_none());
+        assert!(poly(&[]).shape().dist_to_boundary(pt(1.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_classify_point() {
+        let c = circ(pt(1.0, 1.0), 2.0).shape();
+        assert_eq!(c.classify_point(pt(3.0, 1.0)), PointClass::OnBoundary);
+        assert_eq!(c.classify_point(pt(2.999, 1.0)), PointClass::Inside);
+        assert_eq!(c.classify_point(pt(3.001, 1.0)), PointClass::Outside);
+        assert_eq!(c.classify_point(pt(1.0, 1.0)), PointClass::Inside);
+
+        let shapes = [
+            rt(0.0, 0.0, 4.0, 2.0).shape(),
+            tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 4.0)).shape(),
+            poly(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 2.0), pt(0.0, 2.0)]).shape(),
+            cap(pt(1.0, 1.0), pt(3.0, 1.0), 1.0).shape(),
+        ];
+        for s in &shapes {
+            assert_eq!(s.classify_point(pt(2.0, 0.0)), PointClass::OnBoundary, "{s:?}");
+            assert_eq!(s.classify_point(pt(2.0, 0.5)), PointClass::Inside, "{s:?}");
+            assert_eq!(s.classify_point(pt(2.0, -0.5)), PointClass::Outside, "{s:?}");
+        }
+        // Corners are on the boundary.
+        assert_eq!(shapes[0].classify_point(pt(4.0, 2.0)), PointClass::OnBoundary);
+        assert_eq!(shapes[1].classify_point(pt(0.0, 4.0)), PointClass::OnBoundary);
+        assert_eq!(shapes[3].classify_point(pt(4.0, 1.0)), PointClass::OnBoundary);
+
+        let s = seg(pt(0.0, 0.0), pt(2.0, 0.0)).shape();
+        assert_eq!(s.classify_point(pt(1.0, 0.0)), PointClass::OnBoundary);
+        assert_eq!(s.classify_point(pt(1.0, 1.0)), PointClass::Outside);
+        let l = line(pt(0.0, 0.0), pt(1.0, 1.0)).shape();
+        assert_eq!(l.classify_point(pt(-3.0, -3.0)), PointClass::OnBoundary);
+        assert_eq!(l.classify_point(pt(1.0, 0.0)), PointClass::Outside);
+        assert_eq!(pt(1.0, 0.0).shape().classify_point(pt(1.0, 0.0)), PointClass::OnBoundary);
+        assert_eq!(Rt::empty().shape().classify_point(pt(0.0, 0.0)), PointClass::Outside);
+
+        // Paths are inside at joints between capsules.
+        let p = path(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0)], 1.0).shape();
+        assert_eq!(p.classify_point(pt(4.0, 0.0)), PointClass::Inside);
+        assert_eq!(p.classify_point(pt(4.5, 0.0)), PointClass::Inside);
+        assert_eq!(p.classify_point(pt(2.0, 1.0)), PointClass::OnBoundary);
+        assert_eq!(p.classify_point(pt(5.0, 2.0)), PointClass::OnBoundary);
+        assert_eq!(p.classify_point(pt(2.0, 2.0)), PointClass::Outside);
+        let mut c = Compound::from_plain_shapes(&[
+            rt(0.0, 0.0, 2.0, 2.0).shape(),
+            circ(pt(1.0, 2.0), 0.5).shape(),
+            circ(pt(10.0, 0.0), 1.0).shape(),
+        ]);
+        assert_eq!(c.clone().shape().classify_point(pt(1.0, 2.0)), PointClass::Inside);
+        assert_eq!(c.clone().shape().classify_point(pt(2.0, 1.0)), PointClass::OnBoundary);
+        assert_eq!(c.clone().shape().classify_point(pt(10.0, 0.5)), PointClass::Inside);
+        c.remove_shape(2);
+        assert_eq!(c.shape().classify_point(pt(10.0, 0.5)), PointClass::Outside);
+    }
+
+    #[test]
+    fn test_is_congruent() {
+        let sq = rt(0.0, 0.0, 1.0, 1.0).shape();
+        let moved = rt(5.0, -2.0, 6.0, -1.0).shape();
+        let tf = sq.is_congruent(&moved, EP).unwrap();
+        assert_relative_eq!(tf.pt(pt(0.0, 0.0)).x, 5.0);
+        assert_relative_eq!(tf.pt(pt(0.0, 0.0)).y, -2.0);
+
+        let rotated = (Tf::translate(pt(3.0, 4.0)) * Tf::rotate(30.0)).shape(&sq);
+        assert!(matches!(rotated, Shape::Polygon(_)));
+        let tf = sq.is_congruent(&rotated, 1e-9).unwrap();
+        let Shape::Polygon(mapped) = tf.shape(&sq) else { panic!("expected polygon") };
+        for p in mapped.pts() {
+            assert_relative_eq!(rotated.dist_to_boundary(*p).unwrap(), 0.0, epsilon = 1e-9);
+        }
+        assert!(rotated.is_congruent(&moved, 1e-9).is_some());
+        assert!(sq.is_congruent(&rt(0.0, 0.0, 1.0, 2.0).shape(), EP).is_none());
+
+        let t = tri(pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 3.0)).shape();
+        let t2 = (Tf::translate(pt(-1.0, 2.0)) * Tf::rotate(100.0)).shape(&t);
+        assert!(t.is_congruent(&t2, 1e-9).is_some());
+        let mirrored = tri(pt(0.0, 0.0), pt(-4.0, 0.0), pt(0.0, 3.0)).shape();
+        assert!(t.is_congruent(&mirrored, 1e-9).is_none());
+
+        let c = circ(pt(1.0, 1.0), 2.0).shape();
+        let tf = c.is_congruent(&circ(pt(3.0, 0.0), 2.0).shape(), EP).unwrap();
+        assert_eq!(tf.pt(pt(1.0, 1.0)), pt(3.0, 0.0));
+        assert!(c.is_congruent(&circ(pt(1.0, 1.0), 2.5).shape(), EP).is_none());
+        assert!(c.is_congruent(&sq, EP).is_none());
+    }
+
+    #[test]
+    fn test_open_queries() {
+        let r = rt(0.0, 0.0, 2.0, 2.0).shape();
+        let edge = pt(2.0, 1.0).shape();
+        assert!(r.contains_shape(&edge));
+        assert!(!r.contains_shape_open(&edge));
+        assert!(r.intersects_shape(&edge));
+        assert!(!r.intersects_shape_open(&edge));
+        let inside = pt(1.9, 1.0).shape();
+        assert!(r.contains_shape_open(&inside));
+        assert!(r.intersects_shape_open(&inside));
+
+        // Touching rects only intersect when closed.
+        let touching = rt(2.0, 0.5, 3.0, 1.5).shape();
+        assert!(r.intersects_shape(&touching));
+        assert!(!r.intersects_shape_open(&touching));
+        assert!(r.intersects_shape_open(&rt(1.5, 0.5, 3.0, 1.5).shape()));
+        assert!(!r.contains_shape_open(&rt(1.0, 0.0, 2.0, 1.0).shape()));
+        assert!(r.contains_shape_open(&rt(0.5, 0.5, 1.5, 1.5).shape()));
+
+        let c = circ(pt(0.0, 0.0), 1.0).shape();
+        assert!(c.contains_shape(&pt(1.0, 0.0).shape()));
+        assert!(!c.contains_shape_open(&pt(1.0, 0.0).shape()));
+        let tangent = circ(pt(2.0, 0.0), 1.0).shape();
+        assert!(c.intersects_shape(&tangent));
+        assert!(!c.intersects_shape_open(&tangent));
+
+        // Polygons, including a point on an internal edge of the triangulation.
+        let p = poly(&[pt(0.0, 0.0), pt(2.0, 0.0), pt(2.0, 2.0), pt(0.0, 2.0)]).shape();
+        assert!(p.contains_shape_open(&pt(1.0, 1.0).shape()));
+        assert!(p.intersects_shape_open(&pt(1.0, 1.0).shape()));
+        assert!(!p.intersects_shape_open(&touching));
+        assert!(p.intersects_shape_open(&seg(pt(1.0, -1.0), pt(1.0, 0.5)).shape()));
+        assert!(!p.intersects_shape_open(&seg(pt(0.0, -1.0), pt(0.0, 3.0)).shape()));
+
+        assert!(
+            !seg(pt(0.0, 0.0), pt(2.0, 0.0)).shape().contains_shape_open(&pt(1.0, 0.0).shape())
+        );
+
+        // Thin shapes lying along an internal edge of the triangulation are
+        // inside the polygon, but shapes touching its corners are not.
+        let Shape::Polygon(sq) = &p else { unreachable!() };
+        for t in sq.tri_idx().chunks_exact(3) {
+            let verts = sq.vertex_buffer();
+            for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                let (a, b) = (verts[a as usize], verts[b as usize]);
+                let along = seg(a + (b - a) * 0.25, a + (b - a) * 0.75).shape();
+                let on_boundary = eq(a.x, b.x) || eq(a.y, b.y);
+                assert_eq!(p.intersects_shape_open(&along), !on_boundary, "{a} {b}");
+                assert_eq!(p.intersects_shape_open(&seg(a, b).shape()), !on_boundary);
+            }
+        }
+        assert!(!p.intersects_shape_open(&seg(pt(-1.0, -1.0), pt(0.0, 0.0)).shape()));
+        assert!(!p.intersects_shape_open(&seg(pt(2.0, 2.0), pt(3.0, 3.0)).shape()));
+        let u = poly(&[
+            pt(0.0, 0.0),
+            pt(6.0, 0.0),
+            pt(6.0, 6.0),
+            pt(4.0, 6.0),
+            pt(4.0, 2.0),
+            pt(2.0, 2.0),
+            pt(2.0, 6.0),
+            pt(0.0, 6.0),
+        ])
+        .shape();
+        assert!(!u.intersects_shape_open(&rt(2.0, 2.0, 4.0, 6.0).shape()));
+        assert!(u.intersects_shape_open(&rt(1.9, 2.0, 4.0, 6.0).shape()));
+
+        // Paths and compounds, piece by piece.
+        let pa = path(&[pt(0.0, 0.0), pt(4.0, 0.0), pt(4.0, 4.0)], 1.0).shape();
+        assert!(pa.contains_shape_open(&pt(4.0, 2.0).shape()));
+        assert!(!pa.contains_shape_open(&pt(4.0, 5.0).shape()));
+        assert!(pa.intersects_shape_open(&rt(4.5, 2.0, 6.0, 3.0).shape()));
+        assert!(!pa.intersects_shape_open(&rt(5.0, 2.0, 6.0, 3.0).shape()));
+        let mut c = Compound::from_plain_shapes(&[r.clone(), circ(pt(10.0, 0.0), 1.0).shape()]);
+        assert!(c.clone().shape().contains_shape_open(&pt(10.5, 0.0).shape()));
+        assert!(!c.clone().shape().intersects_shape_open(&touching));
+        assert!(c.clone().shape().intersects_shape_open(&pt(9.5, 0.0).shape()));
+        c.remove_shape(1);
+        assert!(!c.shape().intersects_shape_open(&pt(9.5, 0.0).shape()));
+    }
+
+    // A user defined rect which only implements the required methods.
+    #[derive(Debug, Clone)]
+    struct Boxed(Rt);
+
+    impl DynShape for Boxed {
+        fn dyn_bounds(&self) -> Rt {
+            self.0
+        }
+
+        fn dyn_intersects_shape(&self, s: &Shape) -> bool {
+            self.0.intersects_shape(s)
+        }
+
+        fn dyn_contains_shape(&self, s: &Shape) -> bool {
+            self.0.contains_shape(s)
+        }
+
+        fn dyn_dist_to_shape(&self, s: &Shape) -> f64 {
+            self.0.dist_to_shape(s)
+        }
+
+        fn clone_box(&self) -> Box<dyn DynShape> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_custom_shape_defaults() {
+        let r = rt(1.0, 1.0, 3.0, 2.0);
+        let s = Shape::Custom(Box::new(Boxed(r)));
+        assert_eq!(s.to_polyline(0.1), [r.pts().to_vec()]);
+        assert_relative_eq!(s.triangulate(0.1).iter().map(Tri::area).sum::<f64>(), 2.0);
+        let mapped = s.map_points(|p| p * 2.0);
+        assert!(matches!(mapped, Shape::Polygon(_)));
+        assert_eq!(mapped.bounds(), rt(2.0, 2.0, 6.0, 4.0));
+        assert_eq!(Tf::translate(pt(1.0, 0.0)).shape(&s).bounds(), rt(2.0, 1.0, 4.0, 2.0));
+
+        // Built in shapes contain custom shapes via their bounds.
+        assert!(circ(pt(2.0, 1.5), 1.2).shape().contains_shape(&s));
+        assert!(!circ(pt(2.0, 1.5), 1.0).shape().contains_shape(&s));
+        assert!(rt(0.0, 0.0, 3.0, 3.0).shape().contains_shape(&s));
+        assert!(poly(&rt(0.0, 0.0, 3.0, 3.0).pts()).shape().contains_shape(&s));
+        assert!(tri(pt(0.0, 0.0), pt(8.0, 0.0), pt(0.0, 8.0)).shape().contains_shape(&s));
+        assert!(!cap(pt(0.0, 0.0), pt(8.0, 0.0), 1.0).shape().contains_shape(&s));
+        assert_eq!(s.classify_point(pt(2.0, 1.5)), PointClass::Inside);
+        assert_eq!(s.classify_point(pt(3.0, 1.5)), PointClass::OnBoundary);
+        assert_eq!(s.classify_point(pt(4.0, 1.5)), PointClass::Outside);
+        // Open queries use the outline as the boundary.
+        assert!(s.contains_shape_open(&pt(2.0, 1.5).shape()));
+        assert!(!s.contains_shape_open(&pt(3.0, 1.5).shape()));
+        assert!(!s.contains_shape_open(&rt(1.0, 1.0, 2.0, 2.0).shape()));
+        assert!(s.intersects_shape_open(&rt(2.0, 1.2, 5.0, 1.8).shape()));
+        assert!(!s.intersects_shape_open(&rt(3.0, 1.0, 5.0, 2.0).shape()));
+        assert!(!s.intersects_shape_open(&pt(3.0, 1.5).shape()));
+
+        // Compounds are transformed child by child.
+        let c = Compound::from_plain_shapes(&[circ(pt(0.0, 0.0), 1.0).shape(), s]).shape();
+        let moved = Tf::translate(pt(10.0, 0.0)).shape(&c);
+        assert_eq!(moved.bounds(), rt(9.0, -1.0, 13.0, 2.0));
+        assert!(moved.contains_shape(&pt(10.5, 0.0).shape()));
+    }
+
+    #[test]
+    fn test_contains_empty_set() {
+        let empties = [
+            poly(&[]).shape(),
+            path(&[], 1.0).shape(),
+            Rt::empty().shape(),
+            Compound::empty().shape(),
+        ];
+        let receivers = [
+            cap(pt(0.0, 0.0), pt(1.0, 0.0), 1.0).shape(),
+            circ(pt(0.0, 0.0), 1.0).shape(),
+            Compound::from_plain_shapes(&[circ(pt(5.0, 5.0), 1.0).shape()]).shape(),
+            line(pt(0.0, 0.0), pt(1.0, 0.0)).shape(),
+            path(&[pt(0.0, 0.0), pt(1.0, 1.0)], 1.0).shape(),
+            pt(3.0, 3.0).shape(),
+            poly(&[pt(0.0, 0.0), pt(1.0, 0.0), pt(0.0, 1.0)]).shape(),
+            rt(0.0, 0.0, 1.0, 1.0).shape(),
+            Rt::empty().shape(),
+            seg(pt(0.0, 0.0), pt(1.0, 0.0)).shape(),
+            tri(pt(0.0, 0.0), pt(1.0, 0.0), pt(0.0, 1.0)).shape(),
+        ];
+        for r in &receivers {
+            for e in &empties {
+                assert!(e.is_empty_set(), "{e:?}");
+                assert!(r.contains_shape(e), "{r:?} contains {e:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dist_to_shape_within() {
+        let probe = pt(0.0, 0.0).shape();
+        let shapes = [
+            rt(3.0, -1.0, 4.0, 1.0).shape(),
+            poly(&[pt(0.0, 5.0), pt(1.0, 5.0), pt(0.0, 6.0)]).shape(),
+            seg(pt(-3.0, -3.0), pt(-3.0, 3.0)).shape(),
+            tri(pt(0.5, 0.0), pt(2.0, 0.0), pt(2.0, 2.0)).shape(),
+            line(pt(0.0, -4.0), pt(1.0, -4.0)).shape(),
+        ];
+        for s in &shapes {
+            let d = probe.dist_to_shape(s);
+            for cutoff in [d, d + 0.5, 10.0] {
+                let within = probe.dist_to_shape_within(s, cutoff).unwrap();
+                assert_relative_eq!(within, d);
+            }
+            if d > 0.0 {
+                assert_eq!(probe.dist_to_shape_within(s, d - 0.1), None, "{s:?}");
+            }
+        }
+
+        let c = Compound::from_plain_shapes(&shapes[..3]).shape();
+        assert_relative_eq!(c.dist_to_shape_within(&probe, 3.0).unwrap(), 3.0);
+        assert_eq!(c.dist_to_shape_within(&probe, 2.5), None);
+    }
+
+    #[test]
+    fn test_tightest_bound() {
+        let c = circ(pt(1.0, 2.0), 3.0);
+        let BoundPrimitive::Circle(b) = c.shape().tightest_bound() else { panic!() };
+        assert_eq!((b.p(), b.r()), (c.p(), c.r()));
+
+        let thin = rt(0.0, 0.0, 10.0, 0.5).shape();
+        assert!(matches!(thin.tightest_bound(), BoundPrimitive::Rect(b) if b == thin.bounds()));
+
+        // A regular octagon is closer to a circle than to a square.
+        let octagon = poly(&circ(pt(0.0, 0.0), 1.0).outline(8)).shape();
+        let BoundPrimitive::Circle(b) = octagon.tightest_bound() else { panic!() };
+        assert_relative_eq!(b.r(), 1.0, epsilon = EP);
+
+        // A square capsule is also bounded by a circle.
+        let c = cap(pt(0.0, 0.0), pt(0.1, 0.0), 1.0).shape();
+        let BoundPrimitive::Circle(b) = c.tightest_bound() else { panic!() };
+        assert_relative_eq!(b.r(), 1.05, epsilon = EP);
+        assert!(matches!(
+            line(pt(0.0, 0.0), pt(1.0, 1.0)).shape().tightest_bound(),
+            BoundPrimitive::Rect(_)
+        ));
+    }
+
+    #[test]
+    fn test_render_key() {
+        let mut shapes = [
+            rt(5.0, 5.0, 6.0, 6.0).shape(),
+            circ(pt(3.0, 1.0), 1.0).shape(),
+            pt(-1.0, 5.0).shape(),
+            line(pt(0.0, 10.0), pt(1.0, 10.0)).shape(),
+            rt(1.0, 0.0, 2.0, 1.0).shape(),
+        ];
+        shapes.sort_by(|a, b| a.render_key().partial_cmp(&b.render_key()).unwrap());
+        let keys: Vec<_> = shapes.iter().map(Shape::render_key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (f64::NEG_INFINITY, f64::NEG_INFINITY),
+                (0.0, 1.0),
+                (0.0, 2.0),
+                (5.0, -1.0),
+                (5.0, 5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combined_bounds() {
+        let c = circ(pt(0.0, 0.0), 1.0).shape();
+        let r = rt(2.0, -3.0, 4.0, 0.5).shape();
+        let b = c.combined_bounds(&r).unwrap();
+        assert_eq!(b, rt(-1.0, -3.0, 4.0, 1.0));
+        assert!(b.contains_rt(&c.bounds()));
+        assert!(b.contains_rt(&r.bounds()));
+        assert_eq!(r.combined_bounds(&c), Some(b));
+        assert!(c.combined_bounds(&line(pt(0.0, 0.0), pt(1.0, 1.0)).shape()).is_none());
+    }
 }
@@ -3,14 +3,24 @@ use std::ops::Index;
 use derive_more::Display;
 
 use crate::geom::bounds::pt_cloud_bounds;
-use crate::geom::contains::tri_contains_pt;
-use crate::geom::convex::ensure_ccw;
-use crate::geom::intersects::{cap_intersects_tri, circ_intersects_tri, rt_intersects_tri};
+use crate::geom::contains::{
+    tri_contains_cap, tri_contains_circ, tri_contains_path, tri_contains_poly, tri_contains_pt,
+    tri_contains_rt, tri_contains_seg, tri_contains_tri,
+};
+use crate::geom::convex::{ensure_ccw, support_pts};
+use crate::geom::distance::{
+    cap_tri_dist, circ_tri_dist, line_shape_dist, path_tri_dist, poly_tri_dist, pt_tri_dist,
+    rt_tri_dist, seg_tri_dist, tri_tri_dist,
+};
+use crate::geom::intersects::{
+    cap_intersects_tri, circ_intersects_tri, line_intersects_tri, path_intersects_tri,
+    poly_intersects_tri, rt_intersects_tri, seg_intersects_tri, tri_intersects_tri,
+};
 use crate::primitive::point::Pt;
 use crate::primitive::rect::Rt;
 use crate::primitive::segment::Segment;
 use crate::primitive::shape::Shape;
-use crate::primitive::{ShapeOps, seg};
+use crate::primitive::{Convex, ShapeOps, seg};
 
 // Is in CCW order.
 #[must_use]
@@ -30,6 +40,11 @@ impl Tri {
         &self.pts
     }
 
+    #[must_use]
+    pub fn area(&self) -> f64 {
+        (self.pts[1] - self.pts[0]).cross(self.pts[2] - self.pts[0]) / 2.0
+    }
+
     pub fn segs(&self) -> [Segment; 3] {
         [
             seg(self.pts[0], self.pts[1]),
@@ -39,6 +54,16 @@ impl Tri {
     }
 }
 
+impl Convex for Tri {
+    fn support(&self, dir: Pt) -> Pt {
+        support_pts(self.pts(), dir)
+    }
+
+    fn center(&self) -> Pt {
+        (self[0] + self[1] + self[2]) / 3.0
+    }
+}
+
 impl ShapeOps for Tri {
     fn bounds(&self) -> Rt {
         pt_cloud_bounds(&self.pts)
@@ -52,45 +77,55 @@ impl ShapeOps for Tri {
         match s {
             Shape::Capsule(s) => cap_intersects_tri(s, self),
             Shape::Circle(s) => circ_intersects_tri(s, self),
-            Shape::Compound(_) => todo!(),
-            Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
+            Shape::Compound(s) => s.intersects_shape(&self.shape()),
+            Shape::Custom(s) => s.dyn_intersects_shape(&self.shape()),
+            Shape::Line(s) => line_intersects_tri(s, self),
+            Shape::Path(s) => path_intersects_tri(s, self),
             Shape::Point(s) => tri_contains_pt(self, s),
-            Shape::Polygon(_) => todo!(),
+            Shape::Polygon(s) => poly_intersects_tri(s, self),
             Shape::Rect(s) => rt_intersects_tri(s, self),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Segment(s) => seg_intersects_tri(s, self),
+            Shape::Tri(s) => tri_intersects_tri(self, s),
         }
     }
 
     fn contains_shape(&self, s: &Shape) -> bool {
+        if s.is_empty_set() {
+            return true;
+        }
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
-            Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
-            Shape::Polygon(_) => todo!(),
-            Shape::Rect(_) => todo!(),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Capsule(s) => tri_contains_cap(self, s),
+            Shape::Circle(s) => tri_contains_circ(self, s),
+            Shape::Compound(s) => {
+                s.quadtree().live_shapes().all(|v| self.contains_shape(v.shape()))
+            }
+            Shape::Custom(s) => s.dyn_contained_by(&self.shape()),
+            // A triangle is bounded so it can't contain an unbounded line.
+            Shape::Line(_) => false,
+            Shape::Path(s) => tri_contains_path(self, s),
+            Shape::Point(s) => tri_contains_pt(self, s),
+            Shape::Polygon(s) => tri_contains_poly(self, s),
+            Shape::Rect(s) => tri_contains_rt(self, s),
+            Shape::Segment(s) => tri_contains_seg(self, s),
+            Shape::Tri(s) => tri_contains_tri(self, s),
         }
     }
 
     fn dist_to_shape(&self, s: &Shape) -> f64 {
         match s {
-            Shape::Capsule(_) => todo!(),
-            Shape::Circle(_) => todo!(),
-            Shape::Compound(_) => todo!(),
-            Shape::Line(_) => todo!(),
-            Shape::Path(_) => todo!(),
-            Shape::Point(_) => todo!(),
-            Shape::Polygon(_) => todo!(),
-            Shape::Rect(_) => todo!(),
-            Shape::Segment(_) => todo!(),
-            Shape::Tri(_) => todo!(),
+            Shape::Capsule(s) => cap_tri_dist(s, self),
+            Shape::Circle(s) => circ_tri_dist(s, self),
+            Shape::Compound(s) => return s.dist_to_shape(&self.shape()),
+            Shape::Custom(s) => return s.dyn_dist_to_shape(&self.shape()),
+            Shape::Line(s) => line_shape_dist(s, &self.shape()),
+            Shape::Path(s) => path_tri_dist(s, self),
+            Shape::Point(s) => pt_tri_dist(s, self),
+            Shape::Polygon(s) => poly_tri_dist(s, self),
+            Shape::Rect(s) => rt_tri_dist(s, self),
+            Shape::Segment(s) => seg_tri_dist(s, self),
+            Shape::Tri(s) => tri_tri_dist(self, s),
         }
+        .unwrap_or(f64::NAN)
     }
 }
 
@@ -101,3 +136,23 @@ impl Index<usize> for Tri {
         &self.pts[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::compound::Compound;
+    use crate::primitive::{circ, pt, tri};
+
+    #[test]
+    fn test_tri_contains_compound() {
+        let t = tri(pt(0.0, 0.0), pt(10.0, 0.0), pt(0.0, 10.0));
+        let mut c = Compound::from_plain_shapes(&[
+            circ(pt(2.0, 2.0), 1.0).shape(),
+            circ(pt(50.0, 50.0), 1.0).shape(),
+        ]);
+        assert!(!t.contains_shape(&c.clone().shape()));
+        // Removed shapes no longer count.
+        c.remove_shape(1);
+        assert!(t.contains_shape(&c.shape()));
+    }
+}
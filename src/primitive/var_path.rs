@@ -0,0 +1,176 @@
+use crate::geom::bounds::rt_cloud_bounds;
+use crate::primitive::circle::Circle;
+use crate::primitive::point::Pt;
+use crate::primitive::rect::Rt;
+use crate::primitive::{ShapeOps, circ};
+
+// A capsule whose radius varies linearly from |st_r| at |st| to |en_r| at
+// |en|. This is the convex hull of the two end circles.
+#[must_use]
+#[derive(Debug, Copy, Clone)]
+pub struct TaperedCapsule {
+    st: Pt,
+    en: Pt,
+    st_r: f64,
+    en_r: f64,
+}
+
+impl TaperedCapsule {
+    pub const fn new(st: Pt, en: Pt, st_r: f64, en_r: f64) -> Self {
+        Self { st, en, st_r, en_r }
+    }
+
+    pub const fn st(&self) -> Pt {
+        self.st
+    }
+
+    pub const fn en(&self) -> Pt {
+        self.en
+    }
+
+    #[must_use]
+    pub const fn st_r(&self) -> f64 {
+        self.st_r
+    }
+
+    #[must_use]
+    pub const fn en_r(&self) -> f64 {
+        self.en_r
+    }
+
+    pub fn st_cap(&self) -> Circle {
+        circ(self.st, self.st_r)
+    }
+
+    pub fn en_cap(&self) -> Circle {
+        circ(self.en, self.en_r)
+    }
+
+    pub fn bounds(&self) -> Rt {
+        self.st_cap().bounds().united(&self.en_cap().bounds())
+    }
+
+    // Returns the distance from |p| to the tapered capsule, or zero if |p| is
+    // inside it.
+    #[must_use]
+    pub fn dist_to_pt(&self, p: Pt) -> f64 {
+        let dir = self.en - self.st;
+        let len = dir.mag();
+        let st_dist = p.dist(self.st) - self.st_r;
+        let en_dist = p.dist(self.en) - self.en_r;
+        // If one end circle contains the other, the hull is just that circle.
+        if len <= (self.st_r - self.en_r).abs() {
+            return st_dist.min(en_dist).max(0.0);
+        }
+        // Work in coordinates along and across the spine. The side walls are
+        // tangent to both circles and tilted by the change in radius.
+        let u = dir / len;
+        let along = (p - self.st).dot(u);
+        let across = u.cross(p - self.st).abs();
+        let b = (self.st_r - self.en_r) / len;
+        let a = (1.0 - b * b).sqrt();
+        let k = a * along - b * across;
+        let d = if k < 0.0 {
+            st_dist
+        } else if k > a * len {
+            en_dist
+        } else {
+            a * across + b * along - self.st_r
+        };
+        d.max(0.0)
+    }
+}
+
+// A path with a per-vertex radius, e.g. a tapering trace. Each segment is a
+// TaperedCapsule.
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct VarPath {
+    pts: Vec<Pt>,
+    radii: Vec<f64>,
+    bounds: Rt,
+}
+
+impl VarPath {
+    pub fn new(pts: &[Pt], radii: &[f64]) -> Self {
+        assert_eq!(pts.len(), radii.len(), "need one radius per vertex");
+        let bounds = rt_cloud_bounds(pts.iter().zip(radii).map(|(&p, &r)| circ(p, r).bounds()));
+        Self { pts: pts.to_vec(), radii: radii.to_vec(), bounds }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pts(&self) -> &[Pt] {
+        &self.pts
+    }
+
+    #[must_use]
+    pub fn radii(&self) -> &[f64] {
+        &self.radii
+    }
+
+    pub fn caps(&self) -> impl '_ + Iterator<Item = TaperedCapsule> {
+        self.pts
+            .array_windows::<2>()
+            .zip(self.radii.array_windows::<2>())
+            .map(|(p, r)| TaperedCapsule::new(p[0], p[1], r[0], r[1]))
+    }
+
+    pub fn bounds(&self) -> Rt {
+        self.bounds
+    }
+
+    // Returns the distance from |p| to the path, or zero if |p| is inside it.
+    #[must_use]
+    pub fn dist_to_pt(&self, p: Pt) -> f64 {
+        if self.len() == 1 {
+            return (p.dist(self.pts[0]) - self.radii[0]).max(0.0);
+        }
+        self.caps().map(|cap| cap.dist_to_pt(p)).fold(f64::MAX, f64::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::geom::math::EP;
+    use crate::primitive::{pt, rt};
+
+    #[test]
+    fn test_widening_path() {
+        let p = VarPath::new(&[pt(0.0, 0.0), pt(5.0, 0.0), pt(10.0, 0.0)], &[1.0, 1.5, 2.0]);
+        assert_eq!(p.caps().count(), 2);
+        assert_eq!(p.bounds(), rt(-1.0, -2.0, 12.0, 2.0));
+        assert!(p.bounds().contains_rt(&circ(pt(10.0, 0.0), 2.0).bounds()));
+
+        assert_relative_eq!(p.dist_to_pt(pt(5.0, 0.0)), 0.0);
+        assert_relative_eq!(p.dist_to_pt(pt(12.0, 0.0)), 0.0, epsilon = EP);
+        assert_relative_eq!(p.dist_to_pt(pt(13.0, 0.0)), 1.0, epsilon = EP);
+        assert_relative_eq!(p.dist_to_pt(pt(-3.0, 0.0)), 2.0, epsilon = EP);
+        // Inside the wide end but outside a constant radius 1 path.
+        assert_relative_eq!(p.dist_to_pt(pt(10.0, 1.9)), 0.0);
+        // The wall is tilted, so above the middle the distance is a bit less
+        // than the straight-up gap of 3 - 1.5.
+        let d = p.dist_to_pt(pt(5.0, 3.0));
+        assert!(d > 1.45 && d < 1.5, "{d}");
+    }
+
+    #[test]
+    fn test_tapered_capsule_nested() {
+        // The big end circle swallows the small one.
+        let c = TaperedCapsule::new(pt(0.0, 0.0), pt(1.0, 0.0), 3.0, 1.0);
+        assert_relative_eq!(c.dist_to_pt(pt(5.0, 0.0)), 2.0);
+        assert_relative_eq!(c.dist_to_pt(pt(1.5, 0.0)), 0.0);
+    }
+}
@@ -2,10 +2,13 @@ use std::f64::consts::PI;
 
 use auto_ops::impl_op_ex;
 use nalgebra::{Matrix3, vector};
+use serde::{Deserialize, Serialize};
 
 use crate::geom::math::eq;
+use crate::geom::qt::query::ShapeInfo;
 use crate::primitive::capsule::Capsule;
 use crate::primitive::circle::Circle;
+use crate::primitive::compound::Compound;
 use crate::primitive::line_shape::Line;
 use crate::primitive::path_shape::Path;
 use crate::primitive::point::Pt;
@@ -17,7 +20,8 @@ use crate::primitive::triangle::Tri;
 use crate::primitive::{ShapeOps, cap, circ, line, path, poly, pt, seg, tri};
 
 #[must_use]
-#[derive(Debug, Default, PartialEq, Copy, Clone)]
+#[derive(Debug, Default, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(from = "[f64; 9]", into = "[f64; 9]")]
 pub struct Tf {
     m: Matrix3<f64>,
 }
@@ -51,6 +55,40 @@ impl Tf {
         Self::translate(offset) * scale
     }
 
+    // Angle of rotation in radians, assuming no shear.
+    pub(crate) fn rotation(&self) -> f64 {
+        self.m[(1, 0)].atan2(self.m[(0, 0)])
+    }
+
+    // Interpolates from |self| at |t| = 0 to |other| at |t| = 1. Rotation is
+    // interpolated by angle, the shorter way around, and the rest linearly.
+    pub(crate) fn lerp(&self, other: &Tf, t: f64) -> Tf {
+        let a0 = self.rotation();
+        let da = (other.rotation() - a0 + PI).rem_euclid(2.0 * PI) - PI;
+        let linear = |tf: &Tf, a: f64| {
+            let mut m = Matrix3::new_rotation(-a) * tf.m;
+            m[(0, 2)] = 0.0;
+            m[(1, 2)] = 0.0;
+            m
+        };
+        let rest = linear(self, a0) * (1.0 - t) + linear(other, a0 + da) * t;
+        let mut m = Matrix3::new_rotation(a0 + da * t) * rest;
+        m[(0, 2)] = self.m[(0, 2)] * (1.0 - t) + other.m[(0, 2)] * t;
+        m[(1, 2)] = self.m[(1, 2)] * (1.0 - t) + other.m[(1, 2)] * t;
+        Tf { m }
+    }
+
+    // Creates a transform from its matrix coefficients in row-major order.
+    pub fn from_array(a: [f64; 9]) -> Self {
+        Self { m: Matrix3::from_row_slice(&a) }
+    }
+
+    // Returns the matrix coefficients in row-major order.
+    #[must_use]
+    pub fn to_array(&self) -> [f64; 9] {
+        std::array::from_fn(|i| self.m[(i / 3, i % 3)])
+    }
+
     pub fn inv(&self) -> Tf {
         Tf { m: self.m.try_inverse().unwrap() }
     }
@@ -67,8 +105,7 @@ impl Tf {
             let b = self.pt(r.tr());
             Rt::enclosing(a, b).shape()
         } else {
-            let poly = poly(&r.pts());
-            self.poly(&poly).shape()
+            poly(&r.transformed_corners(self)).shape()
         }
     }
 
@@ -105,8 +142,7 @@ impl Tf {
     }
 
     pub fn poly(&self, p: &Poly) -> Poly {
-        let pts = p.pts().iter().map(|&v| self.pt(v)).collect::<Vec<_>>();
-        poly(&pts)
+        p.map_pts(&|v| self.pt(v))
     }
 
     pub fn seg(&self, s: &Segment) -> Segment {
@@ -122,7 +158,15 @@ impl Tf {
         match s {
             Shape::Capsule(s) => self.cap(s).shape(),
             Shape::Circle(s) => self.circ(s).shape(),
-            Shape::Compound(_) => todo!(),
+            Shape::Compound(s) => {
+                let c = Compound::empty();
+                for v in s.quadtree().live_shapes() {
+                    // Empty compounds rebuild their bounds, so this can't fail.
+                    c.add_shape(ShapeInfo::new(self.shape(v.shape()), v.tag(), v.kinds())).unwrap();
+                }
+                c.shape()
+            }
+            Shape::Custom(s) => s.dyn_map_points(&|v| self.pt(v)),
             Shape::Line(s) => self.line(s).shape(),
             Shape::Path(s) => self.path(s).shape(),
             Shape::Point(s) => self.pt(*s).shape(),
@@ -137,7 +181,63 @@ impl Tf {
     pub fn pts(&self, p: &[Pt]) -> Vec<Pt> {
         p.iter().map(|&v| self.pt(v)).collect()
     }
+
+    // Like pts, but overwrites |p| rather than allocating.
+    pub fn transform_in_place(&self, p: &mut [Pt]) {
+        for v in p {
+            *v = self.pt(*v);
+        }
+    }
+}
+
+impl From<[f64; 9]> for Tf {
+    fn from(a: [f64; 9]) -> Self {
+        Self::from_array(a)
+    }
+}
+
+impl From<Tf> for [f64; 9] {
+    fn from(tf: Tf) -> Self {
+        tf.to_array()
+    }
 }
 
 impl_op_ex!(*|a: &Tf, b: &Tf| -> Tf { Tf { m: a.m * b.m } });
 impl_op_ex!(*= |a: &mut Tf, b: &Tf| { a.m *= b.m });
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_transform_in_place() {
+        let tf = Tf::translate(pt(1.0, 2.0)) * Tf::rotate(30.0) * Tf::scale(pt(2.0, 3.0));
+        let pts = [pt(0.0, 0.0), pt(1.0, -2.0), pt(3.5, 4.0)];
+        let mut buf = pts;
+        tf.transform_in_place(&mut buf);
+        assert_eq!(buf.len(), pts.len());
+        assert_eq!(buf.to_vec(), tf.pts(&pts));
+    }
+
+    #[test]
+    fn test_array_round_trip() {
+        let tf = Tf::translate(pt(3.0, -2.0)) * Tf::rotate(30.0) * Tf::scale(pt(2.0, 2.0));
+        let a = tf.to_array();
+        assert_eq!(Tf::from_array(a), tf);
+        for p in [pt(0.0, 0.0), pt(1.0, 0.0), pt(-4.5, 2.25)] {
+            assert_relative_eq!(Tf::from_array(a).pt(p), tf.pt(p));
+        }
+    }
+
+    #[test]
+    fn test_to_array_row_major() {
+        let a = Tf::translate(pt(3.0, -2.0)).to_array();
+        assert_relative_eq!(
+            a.as_slice(),
+            [1.0, 0.0, 3.0, 0.0, 1.0, -2.0, 0.0, 0.0, 1.0].as_slice()
+        );
+    }
+}